@@ -12,7 +12,7 @@ fuzz_target!(|data: &[u8]| {
         }
         let mut failed = false;
         for hash in farc.iter_all_hash() {
-            if farc.get_hashed_file(*hash).is_err() {
+            if farc.get_hashed_file(hash).is_err() {
                 failed = true;
             };
         };
@@ -0,0 +1,16 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use pmd_farc::{Farc, FarcWriter};
+use std::io::{Cursor, Read};
+
+fuzz_target!(|writer: FarcWriter| {
+    let mut archive = Cursor::new(Vec::new());
+    writer.write_hashed(&mut archive).unwrap();
+    let farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+    assert_eq!(farc.file_count(), writer.file_count());
+    for hash in farc.iter_all_hash() {
+        assert!(writer.contains_hash(hash));
+        let mut content = Vec::new();
+        farc.get_hashed_file(hash).unwrap().read_to_end(&mut content).unwrap();
+    }
+});
@@ -0,0 +1,113 @@
+use crate::{Farc, FarcError, MergedFarc};
+use io_partition::PartitionMutex;
+use std::error::Error;
+use std::io::{self, Read, Seek};
+
+/// A file listed by [`ArchiveRead::entries`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArchiveEntry<'a> {
+    /// The hash of the name of the file (see [`hash_name`](crate::hash_name))
+    pub hash: u32,
+    /// The name of the file, if known
+    pub name: Option<&'a str>,
+    /// The length of the file, in bytes
+    pub length: u64,
+}
+
+/// The read access to an archive, implemented by [`Farc`] and [`MergedFarc`]. Tools can be written against this
+/// trait rather than a concrete type, so they can be reused for other pack formats.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, ArchiveRead, Farc, FarcWriter};
+/// use std::io::Cursor;
+///
+/// fn total_length<A: ArchiveRead>(archive: &A) -> u64 {
+///     archive.entries().map(|entry| entry.length).sum()
+/// }
+///
+/// let mut writer = FarcWriter::default();
+/// writer.add_hashed_file(hash_name("a.bin"), b"hello".to_vec());
+/// let mut archive = Cursor::new(Vec::new());
+/// writer.write_hashed(&mut archive).unwrap();
+/// let mut farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+/// farc.check_file_name("a.bin");
+///
+/// assert_eq!(total_length(&farc), 16);
+/// assert_eq!(farc.entries().next().unwrap().name, Some("a.bin"));
+/// assert!(farc.read_named("a.bin").unwrap().starts_with(b"hello"));
+/// ```
+pub trait ArchiveRead {
+    /// A handle to a file of the archive
+    type File: Read;
+    /// The error returned when a file can't be opened
+    type Error: Error + From<io::Error>;
+
+    /// Iterate over the files of the archive
+    fn entries(&self) -> Box<dyn Iterator<Item = ArchiveEntry<'_>> + '_>;
+
+    /// Open the file with this hash
+    fn open_hashed(&self, hash: u32) -> Result<Self::File, Self::Error>;
+
+    /// Open the file with this name
+    fn open_named(&self, name: &str) -> Result<Self::File, Self::Error>;
+
+    /// Read the whole content of the file with this hash
+    fn read_hashed(&self, hash: u32) -> Result<Vec<u8>, Self::Error> {
+        let mut content = Vec::new();
+        self.open_hashed(hash)?.read_to_end(&mut content)?;
+        Ok(content)
+    }
+
+    /// Read the whole content of the file with this name
+    fn read_named(&self, name: &str) -> Result<Vec<u8>, Self::Error> {
+        let mut content = Vec::new();
+        self.open_named(name)?.read_to_end(&mut content)?;
+        Ok(content)
+    }
+}
+
+impl<F: Read + Seek> ArchiveRead for Farc<F> {
+    type File = PartitionMutex<F>;
+    type Error = FarcError;
+
+    fn entries(&self) -> Box<dyn Iterator<Item = ArchiveEntry<'_>> + '_> {
+        Box::new(self.iter_files().map(|file| ArchiveEntry {
+            hash: file.name_hash,
+            name: file.name.as_deref(),
+            length: u64::from(file.length),
+        }))
+    }
+
+    fn open_hashed(&self, hash: u32) -> Result<Self::File, Self::Error> {
+        self.get_hashed_file(hash)
+    }
+
+    fn open_named(&self, name: &str) -> Result<Self::File, Self::Error> {
+        self.get_named_file(name)
+    }
+}
+
+impl<F: Read + Seek> ArchiveRead for MergedFarc<F> {
+    type File = PartitionMutex<F>;
+    type Error = FarcError;
+
+    fn entries(&self) -> Box<dyn Iterator<Item = ArchiveEntry<'_>> + '_> {
+        Box::new(self.iter().filter_map(move |(hash, name, layer)| {
+            let file = self.layers()[layer].index.get_file_by_hash(hash)?;
+            Some(ArchiveEntry {
+                hash,
+                name,
+                length: u64::from(file.length),
+            })
+        }))
+    }
+
+    fn open_hashed(&self, hash: u32) -> Result<Self::File, Self::Error> {
+        self.get_hashed_file(hash)
+    }
+
+    fn open_named(&self, name: &str) -> Result<Self::File, Self::Error> {
+        self.get_named_file(name)
+    }
+}
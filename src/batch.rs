@@ -0,0 +1,190 @@
+use crate::romfs::{is_farc, open_archive};
+use crate::{Farc, RomFsError};
+use std::fs::{read_dir, File};
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use thiserror::Error;
+
+/// An error that happened while processing an archive of a [`Batch`]
+#[derive(Debug, Error)]
+pub enum BatchError<E: std::error::Error + 'static> {
+    /// The archive can't be opened, or the names of its files can't be found
+    #[error("can't open the archive")]
+    OpenError(#[from] RomFsError),
+    /// The processing function returned an error
+    #[error("can't process the archive")]
+    ProcessError(#[source] E),
+}
+
+/// The result of each archive processed by a [`Batch`], in the same order as [`Batch::archives`]
+#[derive(Debug)]
+pub struct BatchReport<T, E: std::error::Error + 'static> {
+    /// The path of each archive, with its result
+    pub results: Vec<(PathBuf, Result<T, BatchError<E>>)>,
+}
+
+impl<T, E: std::error::Error + 'static> BatchReport<T, E> {
+    /// Iterate over the archives that were successfully processed
+    pub fn successes(&self) -> impl Iterator<Item = (&Path, &T)> {
+        self.results
+            .iter()
+            .filter_map(|(path, result)| result.as_ref().ok().map(|value| (path.as_path(), value)))
+    }
+
+    /// Iterate over the archives that couldn't be opened or processed
+    pub fn failures(&self) -> impl Iterator<Item = (&Path, &BatchError<E>)> {
+        self.results
+            .iter()
+            .filter_map(|(path, result)| result.as_ref().err().map(|err| (path.as_path(), err)))
+    }
+
+    /// Return true if every archive was successfully processed
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.results.iter().all(|(_, result)| result.is_ok())
+    }
+}
+
+/// Open and process many archives concurrently, with a bounded number of worker threads, like every archive of a
+/// romfs. An error with an archive doesn't stop the processing of the others.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, Batch, FarcWriter};
+/// let root = std::env::temp_dir().join(format!("pmd_farc_batch_doctest_{}", std::process::id()));
+/// std::fs::create_dir_all(&root).unwrap();
+/// for count in 1..=3 {
+///     let mut writer = FarcWriter::default();
+///     for index in 0..count {
+///         writer.add_hashed_file(hash_name(&format!("{}.bin", index)), Vec::new());
+///     }
+///     let path = root.join(format!("archive_{}.bin", count));
+///     writer.write_hashed(&mut std::fs::File::create(path).unwrap()).unwrap();
+/// }
+/// std::fs::write(root.join("not_an_archive.txt"), "hello").unwrap();
+///
+/// let mut batch = Batch::find(&root).unwrap();
+/// batch.set_workers(2);
+/// let report = batch.run(|_path, farc| Ok::<_, std::io::Error>(farc.file_count()));
+/// assert!(report.is_ok());
+/// assert_eq!(report.successes().map(|(_, count)| *count).collect::<Vec<_>>(), vec![1, 2, 3]);
+/// # std::fs::remove_dir_all(&root).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Batch {
+    archives: Vec<PathBuf>,
+    workers: usize,
+    find_names: bool,
+}
+
+impl Batch {
+    /// Create a batch processing these archives. By default, there is one worker per available CPU, and the names of
+    /// the files are found like [`RomFs`](crate::RomFs) does.
+    pub fn new<P: Into<PathBuf>, I: IntoIterator<Item = P>>(archives: I) -> Self {
+        Self {
+            archives: archives.into_iter().map(Into::into).collect(),
+            workers: thread::available_parallelism().map_or(1, |count| count.get()),
+            find_names: true,
+        }
+    }
+
+    /// Create a batch processing every archive under ``root``, found by their magic in every sub-directory, in
+    /// alphabetical order
+    pub fn find<P: AsRef<Path>>(root: P) -> Result<Self, RomFsError> {
+        let mut archives = Vec::new();
+        find_archives(root.as_ref(), &mut archives)?;
+        archives.sort();
+        Ok(Self::new(archives))
+    }
+
+    /// Return the archives to process
+    #[must_use]
+    pub fn archives(&self) -> &[PathBuf] {
+        &self.archives
+    }
+
+    /// Set the maximum number of archives processed at the same time (at least 1)
+    pub fn set_workers(&mut self, workers: usize) {
+        self.workers = workers.max(1);
+    }
+
+    /// Set whether the names of the files are found before the archive is processed. Default to true.
+    pub fn set_find_names(&mut self, find_names: bool) {
+        self.find_names = find_names;
+    }
+
+    /// Open each archive and give it to ``process``, with its path
+    pub fn run<T, E, P>(&self, process: P) -> BatchReport<T, E>
+    where
+        T: Send,
+        E: std::error::Error + Send + 'static,
+        P: Fn(&Path, Farc<BufReader<File>>) -> Result<T, E> + Sync,
+    {
+        let next = AtomicUsize::new(0);
+        let worker = || {
+            let mut results = Vec::new();
+            loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let path = match self.archives.get(index) {
+                    Some(path) => path,
+                    None => break,
+                };
+                let result = self
+                    .open(path)
+                    .map_err(BatchError::OpenError)
+                    .and_then(|farc| process(path, farc).map_err(BatchError::ProcessError));
+                results.push((index, result));
+            }
+            results
+        };
+        let mut results = thread::scope(|scope| {
+            let handles = (0..self.workers.min(self.archives.len()))
+                .map(|_| scope.spawn(worker))
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .flat_map(|handle| match handle.join() {
+                    Ok(results) => results,
+                    Err(panic) => std::panic::resume_unwind(panic),
+                })
+                .collect::<Vec<_>>()
+        });
+        results.sort_by_key(|(index, _)| *index);
+        BatchReport {
+            results: results
+                .into_iter()
+                .map(|(index, result)| (self.archives[index].clone(), result))
+                .collect(),
+        }
+    }
+
+    fn open(&self, path: &Path) -> Result<Farc<BufReader<File>>, RomFsError> {
+        if self.find_names {
+            let file_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            open_archive(path, &file_name)
+        } else {
+            let file =
+                File::open(path).map_err(|err| RomFsError::IOError(path.to_path_buf(), err))?;
+            Farc::new(BufReader::new(file))
+                .map_err(|err| RomFsError::FarcError(path.to_path_buf(), err))
+        }
+    }
+}
+
+fn find_archives(directory: &Path, archives: &mut Vec<PathBuf>) -> Result<(), RomFsError> {
+    let io_error = |path: &Path, error: io::Error| RomFsError::IOError(path.to_path_buf(), error);
+    for entry in read_dir(directory).map_err(|err| io_error(directory, err))? {
+        let path = entry.map_err(|err| io_error(directory, err))?.path();
+        if path.is_dir() {
+            find_archives(&path, archives)?;
+        } else if is_farc(&path).map_err(|err| io_error(&path, err))? {
+            archives.push(path);
+        };
+    }
+    Ok(())
+}
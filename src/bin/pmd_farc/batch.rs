@@ -0,0 +1,185 @@
+use crate::common::{open_farc, parse_hash, placeholder_name, safe_name, sorted_entries};
+use crate::dehash::add_builtin_dehashers;
+use crate::extract::extract_farc;
+use anyhow::{bail, Context, Result};
+use clap::{Args, Subcommand};
+use pmd_farc::{hash_name, search_hash, validate_farc, CasExporter, DehashPipeline};
+use std::fs::{read_dir, File};
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+#[derive(Args)]
+pub struct BatchArgs {
+    /// The directory to search FARC archives in, recursively (usually the romfs of the game)
+    romfs: PathBuf,
+    #[command(subcommand)]
+    command: BatchCommand,
+}
+
+#[derive(Subcommand)]
+enum BatchCommand {
+    /// List the files of every archive, with a path like archive.bin/file
+    List,
+    /// Extract every archive to a directory named after it, in the output directory
+    Extract {
+        /// The directory to extract the archives to
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Check the structure of every archive
+    Verify,
+    /// Print the archives containing a file, with the name it has in each of them
+    Find {
+        /// The name of the file, or its hash starting with 0x
+        query: String,
+    },
+    /// Export every archive to a content-addressable store, where identical files are only stored once
+    Cas {
+        /// The directory of the store. It can already contain the archives of another version of the game.
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Put the index of the archives in this sub-directory of the indexes of the store, like a version name
+        #[arg(short, long)]
+        label: Option<String>,
+    },
+}
+
+/// Return the path of every file starting with the FARC magic in this directory and its sub-directories, sorted
+fn find_archives(directory: &Path, result: &mut Vec<PathBuf>) -> Result<()> {
+    let mut entries = read_dir(directory)
+        .with_context(|| format!("can't read {:?}", directory))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            find_archives(&path, result)?;
+        } else {
+            let mut magic = [0; 4];
+            let mut file = File::open(&path).with_context(|| format!("can't open {:?}", path))?;
+            if file.read_exact(&mut magic).is_ok() && &magic == b"FARC" {
+                result.push(path);
+            };
+        };
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct BatchReport {
+    archives: usize,
+    files: usize,
+    named: usize,
+    failed: Vec<(PathBuf, String)>,
+}
+
+/// Run the command on one archive, updating the report
+fn run_one(
+    command: &BatchCommand,
+    romfs: &Path,
+    path: &Path,
+    report: &mut BatchReport,
+    cas: Option<&mut CasExporter>,
+) -> Result<()> {
+    let relative = path.strip_prefix(romfs).unwrap_or(path);
+    if let BatchCommand::Verify = command {
+        let file = File::open(path).with_context(|| format!("can't open {:?}", path))?;
+        let problems = validate_farc(BufReader::new(file))?;
+        if !problems.is_empty() {
+            for problem in &problems {
+                println!("{}: {}", relative.display(), problem);
+            }
+            bail!("{} problems", problems.len());
+        };
+    };
+
+    let mut farc = open_farc(path)?;
+    let mut pipeline = DehashPipeline::default();
+    add_builtin_dehashers(&mut pipeline, path);
+    pipeline.run(&mut farc)?;
+    report.files += farc.file_count();
+    report.named += farc.file_known_name();
+
+    match command {
+        BatchCommand::List => {
+            for entry in sorted_entries(&farc) {
+                println!(
+                    "{:08x} {}/{}",
+                    entry.name_hash,
+                    relative.display(),
+                    safe_name(entry.name_hash, entry.name())
+                );
+            }
+        }
+        BatchCommand::Extract { output } => {
+            extract_farc(&farc, &output.join(relative), false, &|_, _| true)?;
+        }
+        BatchCommand::Verify => println!("{}: ok", relative.display()),
+        BatchCommand::Find { query } => {
+            let hash = if query.starts_with("0x") {
+                parse_hash(query)?
+            } else {
+                hash_name(query)
+            };
+            let relative = relative.to_string_lossy();
+            let search = search_hash(std::iter::once((relative.as_ref(), &farc)), hash);
+            for location in &search.locations {
+                println!(
+                    "{}/{}",
+                    location.archive,
+                    location
+                        .name
+                        .map_or_else(|| placeholder_name(hash), str::to_string)
+                );
+            }
+        }
+        BatchCommand::Cas { label, .. } => {
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            let name = match label {
+                Some(label) => format!("{}/{}", label, relative),
+                None => relative,
+            };
+            if let Some(cas) = cas {
+                cas.export(&name, &farc)?;
+            };
+        }
+    };
+    Ok(())
+}
+
+pub fn run(args: BatchArgs) -> Result<()> {
+    let mut archives = Vec::new();
+    find_archives(&args.romfs, &mut archives)?;
+    let mut report = BatchReport::default();
+    let mut cas = match &args.command {
+        BatchCommand::Cas { output, .. } => Some(
+            CasExporter::new(output)
+                .with_context(|| format!("can't create the store {:?}", output))?,
+        ),
+        _ => None,
+    };
+    for path in &archives {
+        report.archives += 1;
+        if let Err(err) = run_one(&args.command, &args.romfs, path, &mut report, cas.as_mut()) {
+            report.failed.push((path.clone(), format!("{:#}", err)));
+        };
+    }
+
+    eprintln!("archives: {}", report.archives);
+    eprintln!("files: {} ({} named)", report.files, report.named);
+    if let Some(cas) = &cas {
+        let stats = cas.stats();
+        eprintln!(
+            "stored: {} new objects, {} bytes written, {} bytes deduplicated",
+            stats.objects_written, stats.bytes_written, stats.bytes_deduplicated
+        );
+    };
+    if report.failed.is_empty() {
+        return Ok(());
+    };
+    eprintln!("failed:");
+    for (path, err) in &report.failed {
+        eprintln!("  {}: {}", path.display(), err);
+    }
+    bail!("{} archives failed", report.failed.len());
+}
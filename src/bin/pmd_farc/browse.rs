@@ -0,0 +1,312 @@
+use crate::common::{open_farc, placeholder_name, read_subfile};
+use crate::extract::output_name;
+use anyhow::{Context, Result};
+use clap::Args;
+use pmd_farc::Farc;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::{BTreeSet, HashSet};
+use std::fs::{create_dir_all, write};
+use std::io::{Read, Seek};
+use std::path::PathBuf;
+
+/// The maximum number of bytes shown in the preview
+const PREVIEW_LENGTH: usize = 0x4000;
+
+#[derive(Args)]
+pub struct BrowseArgs {
+    /// The archive to browse
+    archive: PathBuf,
+    /// The directory the selected files are extracted to
+    #[arg(short, long, default_value = ".")]
+    output: PathBuf,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PreviewMode {
+    Hex,
+    Strings,
+}
+
+struct Browser<F: Read + Seek> {
+    farc: Farc<F>,
+    output: PathBuf,
+    /// The hash and displayed name of every file, sorted by name
+    entries: Vec<(u32, Option<String>)>,
+    /// The index in ``entries`` of the files matching the search
+    visible: Vec<usize>,
+    list_state: ListState,
+    marked: BTreeSet<u32>,
+    search: String,
+    searching: bool,
+    preview_mode: PreviewMode,
+    /// The hash and the preview of the selected file
+    preview: Option<(u32, Vec<u8>)>,
+    status: String,
+}
+
+impl<F: Read + Seek> Browser<F> {
+    fn new(farc: Farc<F>, output: PathBuf) -> Self {
+        let mut entries = farc
+            .iter()
+            .map(|(hash, name)| (hash, name.map(str::to_string)))
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| (a.1.is_none(), &a.1, a.0).cmp(&(b.1.is_none(), &b.1, b.0)));
+        let mut browser = Self {
+            farc,
+            output,
+            entries,
+            visible: Vec::new(),
+            list_state: ListState::default(),
+            marked: BTreeSet::new(),
+            search: String::new(),
+            searching: false,
+            preview_mode: PreviewMode::Hex,
+            preview: None,
+            status: "q: quit  /: search  tab: hex/strings  space: mark  x: extract".to_string(),
+        };
+        browser.update_search();
+        browser
+    }
+
+    fn display_name(hash: u32, name: &Option<String>) -> String {
+        name.clone().unwrap_or_else(|| placeholder_name(hash))
+    }
+
+    fn update_search(&mut self) {
+        let search = self.search.to_lowercase();
+        self.visible = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, (hash, name))| {
+                Self::display_name(*hash, name)
+                    .to_lowercase()
+                    .contains(&search)
+            })
+            .map(|(index, _)| index)
+            .collect();
+        self.list_state.select(if self.visible.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+    }
+
+    fn selected_hash(&self) -> Option<u32> {
+        self.list_state
+            .selected()
+            .and_then(|selected| self.visible.get(selected))
+            .map(|index| self.entries[*index].0)
+    }
+
+    fn update_preview(&mut self) {
+        let hash = match self.selected_hash() {
+            Some(hash) => hash,
+            None => {
+                self.preview = None;
+                return;
+            }
+        };
+        if self.preview.as_ref().map(|(preview_hash, _)| *preview_hash) == Some(hash) {
+            return;
+        };
+        let mut content = Vec::new();
+        let result = self.farc.get_hashed_file(hash).map(|subfile| {
+            subfile
+                .take(PREVIEW_LENGTH as u64)
+                .read_to_end(&mut content)
+        });
+        if !matches!(result, Ok(Ok(_))) {
+            self.status = format!("can't read the file {:08x}", hash);
+        };
+        self.preview = Some((hash, content));
+    }
+
+    fn extract(&mut self) -> Result<()> {
+        let hashes = if self.marked.is_empty() {
+            self.selected_hash().into_iter().collect::<Vec<_>>()
+        } else {
+            self.marked.iter().copied().collect()
+        };
+        create_dir_all(&self.output)
+            .with_context(|| format!("can't create the directory {:?}", self.output))?;
+        let mut used = HashSet::new();
+        for hash in &hashes {
+            let name = self
+                .entries
+                .iter()
+                .find(|(entry_hash, _)| entry_hash == hash)
+                .and_then(|(_, name)| name.as_deref());
+            let path = self.output.join(output_name(*hash, name, &mut used));
+            write(&path, read_subfile(&self.farc, *hash)?)
+                .with_context(|| format!("can't write {:?}", path))?;
+        }
+        self.status = format!(
+            "extracted {} files to {}",
+            hashes.len(),
+            self.output.display()
+        );
+        self.marked.clear();
+        Ok(())
+    }
+
+    /// Handle a key press. Return false if the browser should be closed.
+    fn handle_key(&mut self, code: KeyCode) -> bool {
+        if self.searching {
+            match code {
+                KeyCode::Enter | KeyCode::Esc => self.searching = false,
+                KeyCode::Backspace => {
+                    self.search.pop();
+                    self.update_search();
+                }
+                KeyCode::Char(chara) => {
+                    self.search.push(chara);
+                    self.update_search();
+                }
+                _ => (),
+            };
+            return true;
+        };
+        let last = self.visible.len().saturating_sub(1);
+        let selected = self.list_state.selected().unwrap_or(0);
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.list_state.select(Some((selected + 1).min(last)))
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.list_state.select(Some(selected.saturating_sub(1)))
+            }
+            KeyCode::PageDown => self.list_state.select(Some((selected + 20).min(last))),
+            KeyCode::PageUp => self.list_state.select(Some(selected.saturating_sub(20))),
+            KeyCode::Char('/') => self.searching = true,
+            KeyCode::Tab => {
+                self.preview_mode = match self.preview_mode {
+                    PreviewMode::Hex => PreviewMode::Strings,
+                    PreviewMode::Strings => PreviewMode::Hex,
+                }
+            }
+            KeyCode::Char(' ') => {
+                if let Some(hash) = self.selected_hash() {
+                    if !self.marked.remove(&hash) {
+                        self.marked.insert(hash);
+                    };
+                    self.list_state.select(Some((selected + 1).min(last)));
+                };
+            }
+            KeyCode::Char('x') => {
+                if let Err(err) = self.extract() {
+                    self.status = format!("{:#}", err);
+                };
+            }
+            _ => (),
+        };
+        true
+    }
+
+    fn preview_lines(&self) -> Vec<Line<'static>> {
+        let content = match &self.preview {
+            Some((_, content)) => content,
+            None => return Vec::new(),
+        };
+        match self.preview_mode {
+            PreviewMode::Hex => content
+                .chunks(16)
+                .enumerate()
+                .map(|(line, chunk)| {
+                    let hex = chunk
+                        .iter()
+                        .map(|byte| format!("{:02x}", byte))
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    let ascii = chunk
+                        .iter()
+                        .map(|byte| {
+                            if byte.is_ascii_graphic() || *byte == b' ' {
+                                *byte as char
+                            } else {
+                                '.'
+                            }
+                        })
+                        .collect::<String>();
+                    Line::from(format!("{:08x}  {:<47}  {}", line * 16, hex, ascii))
+                })
+                .collect(),
+            PreviewMode::Strings => content
+                .split(|byte| !(byte.is_ascii_graphic() || *byte == b' '))
+                .filter(|string| string.len() >= 4)
+                .map(|string| Line::from(String::from_utf8_lossy(string).into_owned()))
+                .collect(),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main_area, status_area] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [list_area, preview_area] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(main_area);
+
+        let items = self
+            .visible
+            .iter()
+            .map(|index| {
+                let (hash, name) = &self.entries[*index];
+                let mark = if self.marked.contains(hash) { "*" } else { " " };
+                ListItem::new(format!("{} {}", mark, Self::display_name(*hash, name)))
+            })
+            .collect::<Vec<_>>();
+        let title = if self.search.is_empty() && !self.searching {
+            format!(" {} files ", self.visible.len())
+        } else {
+            format!(" {} files matching {:?} ", self.visible.len(), self.search)
+        };
+        let list = List::new(items)
+            .block(Block::bordered().title(title))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, list_area, &mut self.list_state);
+
+        let preview_title = match (self.selected_hash(), self.preview_mode) {
+            (Some(hash), PreviewMode::Hex) => format!(" {:08x} (hex) ", hash),
+            (Some(hash), PreviewMode::Strings) => format!(" {:08x} (strings) ", hash),
+            (None, _) => String::new(),
+        };
+        frame.render_widget(
+            Paragraph::new(self.preview_lines()).block(Block::bordered().title(preview_title)),
+            preview_area,
+        );
+
+        let status = if self.searching {
+            format!("search: {}", self.search)
+        } else {
+            self.status.clone()
+        };
+        frame.render_widget(Paragraph::new(status), status_area);
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            self.update_preview();
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.handle_key(key.code) {
+                    return Ok(());
+                };
+            };
+        }
+    }
+}
+
+pub fn run(args: BrowseArgs) -> Result<()> {
+    let farc = open_farc(&args.archive)?;
+    let mut browser = Browser::new(farc, args.output);
+    let mut terminal = ratatui::init();
+    let result = browser.run(&mut terminal);
+    ratatui::restore();
+    result
+}
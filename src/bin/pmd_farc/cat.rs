@@ -0,0 +1,25 @@
+use crate::common::{open_farc, parse_entry};
+use anyhow::{Context, Result};
+use clap::Args;
+use std::io::{copy, stdout, Write};
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct CatArgs {
+    /// The archive containing the file
+    archive: PathBuf,
+    /// The name (or hash, starting with 0x) of the file to print
+    entry: String,
+}
+
+pub fn run(args: CatArgs) -> Result<()> {
+    let farc = open_farc(&args.archive)?;
+    let hash = parse_entry(&args.entry);
+    let mut subfile = farc
+        .get_hashed_file(hash)
+        .with_context(|| format!("can't find {} ({:08x})", args.entry, hash))?;
+    let mut output = stdout().lock();
+    copy(&mut subfile, &mut output)?;
+    output.flush()?;
+    Ok(())
+}
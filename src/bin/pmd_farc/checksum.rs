@@ -0,0 +1,93 @@
+use crate::common::{open_farc, read_subfile, sorted_entries};
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+#[derive(Args)]
+pub struct ChecksumArgs {
+    /// The archive to compute the checksums of
+    archive: PathBuf,
+}
+
+#[derive(Args)]
+pub struct CheckArgs {
+    /// The archive to check
+    archive: PathBuf,
+    /// The checksums, as written by the checksum command
+    checksums: PathBuf,
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Return the sha256 of each file of the archive, with the hash as key
+fn compute_checksums(archive: &Path) -> Result<Map<String, Value>> {
+    let farc = open_farc(archive)?;
+    let mut files = Map::new();
+    for entry in sorted_entries(&farc) {
+        let mut file = json!({ "sha256": sha256_hex(&read_subfile(&farc, entry.name_hash)?) });
+        if let Some(name) = entry.name() {
+            file["name"] = name.into();
+        };
+        files.insert(format!("0x{:08x}", entry.name_hash), file);
+    }
+    Ok(files)
+}
+
+pub fn run_checksum(args: ChecksumArgs) -> Result<()> {
+    let checksums = json!({
+        "algorithm": "sha256",
+        "files": compute_checksums(&args.archive)?,
+    });
+    println!("{}", serde_json::to_string_pretty(&checksums)?);
+    Ok(())
+}
+
+pub fn run_check(args: CheckArgs) -> Result<()> {
+    let expected: Value = serde_json::from_str(
+        &read_to_string(&args.checksums)
+            .with_context(|| format!("can't read {:?}", args.checksums))?,
+    )
+    .with_context(|| format!("can't parse {:?}", args.checksums))?;
+    if expected["algorithm"] != "sha256" {
+        bail!("only sha256 checksums are supported");
+    };
+    let expected = expected["files"]
+        .as_object()
+        .context("the checksums doesn't contain any file list")?;
+    let actual = compute_checksums(&args.archive)?;
+
+    let mut problems = 0;
+    for (hash, file) in expected {
+        let label = match file["name"].as_str() {
+            Some(name) => format!("{} ({})", hash, name),
+            None => hash.clone(),
+        };
+        match actual.get(hash) {
+            None => println!("{}: missing", label),
+            Some(actual_file) if actual_file["sha256"] != file["sha256"] => {
+                println!("{}: modified", label)
+            }
+            Some(_) => continue,
+        };
+        problems += 1;
+    }
+    for hash in actual.keys() {
+        if !expected.contains_key(hash) {
+            println!("{}: unexpected", hash);
+            problems += 1;
+        };
+    }
+    if problems > 0 {
+        bail!("{} files doesn't match the checksums", problems);
+    };
+    println!("{}: ok", args.archive.display());
+    Ok(())
+}
@@ -0,0 +1,196 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use pmd_farc::{hash_name, message_dehash, Farc, FarcFile, FileHashType};
+use serde_json::{json, Value};
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Seek};
+use std::path::Path;
+
+/// Open an archive. If it is a message archive with an adjacent ``.lst`` file, it is used to find the file names.
+pub fn open_farc(path: &Path) -> Result<Farc<BufReader<File>>> {
+    let file = File::open(path).with_context(|| format!("can't open {:?}", path))?;
+    let mut farc = Farc::new(BufReader::new(file))
+        .with_context(|| format!("can't parse the archive {:?}", path))?;
+
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("");
+    if FileHashType::predict_from_file_name(file_name) == Some(FileHashType::Message) {
+        if let Some(list_name) = message_dehash::get_file_name(file_name) {
+            let list_path = path.with_file_name(list_name);
+            if list_path.is_file() {
+                let mut list_file = File::open(&list_path)
+                    .with_context(|| format!("can't open {:?}", list_path))?;
+                message_dehash::try_possible_name(&mut farc, &mut list_file)
+                    .with_context(|| format!("can't read {:?}", list_path))?;
+            };
+        };
+    };
+    Ok(farc)
+}
+
+/// The file name used for a file whose name is unknown
+pub fn placeholder_name(hash: u32) -> String {
+    format!("0x{:08x}.bin", hash)
+}
+
+/// Return the name of a file, or its placeholder if it is unknown or can't be used as a file name (because it would
+/// escape the directory it is written to)
+pub fn safe_name(hash: u32, name: Option<&str>) -> String {
+    match name {
+        Some(name) if !name.contains(['/', '\\']) && name != "." && name != ".." => {
+            name.to_string()
+        }
+        _ => placeholder_name(hash),
+    }
+}
+
+/// Return the hash of a name created by [`placeholder_name`]
+pub fn parse_placeholder_name(file_name: &str) -> Option<u32> {
+    let hex = file_name.strip_prefix("0x")?.strip_suffix(".bin")?;
+    if hex.len() != 8 {
+        return None;
+    };
+    u32::from_str_radix(hex, 16).ok()
+}
+
+/// Parse an hexadecimal hash, with or without the ``0x`` prefix
+pub fn parse_hash(text: &str) -> Result<u32> {
+    let hex = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+    u32::from_str_radix(hex, 16).with_context(|| format!("{:?} isn't an hexadecimal hash", text))
+}
+
+/// Return the hash designated by a command line argument: either an hexadecimal hash starting with ``0x``, a
+/// placeholder name (see [`placeholder_name`]), or a file name to hash
+pub fn parse_entry(text: &str) -> u32 {
+    parse_placeholder_name(text)
+        .or_else(|| {
+            if text.starts_with("0x") || text.starts_with("0X") {
+                parse_hash(text).ok()
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| hash_name(text))
+}
+
+/// Read the whole content of a subfile
+pub fn read_subfile<F: Read + Seek>(farc: &Farc<F>, hash: u32) -> Result<Vec<u8>> {
+    let mut content = Vec::new();
+    farc.get_hashed_file(hash)?
+        .read_to_end(&mut content)
+        .with_context(|| format!("can't read the file {:08x}", hash))?;
+    Ok(content)
+}
+
+/// Return true if the file is itself a FARC archive
+fn is_nested_farc<F: Read + Seek>(farc: &Farc<F>, hash: u32) -> Result<bool> {
+    let mut magic = [0; 4];
+    let mut subfile = farc.get_hashed_file(hash)?;
+    Ok(subfile.read_exact(&mut magic).is_ok() && &magic == b"FARC")
+}
+
+/// A function reading the content of a file, given to the visitor of [`walk_farc`]
+pub type ContentReader<'a> = &'a dyn Fn() -> Result<Vec<u8>>;
+
+/// Call ``visit`` for every file of the archive, sorted by hash, with the path-like prefix of the archive (empty, or
+/// ending with ``/``), whether this archive stores the file names, the entry, and a function reading its content.
+///
+/// If ``recursive`` is true, the files that are themselves FARC archives are descended into instead of being
+/// visited, with their name (see [`safe_name`]) added to the prefix.
+pub fn walk_farc<F: Read + Seek>(
+    farc: &Farc<F>,
+    prefix: &str,
+    recursive: bool,
+    visit: &mut dyn FnMut(&str, bool, &FarcFile, ContentReader) -> Result<()>,
+) -> Result<()> {
+    for entry in sorted_entries(farc) {
+        let hash = entry.name_hash;
+        if recursive && is_nested_farc(farc, hash)? {
+            let nested_prefix = format!("{}{}/", prefix, safe_name(hash, entry.name()));
+            match Farc::new(Cursor::new(read_subfile(farc, hash)?)) {
+                Ok(nested) => {
+                    walk_farc(&nested, &nested_prefix, recursive, visit)?;
+                    continue;
+                }
+                Err(err) => warn!(
+                    "{} look like a FARC archive, but can't be parsed: {}",
+                    nested_prefix, err
+                ),
+            };
+        };
+        visit(prefix, farc.is_named_in_archive(), entry, &|| {
+            read_subfile(farc, hash)
+        })?;
+    }
+    Ok(())
+}
+
+#[derive(Args)]
+pub struct FormatArgs {
+    /// Print the result as json
+    #[arg(long, conflicts_with = "csv")]
+    pub json: bool,
+    /// Print the result as csv
+    #[arg(long)]
+    pub csv: bool,
+}
+
+/// Return the entries of the archive, sorted by hash
+pub fn sorted_entries<F: Read + Seek>(farc: &Farc<F>) -> Vec<&FarcFile> {
+    let mut entries = farc.iter_files().collect::<Vec<_>>();
+    entries.sort_unstable_by_key(|entry| entry.name_hash);
+    entries
+}
+
+/// Return the json representation of an entry
+pub fn entry_json(entry: &FarcFile) -> Value {
+    json!({
+        "hash": format!("0x{:08x}", entry.name_hash),
+        "name": entry.name(),
+        "offset": entry.start,
+        "length": entry.length,
+    })
+}
+
+/// Quote a csv field if needed
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Print the entries as csv, with a header line. If given, the paths are added as the first column.
+pub fn print_entries_csv(entries: &[&FarcFile], paths: Option<&[String]>) {
+    if paths.is_some() {
+        print!("path,");
+    };
+    println!("hash,name,offset,length");
+    for (index, entry) in entries.iter().enumerate() {
+        if let Some(paths) = paths {
+            print!("{},", csv_field(&paths[index]));
+        };
+        println!(
+            "0x{:08x},{},{},{}",
+            entry.name_hash,
+            csv_field(entry.name().unwrap_or("")),
+            entry.start,
+            entry.length
+        );
+    }
+}
+
+/// Return an empty directory for a test, in the temporary directory
+#[cfg(test)]
+pub fn test_directory(name: &str) -> std::path::PathBuf {
+    let directory = std::env::temp_dir().join(format!("pmd_farc_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    directory
+}
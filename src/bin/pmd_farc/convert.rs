@@ -0,0 +1,56 @@
+use crate::common::open_farc;
+use anyhow::{bail, Context, Result};
+use clap::{Args, ValueEnum};
+use pmd_farc::{FarcWriter, NameList};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum IndexForm {
+    /// Only store the hash of the file names
+    Hashed,
+    /// Store the file names. The name of every file must be known.
+    Named,
+}
+
+#[derive(Args)]
+pub struct ConvertArgs {
+    /// The form of the created archive
+    #[arg(long, value_enum)]
+    to: IndexForm,
+    /// The archive to convert
+    input: PathBuf,
+    /// The archive to create
+    output: PathBuf,
+    /// A hash to name mapping, in the tab-separated format of NameList, to find the names needed by --to named
+    #[arg(long)]
+    names: Vec<PathBuf>,
+}
+
+pub fn run(args: ConvertArgs) -> Result<()> {
+    let mut farc = open_farc(&args.input)?;
+    for path in &args.names {
+        let file = File::open(path).with_context(|| format!("can't open {:?}", path))?;
+        NameList::parse(&mut BufReader::new(file))
+            .with_context(|| format!("can't read {:?}", path))?
+            .apply(&mut farc);
+    }
+    if args.to == IndexForm::Named && farc.file_unknown_name() > 0 {
+        bail!(
+            "{} files have an unknown name, the archive can't be converted to the named form",
+            farc.file_unknown_name()
+        );
+    };
+
+    let writer = FarcWriter::new_from_farc(&farc)?;
+    let mut output_file = BufWriter::new(
+        File::create(&args.output).with_context(|| format!("can't create {:?}", args.output))?,
+    );
+    match args.to {
+        IndexForm::Hashed => writer.write_hashed(&mut output_file)?,
+        IndexForm::Named => writer.write_named(&mut output_file)?,
+    };
+    output_file.flush()?;
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use crate::common::{open_farc, placeholder_name};
+use crate::lossless::{write_u32_at, Table};
+use anyhow::{Context, Result};
+use clap::Args;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryInto;
+use std::fs::{read, write};
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct DedupeArgs {
+    /// The archive to analyze
+    archive: PathBuf,
+    /// Write a copy of the archive where the files with identical content share the same data
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+pub fn run(args: DedupeArgs) -> Result<()> {
+    let farc = open_farc(&args.archive)?;
+    let archive = read(&args.archive).with_context(|| format!("can't read {:?}", args.archive))?;
+    let table = Table::read(&archive)?;
+    // the index of each entry in the file table, with its start and length, sorted by start
+    let entries = table
+        .storage_order()
+        .into_iter()
+        .map(|index| {
+            let entry = &table.entries[index];
+            (index, entry.start as usize, entry.length as usize)
+        })
+        .collect::<Vec<_>>();
+    let content = |start: usize, length: usize| -> Result<&[u8]> {
+        archive
+            .get(start..start + length)
+            .context("a file goes past the end of the archive")
+    };
+
+    // the names of the files, grouped by content. Files already sharing their data are counted once.
+    let mut groups: BTreeMap<&[u8], Vec<String>> = BTreeMap::new();
+    let mut names = HashMap::new();
+    for file in farc.iter_files() {
+        names.insert(
+            (file.start as usize, file.length as usize),
+            file.name()
+                .map_or_else(|| placeholder_name(file.name_hash), str::to_string),
+        );
+    }
+    let mut previous = None;
+    for (_, start, length) in &entries {
+        if previous == Some((*start, *length)) {
+            continue;
+        };
+        previous = Some((*start, *length));
+        let name = names
+            .get(&(*start, *length))
+            .cloned()
+            .unwrap_or_else(|| format!("(file at 0x{:x})", start));
+        groups
+            .entry(content(*start, *length)?)
+            .or_default()
+            .push(name);
+    }
+
+    let mut saving = 0;
+    let mut duplicated_groups = groups
+        .iter()
+        .filter(|(_, names)| names.len() > 1)
+        .collect::<Vec<_>>();
+    duplicated_groups
+        .sort_by_key(|(data, names)| std::cmp::Reverse(data.len() * (names.len() - 1)));
+    for (data, names) in &duplicated_groups {
+        let group_saving = data.len() * (names.len() - 1);
+        saving += group_saving;
+        println!(
+            "{} files of {} bytes ({} bytes can be saved):",
+            names.len(),
+            data.len(),
+            group_saving
+        );
+        for name in names.iter() {
+            println!("  {}", name);
+        }
+    }
+    println!(
+        "{} groups of identical files, {} bytes can be saved",
+        duplicated_groups.len(),
+        saving
+    );
+
+    if let Some(output) = &args.output {
+        let data_start = entries
+            .first()
+            .map_or(archive.len(), |(_, start, _)| *start);
+        let all_data_offset = table.header.data_offset as usize;
+        let mut result = archive[..data_start].to_vec();
+        let mut placed: HashMap<&[u8], usize> = HashMap::new();
+        for (index, start, length) in &entries {
+            let data = content(*start, *length)?;
+            let start = match placed.get(data) {
+                Some(start) => *start,
+                None => {
+                    let start = result.len();
+                    result.extend_from_slice(data);
+                    result.resize(result.len() + (16 - result.len() % 16) % 16, 0);
+                    placed.insert(data, start);
+                    start
+                }
+            };
+            write_u32_at(
+                &mut result,
+                table.record_position(*index) + 4,
+                (start - all_data_offset).try_into()?,
+            )?;
+        }
+        // the length of the data section change as much as the archive length
+        let data_length =
+            i64::from(table.header.data_length) + result.len() as i64 - archive.len() as i64;
+        write_u32_at(&mut result, 0x30, data_length.try_into()?)?;
+        write(output, result).with_context(|| format!("can't write {:?}", output))?;
+    };
+    Ok(())
+}
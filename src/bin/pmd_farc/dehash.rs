@@ -0,0 +1,79 @@
+use crate::common::open_farc;
+use anyhow::{Context, Result};
+use clap::Args;
+use pmd_farc::{script_dehash, DehashPipeline, FileHashType, ListDehasher};
+use std::fs::File;
+use std::io::{stdout, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Args)]
+pub struct DehashArgs {
+    /// The archive to find the file names of
+    archive: PathBuf,
+    /// A .lst file, with one path per line
+    #[arg(long)]
+    lst: Vec<PathBuf>,
+    /// A word list, with one candidate file name per line
+    #[arg(long)]
+    wordlist: Vec<PathBuf>,
+    /// Write the hash to name mapping to this file instead of the standard output
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+/// Add the strategies that doesn't need any external file, and that apply to this archive
+pub fn add_builtin_dehashers(pipeline: &mut DehashPipeline, archive: &Path) {
+    let file_name = archive
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("");
+    if FileHashType::predict_from_file_name(file_name) == Some(FileHashType::Script) {
+        pipeline.add_dehasher(Box::new(script_dehash::ScriptNameRule::default()));
+    };
+    #[cfg(any(feature = "bgrs", feature = "bch"))]
+    pipeline.add_dehasher(Box::new(pmd_farc::find_name::ContentDehasher));
+}
+
+pub fn run(args: DehashArgs) -> Result<()> {
+    let mut farc = open_farc(&args.archive)?;
+
+    let mut pipeline = DehashPipeline::default();
+    for path in &args.lst {
+        let mut file = File::open(path).with_context(|| format!("can't open {:?}", path))?;
+        pipeline.add_dehasher(Box::new(
+            ListDehasher::from_lst(&mut file).with_context(|| format!("can't read {:?}", path))?,
+        ));
+    }
+    add_builtin_dehashers(&mut pipeline, &args.archive);
+    for path in &args.wordlist {
+        let mut file = File::open(path).with_context(|| format!("can't open {:?}", path))?;
+        pipeline.add_dehasher(Box::new(
+            ListDehasher::from_wordlist(&mut file)
+                .with_context(|| format!("can't read {:?}", path))?,
+        ));
+    }
+    let report = pipeline.run(&mut farc)?;
+
+    let mut names = farc
+        .iter()
+        .filter_map(|(hash, name)| name.map(|name| (hash, name)))
+        .collect::<Vec<_>>();
+    names.sort_unstable();
+    let mut writer: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(BufWriter::new(
+            File::create(path).with_context(|| format!("can't create {:?}", path))?,
+        )),
+        None => Box::new(stdout()),
+    };
+    for (hash, name) in names {
+        writeln!(writer, "0x{:08x}\t{}", hash, name)?;
+    }
+    writer.flush()?;
+
+    // the mapping may be written on the standard output, so keep the statistics apart
+    for (strategy, resolved) in report.resolved_by_strategy() {
+        eprintln!("{}: {} new names", strategy, resolved);
+    }
+    eprint!("{}", farc.coverage());
+    Ok(())
+}
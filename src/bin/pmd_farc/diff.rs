@@ -0,0 +1,83 @@
+use crate::common::{open_farc, read_subfile};
+use anyhow::Result;
+use clap::Args;
+use crc::crc32::checksum_ieee;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Args)]
+pub struct DiffArgs {
+    /// The original archive
+    old: PathBuf,
+    /// The modified archive
+    new: PathBuf,
+    /// Print the differences as json
+    #[arg(long)]
+    json: bool,
+}
+
+/// The name (if known) and the crc32 checksum of the content of each file of an archive
+fn read_checksums(path: &Path) -> Result<BTreeMap<u32, (Option<String>, u32)>> {
+    let farc = open_farc(path)?;
+    let mut result = BTreeMap::new();
+    for (hash, name) in farc.iter() {
+        let checksum = checksum_ieee(&read_subfile(&farc, hash)?);
+        result.insert(hash, (name.map(str::to_string), checksum));
+    }
+    Ok(result)
+}
+
+pub fn run(args: DiffArgs) -> Result<()> {
+    let old = read_checksums(&args.old)?;
+    let new = read_checksums(&args.new)?;
+
+    // (status, hash, name)
+    let mut changes = Vec::new();
+    for (hash, (old_name, old_checksum)) in &old {
+        match new.get(hash) {
+            None => changes.push(("removed", *hash, old_name.clone())),
+            Some((new_name, new_checksum)) if new_checksum != old_checksum => changes.push((
+                "modified",
+                *hash,
+                new_name.clone().or_else(|| old_name.clone()),
+            )),
+            Some(_) => (),
+        }
+    }
+    for (hash, (new_name, _)) in &new {
+        if !old.contains_key(hash) {
+            changes.push(("added", *hash, new_name.clone()));
+        };
+    }
+    changes.sort_by_key(|(_, hash, _)| *hash);
+
+    if args.json {
+        let entries = changes
+            .iter()
+            .map(|(status, hash, name)| {
+                json!({
+                    "status": status,
+                    "hash": format!("0x{:08x}", hash),
+                    "name": name,
+                    "old_checksum": old.get(hash).map(|(_, checksum)| format!("{:08x}", checksum)),
+                    "new_checksum": new.get(hash).map(|(_, checksum)| format!("{:08x}", checksum)),
+                })
+            })
+            .collect::<Vec<_>>();
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+    } else {
+        for (status, hash, name) in &changes {
+            let symbol = match *status {
+                "added" => '+',
+                "removed" => '-',
+                _ => '~',
+            };
+            match name {
+                Some(name) => println!("{} {:08x} {}", symbol, hash, name),
+                None => println!("{} {:08x}", symbol, hash),
+            };
+        }
+    }
+    Ok(())
+}
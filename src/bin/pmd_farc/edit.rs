@@ -0,0 +1,182 @@
+use crate::common::{open_farc, parse_entry};
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use pmd_farc::{hash_name, FarcWriter};
+use std::fs::{read, rename, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+#[derive(Args)]
+pub struct AddArgs {
+    /// The archive to modify
+    archive: PathBuf,
+    /// The name (or hash, starting with 0x) of the new file in the archive. It must be a name if the archive
+    /// store the file names.
+    entry: String,
+    /// The file to add
+    file: PathBuf,
+    /// Write the modified archive to this path instead of modifying it in place
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct RemoveArgs {
+    /// The archive to modify
+    archive: PathBuf,
+    /// The name (or hash, starting with 0x) of the file to remove
+    entry: String,
+    /// Write the modified archive to this path instead of modifying it in place
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct ReplaceArgs {
+    /// The archive to modify
+    archive: PathBuf,
+    /// The name (or hash, starting with 0x) of the file to replace
+    entry: String,
+    /// The file with the new content
+    file: PathBuf,
+    /// Write the modified archive to this path instead of modifying it in place
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+/// Load the archive into a [`FarcWriter`], let the caller modify it (knowing whether the archive store the file
+/// names), then write it back in the same form
+fn edit_archive(
+    archive: &Path,
+    output: Option<&Path>,
+    edit: impl FnOnce(&mut FarcWriter, bool) -> Result<()>,
+) -> Result<()> {
+    let (mut writer, named) = {
+        let farc = open_farc(archive)?;
+        (
+            FarcWriter::new_from_farc(&farc)?,
+            farc.is_named_in_archive(),
+        )
+    };
+    edit(&mut writer, named)?;
+
+    let output = output.unwrap_or(archive);
+    // write to a temporary file first, so the archive isn't lost if writing fail
+    let mut temporary_name = output.as_os_str().to_owned();
+    temporary_name.push(".tmp");
+    let temporary_path = PathBuf::from(temporary_name);
+    let mut output_file = BufWriter::new(
+        File::create(&temporary_path)
+            .with_context(|| format!("can't create {:?}", temporary_path))?,
+    );
+    if named {
+        writer.write_named(&mut output_file)?;
+    } else {
+        writer.write_hashed(&mut output_file)?;
+    };
+    drop(output_file);
+    rename(&temporary_path, output)
+        .with_context(|| format!("can't move {:?} to {:?}", temporary_path, output))
+}
+
+pub fn run_add(args: AddArgs) -> Result<()> {
+    let hash = parse_entry(&args.entry);
+    let content = read(&args.file).with_context(|| format!("can't read {:?}", args.file))?;
+    // only a name hashing to the entry, and not an hash or a placeholder name, can be stored in the archive
+    let name = (hash_name(&args.entry) == hash).then(|| args.entry.clone());
+    edit_archive(&args.archive, args.output.as_deref(), |writer, named| {
+        if writer.contains_hash(hash) {
+            bail!("the archive already contain {} ({:08x})", args.entry, hash);
+        };
+        match name {
+            Some(name) => writer.add_named_file(name, content),
+            None if named => bail!(
+                "{:?} store the file names, so the new file need a name, not only an hash",
+                args.archive
+            ),
+            None => writer.add_hashed_file(hash, content),
+        };
+        Ok(())
+    })
+}
+
+pub fn run_remove(args: RemoveArgs) -> Result<()> {
+    let hash = parse_entry(&args.entry);
+    edit_archive(&args.archive, args.output.as_deref(), |writer, _| {
+        if writer.remove_hashed_file(hash).is_none() {
+            bail!("the archive doesn't contain {} ({:08x})", args.entry, hash);
+        };
+        Ok(())
+    })
+}
+
+pub fn run_replace(args: ReplaceArgs) -> Result<()> {
+    let hash = parse_entry(&args.entry);
+    let content = read(&args.file).with_context(|| format!("can't read {:?}", args.file))?;
+    edit_archive(&args.archive, args.output.as_deref(), |writer, _| {
+        // the name of the file, if known, is kept
+        if !writer.contains_hash(hash) {
+            bail!("the archive doesn't contain {} ({:08x})", args.entry, hash);
+        };
+        writer.add_hashed_file(hash, content);
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::test_directory;
+    use pmd_farc::Farc;
+    use std::fs::write;
+
+    #[test]
+    fn edit_named_archive() {
+        let directory = test_directory("edit_named");
+        let archive = directory.join("archive.bin");
+        let mut writer = FarcWriter::default();
+        writer.add_named_file("a.bin".to_string(), vec![1; 16]);
+        writer.add_named_file("b.bin".to_string(), vec![2; 16]);
+        writer
+            .write_named(&mut File::create(&archive).unwrap())
+            .unwrap();
+        let file = directory.join("new.bin");
+        write(&file, [3; 16]).unwrap();
+
+        run_add(AddArgs {
+            archive: archive.clone(),
+            entry: "c.bin".to_string(),
+            file: file.clone(),
+            output: None,
+        })
+        .unwrap();
+        // a file without a name can't be added to a named archive
+        assert!(run_add(AddArgs {
+            archive: archive.clone(),
+            entry: format!("0x{:08x}", hash_name("d.bin")),
+            file: file.clone(),
+            output: None,
+        })
+        .is_err());
+        run_remove(RemoveArgs {
+            archive: archive.clone(),
+            entry: "a.bin".to_string(),
+            output: None,
+        })
+        .unwrap();
+        run_replace(ReplaceArgs {
+            archive: archive.clone(),
+            entry: format!("0x{:08x}", hash_name("b.bin")),
+            file,
+            output: None,
+        })
+        .unwrap();
+
+        let farc = Farc::new(File::open(&archive).unwrap()).unwrap();
+        assert!(farc.is_named_in_archive());
+        let mut names = farc.iter_name().collect::<Vec<_>>();
+        names.sort_unstable();
+        assert_eq!(names, vec!["b.bin", "c.bin"]);
+        assert!(farc.read_named_file("b.bin").unwrap().starts_with(&[3; 16]));
+    }
+}
@@ -0,0 +1,85 @@
+use crate::common::open_farc;
+use crate::extract::export_farc;
+use anyhow::{bail, Context, Result};
+use clap::{Args, ValueEnum};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Zip,
+    Tar,
+}
+
+impl ExportFormat {
+    /// Guess the format from the extension of the output file
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "zip" => Some(Self::Zip),
+            "tar" => Some(Self::Tar),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct ExportArgs {
+    /// The archive to export
+    archive: PathBuf,
+    /// The zip or tar file to create
+    output: PathBuf,
+    /// The format of the output file. Guessed from its extension by default.
+    #[arg(long, value_enum)]
+    format: Option<ExportFormat>,
+    /// Export the files of the nested archives to a directory named after the archive, instead of the archive itself
+    #[arg(short, long)]
+    recursive: bool,
+}
+
+pub fn run(args: ExportArgs) -> Result<()> {
+    let format = match args
+        .format
+        .or_else(|| ExportFormat::from_path(&args.output))
+    {
+        Some(format) => format,
+        None => bail!(
+            "can't guess the format of {:?} from its extension, use --format",
+            args.output
+        ),
+    };
+    let farc = open_farc(&args.archive)?;
+    let output = BufWriter::new(
+        File::create(&args.output).with_context(|| format!("can't create {:?}", args.output))?,
+    );
+    let count = match format {
+        ExportFormat::Zip => {
+            let mut zip = ZipWriter::new(output);
+            let options =
+                SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+            let count = export_farc(&farc, args.recursive, &|_, _| true, &mut |path, content| {
+                zip.start_file(path, options)?;
+                zip.write_all(&content)?;
+                Ok(())
+            })?;
+            zip.finish()?.flush()?;
+            count
+        }
+        ExportFormat::Tar => {
+            let mut tar = tar::Builder::new(output);
+            let count = export_farc(&farc, args.recursive, &|_, _| true, &mut |path, content| {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(content.len() as u64);
+                header.set_mode(0o644);
+                tar.append_data(&mut header, path, content.as_slice())?;
+                Ok(())
+            })?;
+            tar.into_inner()?.flush()?;
+            count
+        }
+    };
+    info!("exported {} files to {}", count, args.output.display());
+    Ok(())
+}
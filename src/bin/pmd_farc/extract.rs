@@ -0,0 +1,150 @@
+use crate::common::{open_farc, parse_hash, placeholder_name, safe_name, walk_farc};
+use crate::manifest::{LEGACY_MANIFEST_FILE_NAME, MANIFEST_FILE_NAME};
+use anyhow::{Context, Result};
+use clap::Args;
+use pmd_farc::{wildcard_match, Farc, Manifest, ManifestEntry};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{create_dir_all, write};
+use std::io::{Read, Seek};
+use std::path::{Path, PathBuf};
+
+#[derive(Args)]
+pub struct ExtractArgs {
+    /// The archive to extract
+    archive: PathBuf,
+    /// The directory to extract the files to
+    #[arg(short, long)]
+    output: PathBuf,
+    /// Only extract the files whose name (or placeholder name) match this pattern. ``*`` and ``?`` are supported.
+    #[arg(long)]
+    glob: Vec<String>,
+    /// Only extract the file with this hash (in hexadecimal)
+    #[arg(long, value_parser = parse_hash)]
+    hash: Vec<u32>,
+    /// Extract the files of the nested archives to a directory named after the archive, instead of the archive
+    /// itself. The patterns of --glob then match the path, like outer.bin/inner.bin/file.
+    #[arg(short, long)]
+    recursive: bool,
+}
+
+impl ExtractArgs {
+    /// Return true if the file should be extracted. A file is extracted if it match any of the filters.
+    fn is_selected(&self, hash: u32, file_name: &str) -> bool {
+        if self.glob.is_empty() && self.hash.is_empty() {
+            return true;
+        };
+        self.hash.contains(&hash)
+            || self
+                .glob
+                .iter()
+                .any(|pattern| wildcard_match(pattern, file_name))
+    }
+}
+
+/// Return a file name that is safe to write in the output directory (see [`safe_name`]), and that isn't already
+/// used.
+pub fn output_name(hash: u32, name: Option<&str>, used: &mut HashSet<String>) -> String {
+    let name = safe_name(hash, name);
+    // compared in lowercase, as the file system may be case insensitive
+    if used.insert(name.to_lowercase()) {
+        return name;
+    };
+    let deduplicated = match name.rfind('.') {
+        Some(dot) if dot > 0 => format!("{}.0x{:08x}{}", &name[..dot], hash, &name[dot..]),
+        _ => format!("{}.0x{:08x}", name, hash),
+    };
+    used.insert(deduplicated.to_lowercase());
+    deduplicated
+}
+
+/// Call ``write`` with the relative path and the content of each selected file of the archive (and of the nested
+/// archives, if ``recursive``), followed by the manifest of each directory. ``is_selected`` is called with the hash
+/// and the path of each file. The paths use ``/`` as separator. Return the number of archive files.
+pub fn export_farc<F: Read + Seek>(
+    farc: &Farc<F>,
+    recursive: bool,
+    is_selected: &dyn Fn(u32, &str) -> bool,
+    write: &mut dyn FnMut(&str, Vec<u8>) -> Result<()>,
+) -> Result<usize> {
+    // the used names, and the manifest (with the position of each file, to sort them), of each (nested) directory
+    let mut used: HashMap<String, HashSet<String>> = HashMap::new();
+    let mut manifests: BTreeMap<String, (bool, Vec<(u32, ManifestEntry)>)> = BTreeMap::new();
+    let mut written = 0;
+    walk_farc(farc, "", recursive, &mut |prefix, named, entry, content| {
+        let hash = entry.name_hash;
+        let display_name = format!(
+            "{}{}",
+            prefix,
+            entry
+                .name()
+                .map_or_else(|| placeholder_name(hash), str::to_string)
+        );
+        if !is_selected(hash, &display_name) {
+            return Ok(());
+        };
+        let used = used.entry(prefix.to_string()).or_insert_with(|| {
+            [MANIFEST_FILE_NAME, LEGACY_MANIFEST_FILE_NAME]
+                .iter()
+                .map(|name| name.to_string())
+                .collect()
+        });
+        let file_name = output_name(hash, entry.name(), used);
+        manifests
+            .entry(prefix.to_string())
+            .or_insert_with(|| (named, Vec::new()))
+            .1
+            .push((
+                entry.start,
+                ManifestEntry {
+                    hash,
+                    file_name: file_name.clone(),
+                    name: entry.name().map(str::to_string),
+                    length: entry.length,
+                    offset: None,
+                    padding: None,
+                },
+            ));
+        write(&format!("{}{}", prefix, file_name), content()?)?;
+        written += 1;
+        Ok(())
+    })?;
+    for (prefix, (named, mut entries)) in manifests {
+        entries.sort_by_key(|(start, entry)| (*start, entry.hash));
+        let manifest = Manifest {
+            named,
+            entries: entries.into_iter().map(|(_, entry)| entry).collect(),
+            ..Manifest::default()
+        };
+        let mut content = Vec::new();
+        manifest.write(&mut content)?;
+        write(&format!("{}{}", prefix, MANIFEST_FILE_NAME), content)?;
+    }
+    Ok(written)
+}
+
+/// Extract the selected files of the archive (and of the nested archives, if ``recursive``) to the output
+/// directory (see [`export_farc`]). Return the number of written files.
+pub fn extract_farc<F: Read + Seek>(
+    farc: &Farc<F>,
+    output: &Path,
+    recursive: bool,
+    is_selected: &dyn Fn(u32, &str) -> bool,
+) -> Result<usize> {
+    create_dir_all(output).with_context(|| format!("can't create the directory {:?}", output))?;
+    export_farc(farc, recursive, is_selected, &mut |path, content| {
+        let output_path = output.join(path);
+        if let Some(directory) = output_path.parent() {
+            create_dir_all(directory)
+                .with_context(|| format!("can't create the directory {:?}", directory))?;
+        };
+        write(&output_path, content).with_context(|| format!("can't write {:?}", output_path))
+    })
+}
+
+pub fn run(args: ExtractArgs) -> Result<()> {
+    let farc = open_farc(&args.archive)?;
+    extract_farc(&farc, &args.output, args.recursive, &|hash, path| {
+        args.is_selected(hash, path)
+    })?;
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use crate::common::open_farc;
+use crate::dehash::add_builtin_dehashers;
+use anyhow::{Context, Result};
+use clap::Args;
+use pmd_farc::{BinaryStringDehasher, BruteForce, DehashPipeline, ListDehasher, NameGenerator};
+use serde_json::json;
+use std::fs::{read, read_to_string, File};
+use std::path::{Path, PathBuf};
+
+#[derive(Args)]
+pub struct GuessNamesArgs {
+    /// The archive to guess the file names of
+    archive: PathBuf,
+    /// A file with one internal Pokémon name per line, used to generate the PSMD graphic file names
+    #[arg(long)]
+    pokemon: Option<PathBuf>,
+    /// A file with one animation name per line, used with --pokemon
+    #[arg(long)]
+    animation: Option<PathBuf>,
+    /// A binary file (like the code of the game) to search file names in
+    #[arg(long)]
+    binary: Vec<PathBuf>,
+    /// A word list, with one candidate file name per line
+    #[arg(long)]
+    wordlist: Vec<PathBuf>,
+    /// Try every name up to this length made of the --charset characters. Short names are likely to be false
+    /// positives.
+    #[arg(long)]
+    brute_force: Option<usize>,
+    /// The characters used by --brute-force
+    #[arg(long, default_value = "abcdefghijklmnopqrstuvwxyz0123456789_")]
+    charset: String,
+    /// The text added before every brute forced name
+    #[arg(long, default_value = "")]
+    prefix: String,
+    /// The text added after every brute forced name (like an extension)
+    #[arg(long, default_value = ".bin")]
+    suffix: String,
+    /// Print the guesses as json
+    #[arg(long)]
+    json: bool,
+}
+
+/// How much a guess of this strategy can be trusted. Every guess match the hash, but strategies trying many
+/// unrelated names are more likely to find a name that is only a collision.
+fn confidence(strategy: &str) -> &'static str {
+    match strategy {
+        "script id" | "content scan" | "lst" => "high",
+        "name generator" | "binary strings" | "wordlist" => "medium",
+        _ => "low",
+    }
+}
+
+fn read_lines(path: &Path) -> Result<Vec<String>> {
+    Ok(read_to_string(path)
+        .with_context(|| format!("can't read {:?}", path))?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+pub fn run(args: GuessNamesArgs) -> Result<()> {
+    let mut farc = open_farc(&args.archive)?;
+
+    let mut pipeline = DehashPipeline::default();
+    add_builtin_dehashers(&mut pipeline, &args.archive);
+    if let Some(pokemon) = &args.pokemon {
+        let mut generator = NameGenerator::new_psmd_graphic();
+        for name in read_lines(pokemon)? {
+            generator.add_pokemon_name(&name);
+        }
+        if let Some(animation) = &args.animation {
+            for name in read_lines(animation)? {
+                generator.add_animation_name(&name);
+            }
+        };
+        pipeline.add_dehasher(Box::new(generator));
+    };
+    for path in &args.binary {
+        let data = read(path).with_context(|| format!("can't read {:?}", path))?;
+        pipeline.add_dehasher(Box::new(BinaryStringDehasher::new(&data, &[])));
+    }
+    for path in &args.wordlist {
+        let mut file = File::open(path).with_context(|| format!("can't open {:?}", path))?;
+        pipeline.add_dehasher(Box::new(
+            ListDehasher::from_wordlist(&mut file)
+                .with_context(|| format!("can't read {:?}", path))?,
+        ));
+    }
+    if let Some(max_length) = args.brute_force {
+        let mut brute_force = BruteForce::new(&args.charset, 1, max_length);
+        brute_force.set_prefix(&args.prefix);
+        brute_force.set_suffix(&args.suffix);
+        pipeline.add_dehasher(Box::new(brute_force));
+    };
+    let report = pipeline.run(&mut farc)?;
+
+    if args.json {
+        let guesses = report
+            .attribution
+            .iter()
+            .map(|(hash, resolution)| {
+                json!({
+                    "hash": format!("0x{:08x}", hash),
+                    "name": resolution.name,
+                    "strategy": resolution.strategy,
+                    "confidence": confidence(&resolution.strategy),
+                })
+            })
+            .collect::<Vec<_>>();
+        println!("{}", serde_json::to_string_pretty(&guesses)?);
+    } else {
+        for (hash, resolution) in &report.attribution {
+            println!(
+                "0x{:08x}\t{}\t{}\t{}",
+                hash,
+                resolution.name,
+                resolution.strategy,
+                confidence(&resolution.strategy)
+            );
+        }
+    };
+    for report in &report.reports {
+        for collision in &report.collisions {
+            warn!(
+                "{} and {} both match {:08x}, only the first was kept",
+                collision.accepted, collision.rejected, collision.hash
+            );
+        }
+    }
+    eprintln!(
+        "{} new names, {} still unknown",
+        report.total_resolved(),
+        farc.file_unknown_name()
+    );
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use crate::common::open_farc;
+use anyhow::Result;
+use clap::Args;
+use pmd_farc::hash_name;
+use std::io::{stdin, BufRead};
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct HashArgs {
+    /// The names to hash. They are read from the standard input, one per line, if none is given.
+    names: Vec<String>,
+    /// Check the names against this archive, printing if a file with this hash exist
+    #[arg(short, long)]
+    archive: Option<PathBuf>,
+}
+
+pub fn run(args: HashArgs) -> Result<()> {
+    let names = if args.names.is_empty() {
+        stdin().lock().lines().collect::<Result<Vec<_>, _>>()?
+    } else {
+        args.names
+    };
+    let farc = args.archive.as_deref().map(open_farc).transpose()?;
+    for name in names {
+        let hash = hash_name(&name);
+        match &farc {
+            None => println!("{:08x} {}", hash, name),
+            Some(farc) => {
+                let status = if farc.get_hashed_file(hash).is_ok() {
+                    "found"
+                } else {
+                    "not found"
+                };
+                println!("{:08x} {} {}", hash, name, status)
+            }
+        };
+    }
+    Ok(())
+}
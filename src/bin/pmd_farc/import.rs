@@ -0,0 +1,141 @@
+use crate::export::ExportFormat;
+use crate::manifest::{
+    is_manifest, parse_legacy_manifest, LEGACY_MANIFEST_FILE_NAME, MANIFEST_FILE_NAME,
+};
+use crate::pack::{apply_layout, file_hash};
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use pmd_farc::{FarcWriter, Manifest};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Cursor, Read};
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+
+#[derive(Args)]
+pub struct ImportArgs {
+    /// The zip or tar file to import, with the layout created by export
+    input: PathBuf,
+    /// The archive to create
+    #[arg(short, long)]
+    output: PathBuf,
+    /// The format of the input file. Guessed from its extension by default.
+    #[arg(long, value_enum)]
+    format: Option<ExportFormat>,
+}
+
+/// Read every file of a zip or tar file, with their path
+fn read_files(path: &Path, format: ExportFormat) -> Result<BTreeMap<String, Vec<u8>>> {
+    let file = BufReader::new(File::open(path).with_context(|| format!("can't open {:?}", path))?);
+    let mut files = BTreeMap::new();
+    match format {
+        ExportFormat::Zip => {
+            let mut zip = ZipArchive::new(file)?;
+            for index in 0..zip.len() {
+                let mut entry = zip.by_index(index)?;
+                if !entry.is_file() {
+                    continue;
+                };
+                let mut content = Vec::new();
+                entry.read_to_end(&mut content)?;
+                files.insert(entry.name().to_string(), content);
+            }
+        }
+        ExportFormat::Tar => {
+            let mut tar = tar::Archive::new(file);
+            for entry in tar.entries()? {
+                let mut entry = entry?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                };
+                let name = entry
+                    .path()?
+                    .to_str()
+                    .context("a file name in the tar file isn't valid unicode")?
+                    .to_string();
+                let mut content = Vec::new();
+                entry.read_to_end(&mut content)?;
+                files.insert(name, content);
+            }
+        }
+    };
+    Ok(files)
+}
+
+/// Build an archive from the files whose path start with ``prefix``. The subdirectories (created by export
+/// --recursive) become nested archives.
+fn build_farc(files: &BTreeMap<String, Vec<u8>>, prefix: &str) -> Result<FarcWriter> {
+    let legacy = format!("{}{}", prefix, LEGACY_MANIFEST_FILE_NAME);
+    let manifest = match files.get(&format!("{}{}", prefix, MANIFEST_FILE_NAME)) {
+        Some(content) => Manifest::read(content.as_slice())
+            .with_context(|| format!("can't parse {}{}", prefix, MANIFEST_FILE_NAME))?,
+        None => match files.get(&legacy) {
+            Some(content) => parse_legacy_manifest(&String::from_utf8_lossy(content))
+                .with_context(|| format!("can't parse {}", legacy))?,
+            None => Manifest::default(),
+        },
+    };
+    let mut writer = FarcWriter::default();
+    apply_layout(&mut writer, &manifest)?;
+    let mut added: HashMap<u32, String> = HashMap::new();
+    let mut add = |writer: &mut FarcWriter, file_name: &str, content: Vec<u8>| {
+        let hash = file_hash(&manifest, file_name);
+        if let Some(other) = added.insert(hash, file_name.to_string()) {
+            bail!(
+                "{}{} and {}{} both have the hash {:08x}",
+                prefix,
+                other,
+                prefix,
+                file_name,
+                hash
+            );
+        };
+        writer.add_hashed_file(hash, content);
+        Ok(())
+    };
+    let mut directories = Vec::new();
+    for (path, content) in files.range(prefix.to_string()..) {
+        let relative = match path.strip_prefix(prefix) {
+            Some(relative) => relative,
+            None => break,
+        };
+        match relative.split_once('/') {
+            Some((directory, _)) => {
+                if directories.last() != Some(&directory) {
+                    directories.push(directory);
+                };
+            }
+            None if is_manifest(relative) => (),
+            None => add(&mut writer, relative, content.clone())?,
+        };
+    }
+    for directory in directories {
+        let nested = build_farc(files, &format!("{}{}/", prefix, directory))?;
+        let mut content = Cursor::new(Vec::new());
+        nested.write_hashed(&mut content)?;
+        add(&mut writer, directory, content.into_inner())?;
+    }
+    Ok(writer)
+}
+
+pub fn run(args: ImportArgs) -> Result<()> {
+    let format = match args.format.or_else(|| ExportFormat::from_path(&args.input)) {
+        Some(format) => format,
+        None => bail!(
+            "can't guess the format of {:?} from its extension, use --format",
+            args.input
+        ),
+    };
+    let files = read_files(&args.input, format)?;
+    let writer = build_farc(&files, "")?;
+    let mut output_file = BufWriter::new(
+        File::create(&args.output).with_context(|| format!("can't create {:?}", args.output))?,
+    );
+    writer.write_hashed(&mut output_file)?;
+    info!(
+        "imported {} into {}",
+        args.input.display(),
+        args.output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use crate::common::{entry_json, open_farc, print_entries_csv, sorted_entries, FormatArgs};
+use anyhow::Result;
+use clap::Args;
+use serde_json::json;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct InfoArgs {
+    /// The archive to describe
+    archive: PathBuf,
+    #[command(flatten)]
+    format: FormatArgs,
+}
+
+pub fn run(args: InfoArgs) -> Result<()> {
+    let farc = open_farc(&args.archive)?;
+    if args.format.json {
+        let coverage = farc.coverage();
+        let info = json!({
+            "archive": args.archive,
+            "files": farc.file_count(),
+            "named_in_archive": farc.is_named_in_archive(),
+            "named": coverage.named,
+            "unverified": coverage.unverified,
+            "entries": sorted_entries(&farc).into_iter().map(entry_json).collect::<Vec<_>>(),
+        });
+        println!("{}", serde_json::to_string_pretty(&info)?);
+        return Ok(());
+    };
+    if args.format.csv {
+        print_entries_csv(&sorted_entries(&farc), None);
+        return Ok(());
+    };
+    println!("archive: {}", args.archive.display());
+    println!("files: {}", farc.file_count());
+    println!(
+        "index: {}",
+        if farc.is_named_in_archive() {
+            "by name"
+        } else {
+            "by hash"
+        }
+    );
+    print!("{}", farc.coverage());
+    Ok(())
+}
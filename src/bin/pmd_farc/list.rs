@@ -0,0 +1,75 @@
+use crate::common::{
+    entry_json, open_farc, print_entries_csv, safe_name, sorted_entries, walk_farc, FormatArgs,
+};
+use anyhow::Result;
+use clap::Args;
+use pmd_farc::Farc;
+use std::io::{Read, Seek};
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct ListArgs {
+    /// The archive to list
+    archive: PathBuf,
+    #[command(flatten)]
+    format: FormatArgs,
+    /// List the files of the nested archives instead of the archives themselves, with a path like
+    /// outer.bin/inner.bin/file. The json and csv output then contain the path of each file.
+    #[arg(short, long)]
+    recursive: bool,
+}
+
+pub fn run(args: ListArgs) -> Result<()> {
+    let farc = open_farc(&args.archive)?;
+    if args.recursive {
+        return run_recursive(args, &farc);
+    };
+    let entries = sorted_entries(&farc);
+    if args.format.json {
+        let entries = entries.into_iter().map(entry_json).collect::<Vec<_>>();
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+    } else if args.format.csv {
+        print_entries_csv(&entries, None);
+    } else {
+        for entry in entries {
+            match entry.name() {
+                Some(name) => println!("{:08x} {}", entry.name_hash, name),
+                None => println!("{:08x}", entry.name_hash),
+            };
+        }
+    };
+    Ok(())
+}
+
+fn run_recursive<F: Read + Seek>(args: ListArgs, farc: &Farc<F>) -> Result<()> {
+    let mut paths = Vec::new();
+    let mut entries = Vec::new();
+    walk_farc(farc, "", true, &mut |prefix, _, entry, _| {
+        paths.push(format!(
+            "{}{}",
+            prefix,
+            safe_name(entry.name_hash, entry.name())
+        ));
+        entries.push(entry.clone());
+        Ok(())
+    })?;
+    if args.format.json {
+        let entries = entries
+            .iter()
+            .zip(&paths)
+            .map(|(entry, path)| {
+                let mut json = entry_json(entry);
+                json["path"] = path.clone().into();
+                json
+            })
+            .collect::<Vec<_>>();
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+    } else if args.format.csv {
+        print_entries_csv(&entries.iter().collect::<Vec<_>>(), Some(&paths));
+    } else {
+        for (entry, path) in entries.iter().zip(&paths) {
+            println!("{:08x} {}", entry.name_hash, path);
+        }
+    };
+    Ok(())
+}
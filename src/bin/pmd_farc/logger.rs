@@ -0,0 +1,62 @@
+use clap::ValueEnum;
+use log::{LevelFilter, Log, Metadata, Record};
+use serde_json::json;
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// One human-readable line per message
+    Text,
+    /// One json object per line, with the level, target and message
+    Json,
+}
+
+/// A logger writing the messages of the ``log`` crate to the standard error
+struct StderrLogger {
+    level: LevelFilter,
+    format: LogFormat,
+}
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        };
+        match self.format {
+            LogFormat::Text => {
+                eprintln!("[{} {}] {}", record.level(), record.target(), record.args())
+            }
+            LogFormat::Json => eprintln!(
+                "{}",
+                json!({
+                    "level": record.level().as_str(),
+                    "target": record.target(),
+                    "message": record.args().to_string(),
+                })
+            ),
+        };
+    }
+
+    fn flush(&self) {}
+}
+
+/// Install the logger. Warnings and errors are shown by default, ``quiet`` only show errors, and each ``verbose``
+/// level show one more level.
+pub fn init(quiet: bool, verbose: u8, format: LogFormat) {
+    let level = if quiet {
+        LevelFilter::Error
+    } else {
+        match verbose {
+            0 => LevelFilter::Warn,
+            1 => LevelFilter::Info,
+            2 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    };
+    log::set_max_level(level);
+    // this can only fail if a logger is already installed
+    let _ = log::set_logger(Box::leak(Box::new(StderrLogger { level, format })));
+}
@@ -0,0 +1,310 @@
+use crate::common::open_farc;
+use crate::extract::output_name;
+use crate::manifest::{read_manifest, LEGACY_MANIFEST_FILE_NAME, MANIFEST_FILE_NAME};
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use pmd_farc::raw::{
+    read_entries, read_header, FarcParser, ParseStep, RawEntry, RawHeader, RawName,
+};
+use pmd_farc::{Manifest, ManifestEntry};
+use std::collections::{HashMap, HashSet};
+use std::convert::TryInto;
+use std::fs::{create_dir_all, read, write, File};
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+/// The name of the file containing the header and the file table of the original archive
+const METADATA_FILE_NAME: &str = "layout.metadata.bin";
+
+#[derive(Args)]
+pub struct UnpackArgs {
+    /// The archive to unpack
+    archive: PathBuf,
+    /// The directory to unpack the archive to
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+#[derive(Args)]
+pub struct RepackArgs {
+    /// A directory created by unpack. The content of the files can be modified, but files can't be added or removed.
+    input: PathBuf,
+    /// The archive to create
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+/// The header and the file table of an archive
+pub struct Table {
+    pub header: RawHeader,
+    /// The position of the first record of the file table
+    pub offset: usize,
+    /// The entries, in the order of the file table
+    pub entries: Vec<RawEntry>,
+}
+
+impl Table {
+    /// Read the header and the file table of an archive, or of the metadata written by unpack
+    pub fn read(archive: &[u8]) -> Result<Self> {
+        let header = read_header(archive).context("can't read the header")?;
+        let mut parser = FarcParser::from_header(&header);
+        let offset = loop {
+            match parser.next_from(archive)? {
+                ParseStep::Table { offset, .. } => break offset,
+                ParseStep::Done => bail!("the file table can't be found"),
+                _ => (),
+            };
+        };
+        let entries = read_entries(archive, &header).context("can't read the file table")?;
+        Ok(Self {
+            header,
+            offset: offset.try_into()?,
+            entries,
+        })
+    }
+
+    /// Return the position of the record of the entry at this index
+    pub fn record_position(&self, index: usize) -> usize {
+        self.offset + index * 12
+    }
+
+    /// Return the index of the entries, sorted by the position of their content
+    pub fn storage_order(&self) -> Vec<usize> {
+        let mut order = (0..self.entries.len()).collect::<Vec<_>>();
+        order.sort_by_key(|index| (self.entries[*index].start, *index));
+        order
+    }
+}
+
+pub fn write_u32_at(data: &mut [u8], offset: usize, value: u32) -> Result<()> {
+    data.get_mut(offset..offset + 4)
+        .context("the metadata is too short")?
+        .copy_from_slice(&value.to_le_bytes());
+    Ok(())
+}
+
+pub fn run_unpack(args: UnpackArgs) -> Result<()> {
+    // used for the names of hashed files, that may be known from a list
+    let farc = open_farc(&args.archive)?;
+    let names = farc
+        .iter()
+        .filter_map(|(hash, name)| Some((hash, name?.to_string())))
+        .collect::<HashMap<_, _>>();
+    let archive = read(&args.archive).with_context(|| format!("can't read {:?}", args.archive))?;
+    let table = Table::read(&archive)?;
+    let order = table.storage_order();
+    let metadata_end = order
+        .first()
+        .map_or(archive.len(), |index| table.entries[*index].start as usize);
+
+    create_dir_all(&args.output)
+        .with_context(|| format!("can't create the directory {:?}", args.output))?;
+    let mut used = [
+        MANIFEST_FILE_NAME,
+        LEGACY_MANIFEST_FILE_NAME,
+        METADATA_FILE_NAME,
+    ]
+    .iter()
+    .map(|name| name.to_string())
+    .collect::<HashSet<_>>();
+    let mut manifest = Manifest {
+        named: farc.is_named_in_archive(),
+        metadata: Some(METADATA_FILE_NAME.to_string()),
+        ..Manifest::default()
+    };
+    for (position, index) in order.iter().enumerate() {
+        let entry = &table.entries[*index];
+        let hash = entry.name.hash();
+        let name = match &entry.name {
+            RawName::Name(name) => Some(name.clone()),
+            RawName::Hash(hash) => names.get(hash).cloned(),
+        };
+        let start = entry.start as usize;
+        let end = start + entry.length as usize;
+        let data = archive
+            .get(start..end)
+            .with_context(|| format!("the file {:08x} goes past the end of the archive", hash))?;
+        // the bytes up to the next file (ignoring the ones sharing this data), or the end of the archive
+        let next_start = order[position + 1..]
+            .iter()
+            .map(|next| table.entries[*next].start as usize)
+            .find(|next_start| *next_start > start)
+            .unwrap_or(archive.len());
+        let padding = archive.get(end..next_start.max(end)).unwrap_or(&[]);
+
+        let file_name = output_name(hash, name.as_deref(), &mut used);
+        write(args.output.join(&file_name), data)
+            .with_context(|| format!("can't write {:?}", file_name))?;
+        manifest.entries.push(ManifestEntry {
+            hash,
+            file_name,
+            name,
+            length: entry.length,
+            offset: Some(entry.start),
+            padding: Some(padding.to_vec()),
+        });
+    }
+
+    write(
+        args.output.join(METADATA_FILE_NAME),
+        &archive[..metadata_end],
+    )
+    .context("can't write the metadata")?;
+    let manifest_path = args.output.join(MANIFEST_FILE_NAME);
+    let manifest_file = File::create(&manifest_path)
+        .with_context(|| format!("can't create {:?}", manifest_path))?;
+    manifest
+        .write(BufWriter::new(manifest_file))
+        .context("can't write the manifest")?;
+    Ok(())
+}
+
+pub fn run_repack(args: RepackArgs) -> Result<()> {
+    let manifest = read_manifest(&args.input)?;
+    let metadata_name = manifest.metadata.as_ref().with_context(|| {
+        format!(
+            "{:?} wasn't created by unpack, use pack instead",
+            args.input
+        )
+    })?;
+    let mut metadata = read(args.input.join(metadata_name)).context("can't read the metadata")?;
+    let table = Table::read(&metadata)?;
+    let all_data_offset = table.header.data_offset as usize;
+    // the end of the last file of the original archive, with its padding
+    let mut original_length = metadata.len();
+
+    let mut data = Vec::new();
+    // the new position of each original offset, for files sharing their data
+    let mut moved = HashMap::new();
+    for entry in &manifest.entries {
+        let (original_offset, padding) = match (entry.offset, &entry.padding) {
+            (Some(offset), Some(padding)) => (offset as usize, padding),
+            _ => bail!(
+                "the manifest doesn't have the original offset and padding of {:?}",
+                entry.file_name
+            ),
+        };
+        original_length =
+            original_length.max(original_offset + entry.length as usize + padding.len());
+        let index = table
+            .entries
+            .iter()
+            .position(|raw| raw.name.hash() == entry.hash)
+            .with_context(|| {
+                format!(
+                    "the file {:08x} isn't in the file table of the metadata",
+                    entry.hash
+                )
+            })?;
+        let content = read(args.input.join(&entry.file_name))
+            .with_context(|| format!("can't read {:?}", entry.file_name))?;
+        let start = match moved.get(&original_offset) {
+            Some(start) => *start,
+            None => {
+                let start = metadata.len() + data.len();
+                moved.insert(original_offset, start);
+                data.extend_from_slice(&content);
+                if content.len() == entry.length as usize {
+                    data.extend_from_slice(padding);
+                } else {
+                    // the content changed, only keep the 16 bytes alignment
+                    let position = metadata.len() + data.len();
+                    data.resize(data.len() + (16 - position % 16) % 16, 0);
+                };
+                start
+            }
+        };
+        let record = table.record_position(index);
+        let relative_start = start
+            .checked_sub(all_data_offset)
+            .context("a file start before the data section")?;
+        write_u32_at(&mut metadata, record + 4, relative_start.try_into()?)?;
+        write_u32_at(&mut metadata, record + 8, content.len().try_into()?)?;
+    }
+
+    // the length of the data section change as much as the archive length
+    let new_length = metadata.len() + data.len();
+    let data_length =
+        i64::from(table.header.data_length) + new_length as i64 - original_length as i64;
+    write_u32_at(&mut metadata, 0x30, data_length.try_into()?)?;
+
+    metadata.extend_from_slice(&data);
+    write(&args.output, metadata).with_context(|| format!("can't write {:?}", args.output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::test_directory;
+    use pmd_farc::{hash_name, FarcWriter};
+    use std::io::Cursor;
+
+    fn archive() -> Vec<u8> {
+        let mut writer = FarcWriter::default();
+        writer.add_named_file("a.bin".to_string(), vec![1; 20]);
+        writer.add_named_file("b.bin".to_string(), vec![2; 40]);
+        let mut archive = Cursor::new(Vec::new());
+        writer.write_named(&mut archive).unwrap();
+        archive.into_inner()
+    }
+
+    #[test]
+    fn table_of_broken_archives() {
+        let archive = archive();
+        let table = Table::read(&archive).unwrap();
+        assert_eq!(table.entries.len(), 2);
+        let order = table.storage_order();
+        assert!(order
+            .windows(2)
+            .all(|pair| table.entries[pair[0]].start < table.entries[pair[1]].start));
+        assert!(Table::read(&archive[..0x20]).is_err());
+        assert!(Table::read(&archive[..table.offset + 6]).is_err());
+    }
+
+    #[test]
+    fn unpack_and_repack() {
+        let directory = test_directory("lossless");
+        let original = directory.join("original.bin");
+        let unpacked = directory.join("unpacked");
+        let repacked = directory.join("repacked.bin");
+        write(&original, archive()).unwrap();
+        run_unpack(UnpackArgs {
+            archive: original.clone(),
+            output: unpacked.clone(),
+        })
+        .unwrap();
+        run_repack(RepackArgs {
+            input: unpacked.clone(),
+            output: repacked.clone(),
+        })
+        .unwrap();
+        assert_eq!(read(&repacked).unwrap(), read(&original).unwrap());
+
+        // a longer file move the next ones
+        write(unpacked.join("a.bin"), vec![3; 50]).unwrap();
+        run_repack(RepackArgs {
+            input: unpacked.clone(),
+            output: repacked.clone(),
+        })
+        .unwrap();
+        let farc = open_farc(&repacked).unwrap();
+        assert_eq!(farc.read_named_file("a.bin").unwrap()[..], [3; 50][..]);
+        assert!(farc
+            .read_hashed_file(hash_name("b.bin"))
+            .unwrap()
+            .starts_with(&[2; 40]));
+
+        // pack must be used for a directory without the metadata
+        let mut manifest = read_manifest(&unpacked).unwrap();
+        manifest.metadata = None;
+        manifest
+            .write(File::create(unpacked.join(MANIFEST_FILE_NAME)).unwrap())
+            .unwrap();
+        assert!(run_repack(RepackArgs {
+            input: unpacked,
+            output: repacked,
+        })
+        .is_err());
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
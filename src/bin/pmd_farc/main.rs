@@ -0,0 +1,160 @@
+//! A command line tool to inspect, extract and create farc files
+#[macro_use]
+extern crate log;
+
+use anyhow::Result;
+use clap::{ArgAction, Parser, Subcommand};
+
+mod batch;
+#[cfg(feature = "tui")]
+mod browse;
+mod cat;
+mod checksum;
+mod common;
+mod convert;
+mod dedupe;
+mod dehash;
+mod diff;
+mod edit;
+mod export;
+mod extract;
+mod guess_names;
+mod hash;
+mod import;
+mod info;
+mod list;
+mod logger;
+mod lossless;
+mod manifest;
+mod pack;
+mod patch;
+mod rename;
+mod repair;
+mod stat;
+mod status;
+mod verify;
+#[cfg(feature = "watch")]
+mod watch;
+
+#[derive(Parser)]
+#[command(
+    name = "pmd_farc",
+    version,
+    about = "Inspect, extract and create FARC archives"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    /// Only show the errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Show more messages. Can be repeated (-vv) to also show the debug messages.
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+    /// The format of the messages, written to the standard error
+    #[arg(long, global = true, value_enum, default_value_t = logger::LogFormat::Text)]
+    log_format: logger::LogFormat,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the files contained in an archive
+    List(list::ListArgs),
+    /// Extract the files contained in an archive to a directory
+    Extract(extract::ExtractArgs),
+    /// Create an hash-indexed archive from the files of a directory
+    #[command(alias = "create")]
+    Pack(pack::PackArgs),
+    /// Find the names of the files of an archive, and print the hash to name mapping
+    Dehash(dehash::DehashArgs),
+    /// Convert an archive to a zip or tar file, with a manifest for the files whose name isn't known
+    Export(export::ExportArgs),
+    /// Create an hash-indexed archive from a zip or tar file with the layout created by export
+    Import(import::ImportArgs),
+    /// Create a patch between two versions of an archive, or apply it
+    Patch(patch::PatchArgs),
+    /// Print the files added, removed or modified between two archives
+    Diff(diff::DiffArgs),
+    /// Check the structure of an archive, and exit with an error if there is any problem
+    Verify(verify::VerifyArgs),
+    /// Recover the files of a damaged archive, like one truncated by an interrupted write, into a new archive
+    Repair(repair::RepairArgs),
+    /// Print the hash of file names, optionally checking them against an archive
+    Hash(hash::HashArgs),
+    /// Add a file to an hash-indexed archive
+    Add(edit::AddArgs),
+    /// Remove a file from an hash-indexed archive
+    Remove(edit::RemoveArgs),
+    /// Replace the content of a file of an hash-indexed archive
+    Replace(edit::ReplaceArgs),
+    /// Write the content of a file of an archive to the standard output
+    Cat(cat::CatArgs),
+    /// Print statistics about the size and naming of an archive
+    Stat(stat::StatArgs),
+    /// Browse an archive in an interactive terminal interface
+    #[cfg(feature = "tui")]
+    Browse(browse::BrowseArgs),
+    /// Run a command on every archive found in a directory, like the romfs of the game
+    Batch(batch::BatchArgs),
+    /// Extract an archive to a directory, recording its layout so repack can reproduce it exactly
+    Unpack(lossless::UnpackArgs),
+    /// Create an archive from a directory created by unpack, keeping the original layout
+    Repack(lossless::RepackArgs),
+    /// Print the sha256 of each file of an archive, as json
+    Checksum(checksum::ChecksumArgs),
+    /// Check the files of an archive against the output of checksum
+    Check(checksum::CheckArgs),
+    /// Report the files with identical content, optionally writing an archive where they share their data
+    Dedupe(dedupe::DedupeArgs),
+    /// Apply a hash to name mapping, and write the archive with the file names, with its .lst file
+    Rename(rename::RenameArgs),
+    /// Run the heuristic and brute force strategies, and print the guessed names with how much they can be trusted
+    GuessNames(guess_names::GuessNamesArgs),
+    /// Rewrite an archive in the hashed or named form
+    Convert(convert::ConvertArgs),
+    /// Print the files of an extracted directory that were modified, deleted or added since the extraction
+    Status(status::StatusArgs),
+    /// Rebuild an archive each time a file of its extracted directory change
+    #[cfg(feature = "watch")]
+    Watch(watch::WatchArgs),
+    /// Print general information about an archive
+    Info(info::InfoArgs),
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    logger::init(cli.quiet, cli.verbose, cli.log_format);
+    match cli.command {
+        Command::List(args) => list::run(args),
+        Command::Extract(args) => extract::run(args),
+        Command::Pack(args) => pack::run(args),
+        Command::Dehash(args) => dehash::run(args),
+        Command::Export(args) => export::run(args),
+        Command::Import(args) => import::run(args),
+        Command::Patch(args) => patch::run(args),
+        Command::Diff(args) => diff::run(args),
+        Command::Verify(args) => verify::run(args),
+        Command::Repair(args) => repair::run(args),
+        Command::Hash(args) => hash::run(args),
+        Command::Add(args) => edit::run_add(args),
+        Command::Remove(args) => edit::run_remove(args),
+        Command::Replace(args) => edit::run_replace(args),
+        Command::Cat(args) => cat::run(args),
+        Command::Stat(args) => stat::run(args),
+        #[cfg(feature = "tui")]
+        Command::Browse(args) => browse::run(args),
+        Command::Batch(args) => batch::run(args),
+        Command::Unpack(args) => lossless::run_unpack(args),
+        Command::Repack(args) => lossless::run_repack(args),
+        Command::Checksum(args) => checksum::run_checksum(args),
+        Command::Check(args) => checksum::run_check(args),
+        Command::Dedupe(args) => dedupe::run(args),
+        Command::Rename(args) => rename::run(args),
+        Command::GuessNames(args) => guess_names::run(args),
+        Command::Convert(args) => convert::run(args),
+        Command::Status(args) => status::run(args),
+        #[cfg(feature = "watch")]
+        Command::Watch(args) => watch::run(args),
+        Command::Info(args) => info::run(args),
+    }
+}
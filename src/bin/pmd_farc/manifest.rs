@@ -0,0 +1,111 @@
+use crate::common::parse_hash;
+use anyhow::{Context, Result};
+use pmd_farc::{Manifest, ManifestEntry};
+use std::collections::BTreeMap;
+use std::fs::{read_to_string, File};
+use std::io::BufReader;
+use std::path::Path;
+
+/// The name of the manifest file, in an extracted directory. See [`Manifest`] for its format.
+pub const MANIFEST_FILE_NAME: &str = "manifest.txt";
+
+/// The name of the manifest written by older versions of extract. It is only read if there isn't a
+/// [`MANIFEST_FILE_NAME`] file, and is never packed.
+pub const LEGACY_MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// Return true if a file with this name is a manifest, and not a file to pack
+pub fn is_manifest(file_name: &str) -> bool {
+    file_name == MANIFEST_FILE_NAME || file_name == LEGACY_MANIFEST_FILE_NAME
+}
+
+/// Parse a manifest written by older versions of extract: a json object, with the file names as key, and the
+/// hexadecimal hashes (as string) as value. It doesn't store the length or the order of the files, so the entries
+/// are sorted by file name, with a length of 0, and the default alignment.
+pub fn parse_legacy_manifest(content: &str) -> Result<Manifest> {
+    let raw: BTreeMap<String, String> = serde_json::from_str(content)?;
+    let entries = raw
+        .into_iter()
+        .map(|(file_name, hash)| {
+            let hash =
+                parse_hash(&hash).with_context(|| format!("invalid hash for {:?}", file_name))?;
+            Ok(ManifestEntry {
+                hash,
+                file_name,
+                name: None,
+                length: 0,
+                offset: None,
+                padding: None,
+            })
+        })
+        .collect::<Result<_>>()?;
+    Ok(Manifest {
+        entries,
+        ..Manifest::default()
+    })
+}
+
+/// Read the manifest of a directory, falling back to the legacy manifest.json. Return an empty manifest if there
+/// isn't one.
+pub fn read_manifest(directory: &Path) -> Result<Manifest> {
+    let path = directory.join(MANIFEST_FILE_NAME);
+    if path.is_file() {
+        let file = File::open(&path).with_context(|| format!("can't read {:?}", path))?;
+        return Manifest::read(BufReader::new(file))
+            .with_context(|| format!("can't parse {:?}", path));
+    };
+    let legacy_path = directory.join(LEGACY_MANIFEST_FILE_NAME);
+    if legacy_path.is_file() {
+        let content = read_to_string(&legacy_path)
+            .with_context(|| format!("can't read {:?}", legacy_path))?;
+        return parse_legacy_manifest(&content)
+            .with_context(|| format!("can't parse {:?}", legacy_path));
+    };
+    Ok(Manifest::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::test_directory;
+    use std::fs::write;
+
+    #[test]
+    fn legacy_manifest() {
+        let manifest = parse_legacy_manifest(r#"{"b.bin": "0x2", "a.bin": "1"}"#).unwrap();
+        let entries = manifest
+            .entries
+            .iter()
+            .map(|entry| (entry.file_name.as_str(), entry.hash, entry.offset))
+            .collect::<Vec<_>>();
+        assert_eq!(entries, vec![("a.bin", 1, None), ("b.bin", 2, None)]);
+        assert_eq!(manifest.alignment, 16);
+
+        assert!(parse_legacy_manifest(r#"{"a.bin": "xyz"}"#).is_err());
+        assert!(parse_legacy_manifest(r#"["a.bin"]"#).is_err());
+    }
+
+    #[test]
+    fn read_manifest_fallback() {
+        let directory = test_directory("read_manifest");
+        assert_eq!(read_manifest(&directory).unwrap(), Manifest::default());
+
+        write(
+            directory.join(LEGACY_MANIFEST_FILE_NAME),
+            r#"{"a.bin": "1"}"#,
+        )
+        .unwrap();
+        assert_eq!(read_manifest(&directory).unwrap().entries[0].hash, 1);
+
+        // the new manifest is used if both exists
+        write(
+            directory.join(MANIFEST_FILE_NAME),
+            "FARC manifest 1\n0x00000002\t16\ta.bin\n",
+        )
+        .unwrap();
+        assert_eq!(read_manifest(&directory).unwrap().entries[0].hash, 2);
+
+        write(directory.join(MANIFEST_FILE_NAME), "not a manifest\n").unwrap();
+        assert!(read_manifest(&directory).is_err());
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,146 @@
+use crate::common::parse_placeholder_name;
+use crate::manifest::{is_manifest, read_manifest};
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use pmd_farc::{hash_name, FarcWriter, Manifest};
+use std::collections::HashMap;
+use std::fs::{read, read_dir, File};
+use std::io::{BufWriter, Seek, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Args)]
+pub struct PackArgs {
+    /// The directory containing the files to add.
+    ///
+    /// The hash of a file is taken from the manifest.txt file (or the older manifest.json) if it is listed there,
+    /// from its name if it is a placeholder like 0x1234abcd.bin, and is otherwise the hash of its name.
+    input: PathBuf,
+    /// The archive to create
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+/// Return the hash a file with this name should have in the archive: the one in the manifest, the one of a
+/// placeholder name, or the hash of its name.
+pub fn file_hash(manifest: &Manifest, file_name: &str) -> u32 {
+    manifest
+        .hash_of(file_name)
+        .or_else(|| parse_placeholder_name(file_name))
+        .unwrap_or_else(|| hash_name(file_name))
+}
+
+/// Return the hash and the path of every file of the directory, sorted by path. The hash comes from the manifest,
+/// from a placeholder name, or from hashing the file name (see the pack command).
+pub fn directory_files(directory: &Path) -> Result<Vec<(u32, PathBuf)>> {
+    let manifest = read_manifest(directory)?;
+    let mut added: HashMap<u32, String> = HashMap::new();
+    let mut entries = read_dir(directory)
+        .with_context(|| format!("can't read {:?}", directory))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+    let mut result = Vec::new();
+    for path in entries {
+        if !path.is_file() {
+            continue;
+        };
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .with_context(|| format!("the file name of {:?} isn't valid unicode", path))?;
+        // the metadata of an unpacked directory is only used by repack
+        if is_manifest(file_name) || manifest.metadata.as_deref() == Some(file_name) {
+            continue;
+        };
+        let hash = file_hash(&manifest, file_name);
+        if let Some(other) = added.insert(hash, file_name.to_string()) {
+            bail!(
+                "{:?} and {:?} both have the hash {:08x}",
+                other,
+                file_name,
+                hash
+            );
+        };
+        result.push((hash, path));
+    }
+    Ok(result)
+}
+
+/// Use the alignment and the storage order of the manifest for the archive written by ``writer``
+pub fn apply_layout(writer: &mut FarcWriter, manifest: &Manifest) -> Result<()> {
+    writer
+        .set_alignment(manifest.alignment)
+        .context("the alignment of the manifest is invalid")?;
+    writer.set_order(manifest.entries.iter().map(|entry| entry.hash));
+    Ok(())
+}
+
+/// Return the name a file should have in a named archive: the one in the manifest, or its file name if its hash
+/// match
+fn archive_name(manifest: &Manifest, hash: u32, path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_str()?;
+    match manifest.get(file_name).and_then(|entry| entry.name.clone()) {
+        Some(name) => Some(name),
+        None if hash_name(file_name) == hash => Some(file_name.to_string()),
+        None => None,
+    }
+}
+
+/// Create the writer of the archive of ``directory``, from ``files`` (as returned by [`directory_files`]) whose
+/// content is read with ``read_file``, in the layout of the manifest: with the file names if the extracted archive
+/// had them and they are all known, and with its alignment and storage order. Return it with whether it should be
+/// written with the file names.
+pub fn archive_writer(
+    directory: &Path,
+    files: &[(u32, PathBuf)],
+    read_file: &mut dyn FnMut(&Path) -> Result<Vec<u8>>,
+) -> Result<(FarcWriter, bool)> {
+    let manifest = read_manifest(directory)?;
+    let names = if manifest.named {
+        files
+            .iter()
+            .map(|(hash, path)| archive_name(&manifest, *hash, path))
+            .collect::<Option<Vec<_>>>()
+    } else {
+        None
+    };
+    if manifest.named && names.is_none() {
+        warn!("the name of some files isn't known, the archive will only contain their hash");
+    };
+
+    let mut writer = FarcWriter::default();
+    apply_layout(&mut writer, &manifest)?;
+    for (index, (hash, path)) in files.iter().enumerate() {
+        let content = read_file(path)?;
+        match &names {
+            Some(names) => writer.add_named_file(names[index].clone(), content),
+            None => writer.add_hashed_file(*hash, content),
+        };
+    }
+    Ok((writer, names.is_some()))
+}
+
+/// Write the archive created by [`archive_writer`], with the file names if ``named``
+pub fn write_archive<W: Write + Seek>(
+    writer: &FarcWriter,
+    named: bool,
+    file: &mut W,
+) -> Result<()> {
+    if named {
+        writer.write_named(file)?;
+    } else {
+        writer.write_hashed(file)?;
+    };
+    Ok(())
+}
+
+pub fn run(args: PackArgs) -> Result<()> {
+    let files = directory_files(&args.input)?;
+    let (writer, named) = archive_writer(&args.input, &files, &mut |path| {
+        read(path).with_context(|| format!("can't read {:?}", path))
+    })?;
+    let mut output_file = BufWriter::new(
+        File::create(&args.output).with_context(|| format!("can't create {:?}", args.output))?,
+    );
+    write_archive(&writer, named, &mut output_file)
+}
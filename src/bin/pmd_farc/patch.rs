@@ -0,0 +1,84 @@
+use crate::common::open_farc;
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use pmd_farc::{FarcPatch, PatchOperation};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct PatchArgs {
+    #[command(subcommand)]
+    command: PatchCommand,
+}
+
+#[derive(Subcommand)]
+enum PatchCommand {
+    /// Create a patch transforming the original archive into the modified one
+    Create {
+        /// The original archive
+        original: PathBuf,
+        /// The modified archive
+        modified: PathBuf,
+        /// The patch file to create
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Apply a patch to the original archive, and write the modified archive
+    Apply {
+        /// The original archive
+        archive: PathBuf,
+        /// The patch to apply
+        patch: PathBuf,
+        /// The modified archive to create
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+}
+
+pub fn run(args: PatchArgs) -> Result<()> {
+    match args.command {
+        PatchCommand::Create {
+            original,
+            modified,
+            output,
+        } => {
+            let patch = FarcPatch::create(&open_farc(&original)?, &open_farc(&modified)?)?;
+            let (mut added, mut removed, mut modified) = (0, 0, 0);
+            for entry in &patch.entries {
+                match entry.operation {
+                    PatchOperation::Add(_) => added += 1,
+                    PatchOperation::Remove => removed += 1,
+                    PatchOperation::Modify { .. } => modified += 1,
+                };
+            }
+            let mut output_file = BufWriter::new(
+                File::create(&output).with_context(|| format!("can't create {:?}", output))?,
+            );
+            patch.write(&mut output_file)?;
+            output_file.flush()?;
+            info!(
+                "{} files added, {} removed, {} modified",
+                added, removed, modified
+            );
+        }
+        PatchCommand::Apply {
+            archive,
+            patch,
+            output,
+        } => {
+            let patch_file =
+                File::open(&patch).with_context(|| format!("can't open {:?}", patch))?;
+            let patch = FarcPatch::read(&mut BufReader::new(patch_file))
+                .with_context(|| format!("can't read the patch {:?}", patch))?;
+            let writer = patch
+                .apply(&open_farc(&archive)?)
+                .with_context(|| format!("can't apply the patch to {:?}", archive))?;
+            let mut output_file = BufWriter::new(
+                File::create(&output).with_context(|| format!("can't create {:?}", output))?,
+            );
+            writer.write_hashed(&mut output_file)?;
+        }
+    };
+    Ok(())
+}
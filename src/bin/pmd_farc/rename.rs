@@ -0,0 +1,74 @@
+use crate::common::open_farc;
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use pmd_farc::{message_dehash, FarcWriter, NameList};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct RenameArgs {
+    /// The archive to rename the files of
+    archive: PathBuf,
+    /// The hash to name mapping, in the tab-separated format of NameList (like the output of dehash)
+    mapping: PathBuf,
+    /// The name-indexed archive to create
+    #[arg(short, long)]
+    output: PathBuf,
+    /// The .lst file to create. By default, it is next to the output, named like the game does.
+    #[arg(long)]
+    lst: Option<PathBuf>,
+    /// The directory written before each name in the .lst file
+    #[arg(long, default_value = "")]
+    lst_directory: String,
+}
+
+pub fn run(args: RenameArgs) -> Result<()> {
+    let mut farc = open_farc(&args.archive)?;
+    let mapping_file =
+        File::open(&args.mapping).with_context(|| format!("can't open {:?}", args.mapping))?;
+    let name_list = NameList::parse(&mut BufReader::new(mapping_file))
+        .with_context(|| format!("can't read {:?}", args.mapping))?;
+    let report = name_list.apply(&mut farc);
+    for entry in &report.mismatched {
+        warn!(
+            "{:?} doesn't have the hash given in the mapping ({:08x?})",
+            entry.name, entry.hash
+        );
+    }
+    let unknown = farc.file_unknown_name();
+    if unknown > 0 {
+        bail!(
+            "{} files still have an unknown name, the archive can't be written with the file names",
+            unknown
+        );
+    };
+
+    let writer = FarcWriter::new_from_farc(&farc)?;
+    let mut output_file = BufWriter::new(
+        File::create(&args.output).with_context(|| format!("can't create {:?}", args.output))?,
+    );
+    writer.write_named(&mut output_file)?;
+    output_file.flush()?;
+
+    let lst_path = match args.lst {
+        Some(lst_path) => lst_path,
+        None => {
+            let output_name = args
+                .output
+                .file_name()
+                .and_then(|name| name.to_str())
+                .context("the output file name isn't valid unicode")?;
+            args.output.with_file_name(
+                message_dehash::get_file_name(output_name)
+                    .context("can't deduce the name of the .lst file")?,
+            )
+        }
+    };
+    let mut lst_file = BufWriter::new(
+        File::create(&lst_path).with_context(|| format!("can't create {:?}", lst_path))?,
+    );
+    message_dehash::write_list_file(&farc, &args.lst_directory, &mut lst_file)?;
+    lst_file.flush()?;
+    Ok(())
+}
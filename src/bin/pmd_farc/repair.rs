@@ -0,0 +1,34 @@
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use pmd_farc::repair_farc;
+use std::fs::{read, File};
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct RepairArgs {
+    /// The damaged archive
+    archive: PathBuf,
+    /// The repaired archive to write
+    output: PathBuf,
+}
+
+pub fn run(args: RepairArgs) -> Result<()> {
+    let archive = read(&args.archive).with_context(|| format!("can't read {:?}", args.archive))?;
+    let repaired =
+        repair_farc(&archive).with_context(|| format!("can't repair {:?}", args.archive))?;
+    for action in &repaired.report.actions {
+        eprintln!("{}: {}", args.archive.display(), action);
+    }
+    let mut output = BufWriter::new(
+        File::create(&args.output).with_context(|| format!("can't create {:?}", args.output))?,
+    );
+    repaired
+        .write(&mut output)
+        .with_context(|| format!("can't write {:?}", args.output))?;
+    eprintln!("recovered {} files", repaired.report.recovered);
+    if !repaired.report.is_lossless() {
+        bail!("some content of {:?} was lost", args.archive);
+    };
+    Ok(())
+}
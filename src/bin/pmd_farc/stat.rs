@@ -0,0 +1,75 @@
+use crate::common::{open_farc, placeholder_name};
+use anyhow::{Context, Result};
+use clap::Args;
+use std::fs::metadata;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct StatArgs {
+    /// The archive to analyze
+    archive: PathBuf,
+    /// The number of largest files to print
+    #[arg(long, default_value_t = 5)]
+    top: usize,
+}
+
+pub fn run(args: StatArgs) -> Result<()> {
+    let farc = open_farc(&args.archive)?;
+    let archive_size = metadata(&args.archive)
+        .with_context(|| format!("can't read the metadata of {:?}", args.archive))?
+        .len();
+
+    let mut entries = farc.iter_files().collect::<Vec<_>>();
+    entries.sort_unstable_by_key(|entry| entry.start);
+    let data_size = entries
+        .iter()
+        .map(|entry| u64::from(entry.length))
+        .sum::<u64>();
+    // the unused bytes between two consecutive files, and after the last one
+    let mut padding = 0;
+    let mut previous_end = None;
+    for entry in &entries {
+        if let Some(previous_end) = previous_end {
+            padding += u64::from(entry.start).saturating_sub(previous_end);
+        };
+        previous_end = Some(u64::from(entry.start) + u64::from(entry.length));
+    }
+    if let Some(previous_end) = previous_end {
+        padding += archive_size.saturating_sub(previous_end);
+    };
+
+    println!("archive: {}", args.archive.display());
+    println!("archive size: {} bytes", archive_size);
+    println!("files: {}", farc.file_count());
+    let coverage = farc.coverage();
+    println!(
+        "named: {}/{} ({:.2}%)",
+        coverage.named,
+        coverage.total,
+        coverage.percentage()
+    );
+    if coverage.unverified > 0 {
+        println!("unverified names: {}", coverage.unverified);
+    };
+    println!("data size: {} bytes", data_size);
+    println!("padding: {} bytes", padding);
+    if let Some(first) = entries.first() {
+        println!(
+            "metadata: {} bytes (before the first file)",
+            u64::from(first.start)
+        );
+    };
+
+    entries.sort_unstable_by(|a, b| b.length.cmp(&a.length).then(a.name_hash.cmp(&b.name_hash)));
+    println!("largest files:");
+    for entry in entries.iter().take(args.top) {
+        println!(
+            "  {:>10} {}",
+            entry.length,
+            entry
+                .name()
+                .map_or_else(|| placeholder_name(entry.name_hash), str::to_string)
+        );
+    }
+    Ok(())
+}
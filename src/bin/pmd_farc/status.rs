@@ -0,0 +1,54 @@
+use crate::common::{open_farc, read_subfile, safe_name, sorted_entries};
+use crate::pack::directory_files;
+use anyhow::{Context, Result};
+use clap::Args;
+use std::collections::HashMap;
+use std::fs::read;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct StatusArgs {
+    /// The original archive
+    archive: PathBuf,
+    /// The directory the archive was extracted to, and that may have been modified since
+    directory: PathBuf,
+}
+
+pub fn run(args: StatusArgs) -> Result<()> {
+    let farc = open_farc(&args.archive)?;
+    let mut files = directory_files(&args.directory)?
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+
+    let mut changes = 0;
+    for entry in sorted_entries(&farc) {
+        let name = safe_name(entry.name_hash, entry.name());
+        match files.remove(&entry.name_hash) {
+            None => println!("deleted:  {}", name),
+            Some(path) => {
+                let content = read(&path).with_context(|| format!("can't read {:?}", path))?;
+                if content == read_subfile(&farc, entry.name_hash)? {
+                    continue;
+                };
+                println!(
+                    "modified: {}",
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                );
+            }
+        };
+        changes += 1;
+    }
+    let mut new_files = files.into_values().collect::<Vec<_>>();
+    new_files.sort();
+    for path in &new_files {
+        println!(
+            "new:      {}",
+            path.file_name().unwrap_or_default().to_string_lossy()
+        );
+        changes += 1;
+    }
+    if changes == 0 {
+        println!("no changes");
+    };
+    Ok(())
+}
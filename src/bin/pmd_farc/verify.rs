@@ -0,0 +1,33 @@
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use pmd_farc::validate_farc;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    /// The archive to check
+    archive: PathBuf,
+}
+
+pub fn run(args: VerifyArgs) -> Result<()> {
+    let file =
+        File::open(&args.archive).with_context(|| format!("can't open {:?}", args.archive))?;
+    let problems = validate_farc(BufReader::new(file))
+        .with_context(|| format!("can't read {:?}", args.archive))?;
+    if problems.is_empty() {
+        println!("{}: ok", args.archive.display());
+        return Ok(());
+    };
+    for problem in &problems {
+        let mut message = problem.to_string();
+        let mut source = std::error::Error::source(problem);
+        while let Some(cause) = source {
+            message.push_str(&format!(": {}", cause));
+            source = cause.source();
+        }
+        eprintln!("{}: {}", args.archive.display(), message);
+    }
+    bail!("found {} problems in {:?}", problems.len(), args.archive);
+}
@@ -0,0 +1,162 @@
+use crate::pack::{archive_writer, directory_files, write_archive};
+use anyhow::{Context, Result};
+use clap::Args;
+use notify::{RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::fs::{metadata, read, rename, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use std::time::{Duration, SystemTime};
+
+/// How long to wait for other changes before rebuilding, as editors often write a file in multiple steps
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Args)]
+pub struct WatchArgs {
+    /// The directory to watch, in the layout used by pack
+    input: PathBuf,
+    /// The archive to rebuild
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+/// Keep the content of the files between rebuilds, so only the modified files are read again
+#[derive(Default)]
+struct FileCache {
+    files: HashMap<PathBuf, (SystemTime, u64, Vec<u8>)>,
+}
+
+impl FileCache {
+    fn get(&mut self, path: &Path) -> Result<Vec<u8>> {
+        let file_metadata = metadata(path).with_context(|| format!("can't read {:?}", path))?;
+        let key = (file_metadata.modified()?, file_metadata.len());
+        if let Some((modified, length, content)) = self.files.get(path) {
+            if (*modified, *length) == key {
+                return Ok(content.clone());
+            };
+        };
+        let content = read(path).with_context(|| format!("can't read {:?}", path))?;
+        self.files
+            .insert(path.to_path_buf(), (key.0, key.1, content.clone()));
+        Ok(content)
+    }
+}
+
+fn rebuild(
+    args: &WatchArgs,
+    cache: &mut FileCache,
+    is_output: &dyn Fn(&Path) -> bool,
+) -> Result<usize> {
+    let mut files = directory_files(&args.input)?;
+    files.retain(|(_, path)| !is_output(path));
+    // the same layout as pack: named or hashed, alignment and order from the manifest
+    let (writer, named) = archive_writer(&args.input, &files, &mut |path| cache.get(path))?;
+    // write to a temporary file first, so the emulator never see a partially written archive
+    let mut temporary_name = args.output.as_os_str().to_owned();
+    temporary_name.push(".tmp");
+    let temporary_path = PathBuf::from(temporary_name);
+    let mut output_file = BufWriter::new(
+        File::create(&temporary_path)
+            .with_context(|| format!("can't create {:?}", temporary_path))?,
+    );
+    write_archive(&writer, named, &mut output_file)?;
+    output_file.flush()?;
+    drop(output_file);
+    rename(&temporary_path, &args.output)
+        .with_context(|| format!("can't move {:?} to {:?}", temporary_path, args.output))?;
+    Ok(files.len())
+}
+
+fn rebuild_and_report(args: &WatchArgs, cache: &mut FileCache, is_output: &dyn Fn(&Path) -> bool) {
+    match rebuild(args, cache, is_output) {
+        Ok(count) => info!("rebuilt {} with {} files", args.output.display(), count),
+        Err(err) => error!("can't rebuild {}: {:#}", args.output.display(), err),
+    };
+}
+
+pub fn run(args: WatchArgs) -> Result<()> {
+    let output_directory = std::path::absolute(&args.output)?
+        .parent()
+        .and_then(|parent| parent.canonicalize().ok());
+    let output_name = args
+        .output
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    // the archive itself (and its temporary file) may be in the watched directory
+    let is_output = |path: &Path| {
+        path.parent().and_then(|parent| parent.canonicalize().ok()) == output_directory
+            && path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with(&output_name))
+    };
+    let mut cache = FileCache::default();
+    rebuild(&args, &mut cache, &is_output)?;
+    eprintln!("watching {}, press Ctrl-C to stop", args.input.display());
+
+    let (sender, receiver) = channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    watcher.watch(&args.input, RecursiveMode::NonRecursive)?;
+    let is_relevant = |event: &notify::Result<notify::Event>| match event {
+        // reading the files to rebuild the archive create access events, which are ignored
+        Ok(event) => !event.kind.is_access() && event.paths.iter().any(|path| !is_output(path)),
+        Err(err) => {
+            warn!("error while watching {}: {}", args.input.display(), err);
+            false
+        }
+    };
+    while let Ok(event) = receiver.recv() {
+        if !is_relevant(&event) {
+            continue;
+        };
+        // wait for the other changes of the same save
+        while receiver.recv_timeout(DEBOUNCE).is_ok() {}
+        rebuild_and_report(&args, &mut cache, &is_output);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::test_directory;
+    use crate::manifest::MANIFEST_FILE_NAME;
+    use pmd_farc::{hash_name, Farc};
+    use std::fs::write;
+
+    #[test]
+    fn rebuild_with_the_manifest_layout() {
+        let input = test_directory("watch_layout");
+        write(input.join("a.bin"), [1; 20]).unwrap();
+        write(input.join("b.bin"), [2; 20]).unwrap();
+        write(
+            input.join(MANIFEST_FILE_NAME),
+            format!(
+                "FARC manifest 1\nnamed\ttrue\nalignment\t64\n0x{:08x}\t64\tb.bin\tb.bin\n0x{:08x}\t64\ta.bin\ta.bin\n",
+                hash_name("b.bin"),
+                hash_name("a.bin")
+            ),
+        )
+        .unwrap();
+        let args = WatchArgs {
+            input,
+            output: test_directory("watch_layout_output").join("archive.bin"),
+        };
+        assert_eq!(
+            rebuild(&args, &mut FileCache::default(), &|_| false).unwrap(),
+            2
+        );
+
+        let farc = Farc::new(File::open(&args.output).unwrap()).unwrap();
+        assert!(farc.is_named_in_archive());
+        let start = |name| {
+            farc.iter_files()
+                .find(|file| file.name() == Some(name))
+                .unwrap()
+                .start
+        };
+        assert!(start("b.bin") < start("a.bin"));
+        assert_eq!(start("a.bin") - start("b.bin"), 64);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::{DehashError, DehashInput, Dehasher};
+use std::collections::HashSet;
+
+fn is_file_name_char(chara: u8) -> bool {
+    chara.is_ascii_alphanumeric() || b"_-./".contains(&chara)
+}
+
+/// Keep the strings that look like a file name with one of the extensions (any extension if the list is empty),
+/// removing their directory
+fn filter_file_names(strings: Vec<String>, extensions: &[&str], result: &mut Vec<String>) {
+    let mut seen = result.iter().cloned().collect::<HashSet<_>>();
+    for string in strings {
+        let file_name = string.rsplit('/').next().unwrap_or(&string);
+        let extension = match file_name.rfind('.') {
+            Some(dot) if dot > 0 && dot + 1 < file_name.len() => &file_name[dot + 1..],
+            _ => continue,
+        };
+        if !extensions.is_empty()
+            && !extensions
+                .iter()
+                .any(|wanted| wanted.eq_ignore_ascii_case(extension))
+        {
+            continue;
+        };
+        if seen.insert(file_name.to_string()) {
+            result.push(file_name.to_string());
+        };
+    }
+}
+
+/// Return every run of at least ``min_len`` file-name-like characters, encoded as ascii, or as utf-16 (little
+/// endian) at the given alignment
+fn scan_runs(data: &[u8], utf16_alignment: Option<usize>, min_len: usize) -> Vec<String> {
+    let mut runs = Vec::new();
+    let mut current = String::new();
+    let mut flush = |current: &mut String| {
+        if current.len() >= min_len {
+            runs.push(current.clone());
+        };
+        current.clear();
+    };
+    match utf16_alignment {
+        None => {
+            for chara in data {
+                if is_file_name_char(*chara) {
+                    current.push(*chara as char);
+                } else {
+                    flush(&mut current);
+                }
+            }
+        }
+        Some(alignment) => {
+            for pair in data.get(alignment..).unwrap_or(&[]).chunks_exact(2) {
+                if pair[1] == 0 && is_file_name_char(pair[0]) {
+                    current.push(pair[0] as char);
+                } else {
+                    flush(&mut current);
+                }
+            }
+        }
+    }
+    flush(&mut current);
+    runs
+}
+
+/// Return the strings (ascii or utf-16) contained in an arbitrary binary file (like the code of the game) that look
+/// like file names with one of the given extensions (any extension if the list is empty). The directory of the
+/// found paths is removed.
+///
+/// # Example
+/// ```
+/// use pmd_farc::extract_file_names;
+/// let mut code = b"\x01\x02rom:/message/script_001.bin\0\xFFother\xFF".to_vec();
+/// code.extend("model.bgrs".encode_utf16().flat_map(|c| c.to_le_bytes().to_vec()));
+/// assert_eq!(
+///     extract_file_names(&code, &["bin", "bgrs"]),
+///     vec!["script_001.bin", "model.bgrs"]
+/// );
+/// ```
+#[must_use]
+pub fn extract_file_names(data: &[u8], extensions: &[&str]) -> Vec<String> {
+    let mut result = Vec::new();
+    for utf16_alignment in &[None, Some(0), Some(1)] {
+        filter_file_names(
+            scan_runs(data, *utf16_alignment, 3),
+            extensions,
+            &mut result,
+        );
+    }
+    result
+}
+
+#[derive(Debug, Clone, Default)]
+/// A [`Dehasher`] that try the file names found in a binary file with [`extract_file_names`]
+pub struct BinaryStringDehasher {
+    names: Vec<String>,
+}
+
+impl BinaryStringDehasher {
+    /// Create a new [`BinaryStringDehasher`], with the file names found in the binary file
+    #[must_use]
+    pub fn new(data: &[u8], extensions: &[&str]) -> Self {
+        Self {
+            names: extract_file_names(data, extensions),
+        }
+    }
+}
+
+impl Dehasher for BinaryStringDehasher {
+    fn name(&self) -> &str {
+        "binary strings"
+    }
+
+    fn find_names(&self, _input: &DehashInput) -> Result<Vec<String>, DehashError> {
+        Ok(self.names.clone())
+    }
+}
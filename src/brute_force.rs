@@ -0,0 +1,376 @@
+use crate::clock::Stopwatch;
+use crate::raw::string_to_utf16;
+use crate::{DehashError, DehashInput, Dehasher};
+use crc::crc32::{update, IEEE_TABLE};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A position in the enumeration of a [`BruteForce`]. Every candidate before it have been tried.
+pub struct BruteForcePosition {
+    /// The number of character generated between the prefix and the suffix
+    pub length: usize,
+    /// The index of the candidate among the candidates of this length
+    pub index: u64,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The progress of a running [`BruteForce`], given to the progress callback
+pub struct BruteForceProgress {
+    /// The position the brute force can be resumed from, with [`BruteForce::run_from`]
+    pub position: BruteForcePosition,
+    /// The number of candidates tried since the start of this run
+    pub candidates_tried: u64,
+    /// The number of names found since the start of this run
+    pub names_found: usize,
+    /// The average number of candidates tried per second since the start of this run
+    pub candidates_per_second: f64,
+}
+
+#[derive(Debug, Clone)]
+/// Try every combination of characters of a charset (between a prefix and a suffix) against a set of hashes.
+///
+/// With the ``rayon`` feature, candidates are tried in parallel.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, BruteForce};
+/// use std::collections::HashSet;
+/// let mut brute_force = BruteForce::new("0123456789", 3, 3);
+/// brute_force.set_prefix("script_");
+/// brute_force.set_suffix(".bin");
+/// let unknown_hashes = [hash_name("script_042.bin")].iter().copied().collect::<HashSet<_>>();
+/// let found = brute_force.run(&unknown_hashes, &|_progress| ());
+/// assert_eq!(found, vec![(hash_name("script_042.bin"), "script_042.bin".to_string())]);
+/// ```
+pub struct BruteForce {
+    charset: Vec<char>,
+    min_length: usize,
+    max_length: usize,
+    prefix: String,
+    suffix: String,
+    chunk_size: u64,
+}
+
+impl BruteForce {
+    /// Create a new [`BruteForce`] generating every string of the given characters with a length between
+    /// ``min_length`` and ``max_length`` (inclusive)
+    #[must_use]
+    pub fn new(charset: &str, min_length: usize, max_length: usize) -> Self {
+        let mut charset = charset.chars().collect::<Vec<_>>();
+        charset.sort_unstable();
+        charset.dedup();
+        Self {
+            charset,
+            min_length,
+            max_length,
+            prefix: String::new(),
+            suffix: String::new(),
+            chunk_size: 0x10000,
+        }
+    }
+
+    /// Set the string placed before each generated string
+    pub fn set_prefix(&mut self, prefix: &str) {
+        self.prefix = prefix.to_string();
+    }
+
+    /// Set the string placed after each generated string (usually the extension)
+    pub fn set_suffix(&mut self, suffix: &str) {
+        self.suffix = suffix.to_string();
+    }
+
+    /// Set the number of candidates tried between two calls of the progress callback, per thread. Default to 65536.
+    pub fn set_chunk_size(&mut self, chunk_size: u64) {
+        self.chunk_size = chunk_size.max(1);
+    }
+
+    /// Return the number of candidates of the given length (saturating to ``u64::MAX``)
+    #[must_use]
+    pub fn candidate_count_for_length(&self, length: usize) -> u64 {
+        let mut count: u64 = 1;
+        for _ in 0..length {
+            count = count.saturating_mul(self.charset.len() as u64);
+        }
+        count
+    }
+
+    /// Return the total number of candidates (saturating to ``u64::MAX``)
+    #[must_use]
+    pub fn candidate_count(&self) -> u64 {
+        (self.min_length..=self.max_length)
+            .map(|length| self.candidate_count_for_length(length))
+            .fold(0, u64::saturating_add)
+    }
+
+    /// Return the candidate at the given position
+    #[must_use]
+    pub fn candidate_at(&self, position: BruteForcePosition) -> String {
+        let mut generated = vec![' '; position.length];
+        let mut remaining = position.index;
+        let charset_len = self.charset.len() as u64;
+        for chara in generated.iter_mut().rev() {
+            *chara = self.charset[(remaining % charset_len) as usize];
+            remaining /= charset_len;
+        }
+        let mut result = self.prefix.clone();
+        result.extend(generated);
+        result.push_str(&self.suffix);
+        result
+    }
+
+    /// Try the candidates of the given length between ``start`` (inclusive) and ``end`` (exclusive).
+    ///
+    /// Candidates are enumerated like an odometer, and the crc32 state after each generated character is kept, so
+    /// only the characters that changed (and the suffix) need to be hashed for each candidate.
+    fn try_range(
+        &self,
+        length: usize,
+        start: u64,
+        end: u64,
+        unknown_hashes: &HashSet<u32>,
+    ) -> Vec<(u32, String)> {
+        let mut found = Vec::new();
+        if start >= end {
+            return found;
+        };
+        let charset_utf16 = self
+            .charset
+            .iter()
+            .map(|chara| string_to_utf16(&chara.to_string()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let suffix_utf16 = string_to_utf16(&self.suffix).collect::<Vec<_>>();
+        let charset_len = self.charset.len() as u64;
+
+        let mut digits = vec![0; length];
+        let mut remaining = start;
+        for digit in digits.iter_mut().rev() {
+            *digit = (remaining % charset_len) as usize;
+            remaining /= charset_len;
+        }
+        let mut states = vec![
+            update(
+                0,
+                &IEEE_TABLE,
+                &string_to_utf16(&self.prefix).collect::<Vec<_>>()
+            );
+            length + 1
+        ];
+        let mut first_changed = 0;
+
+        for index in start..end {
+            for position in first_changed..length {
+                states[position + 1] = update(
+                    states[position],
+                    &IEEE_TABLE,
+                    &charset_utf16[digits[position]],
+                );
+            }
+            let hash = update(states[length], &IEEE_TABLE, &suffix_utf16);
+            if unknown_hashes.contains(&hash) {
+                let candidate = self.candidate_at(BruteForcePosition { length, index });
+                debug!("found the name {} by brute force", candidate);
+                found.push((hash, candidate));
+            };
+
+            // increment the odometer
+            first_changed = length;
+            while first_changed > 0 {
+                first_changed -= 1;
+                digits[first_changed] += 1;
+                if digits[first_changed] < self.charset.len() {
+                    break;
+                };
+                digits[first_changed] = 0;
+            }
+        }
+        found
+    }
+
+    /// Try every candidate. See [`BruteForce::run_from`].
+    pub fn run(
+        &self,
+        unknown_hashes: &HashSet<u32>,
+        progress: &(dyn Fn(&BruteForceProgress) + Sync),
+    ) -> Vec<(u32, String)> {
+        self.run_from(
+            unknown_hashes,
+            BruteForcePosition {
+                length: self.min_length,
+                index: 0,
+            },
+            progress,
+        )
+    }
+
+    /// Try every candidate starting from the given position (as given by a previous [`BruteForceProgress`]), and
+    /// return the ones whose hash is in ``unknown_hashes``, with their hash.
+    ///
+    /// ``progress`` is called regularly. Every candidate before the position it receive have been tried.
+    pub fn run_from(
+        &self,
+        unknown_hashes: &HashSet<u32>,
+        start: BruteForcePosition,
+        progress: &(dyn Fn(&BruteForceProgress) + Sync),
+    ) -> Vec<(u32, String)> {
+        let found = Mutex::new(Vec::new());
+        let candidates_tried = AtomicU64::new(0);
+        let start_time = Stopwatch::start();
+        #[cfg(feature = "rayon")]
+        let batch_size = rayon::current_num_threads() as u64 * 4;
+        #[cfg(not(feature = "rayon"))]
+        let batch_size = 1;
+
+        for length in start.length.max(self.min_length)..=self.max_length {
+            let total = self.candidate_count_for_length(length);
+            let mut index = if length == start.length {
+                start.index
+            } else {
+                0
+            };
+            while index < total {
+                let batch_end = index.saturating_add(self.chunk_size.saturating_mul(batch_size));
+                let batch_end = batch_end.min(total);
+                let chunks = (index..batch_end)
+                    .step_by(self.chunk_size as usize)
+                    .collect::<Vec<_>>();
+                let try_chunk = |chunk_start: &u64| {
+                    let chunk_end = chunk_start.saturating_add(self.chunk_size).min(batch_end);
+                    let chunk_found =
+                        self.try_range(length, *chunk_start, chunk_end, unknown_hashes);
+                    if !chunk_found.is_empty() {
+                        found
+                            .lock()
+                            .unwrap_or_else(std::sync::PoisonError::into_inner)
+                            .extend(chunk_found);
+                    };
+                    candidates_tried.fetch_add(chunk_end - chunk_start, Ordering::Relaxed);
+                };
+                #[cfg(feature = "rayon")]
+                chunks.par_iter().for_each(try_chunk);
+                #[cfg(not(feature = "rayon"))]
+                chunks.iter().for_each(try_chunk);
+                index = batch_end;
+
+                let tried = candidates_tried.load(Ordering::Relaxed);
+                let elapsed = start_time.elapsed_secs();
+                progress(&BruteForceProgress {
+                    position: BruteForcePosition { length, index },
+                    candidates_tried: tried,
+                    names_found: found
+                        .lock()
+                        .unwrap_or_else(std::sync::PoisonError::into_inner)
+                        .len(),
+                    candidates_per_second: if elapsed > 0.0 {
+                        tried as f64 / elapsed
+                    } else {
+                        0.0
+                    },
+                });
+            }
+        }
+        let mut found = found
+            .into_inner()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        found.sort_unstable();
+        found
+    }
+}
+
+impl Dehasher for BruteForce {
+    fn name(&self) -> &str {
+        "brute force"
+    }
+
+    fn find_names(&self, input: &DehashInput) -> Result<Vec<String>, DehashError> {
+        Ok(self
+            .run(input.unknown_hashes(), &|_| ())
+            .into_iter()
+            .map(|(_, name)| name)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_name;
+
+    /// Every candidate of the charset with a length between ``min_length`` and ``max_length``, built one by one
+    fn naive_candidates(charset: &str, min_length: usize, max_length: usize) -> Vec<String> {
+        let mut candidates = Vec::new();
+        let mut current = vec![String::new()];
+        for length in 0..=max_length {
+            if length >= min_length {
+                candidates.extend(current.iter().cloned());
+            };
+            current = current
+                .iter()
+                .flat_map(|start| {
+                    charset
+                        .chars()
+                        .map(move |chara| format!("{}{}", start, chara))
+                })
+                .collect();
+        }
+        candidates
+    }
+
+    #[test]
+    fn same_matches_as_naive_enumeration() {
+        // the charset is already sorted, so the naive enumeration is in the same order as the brute force
+        let mut brute_force = BruteForce::new("1_ab", 0, 4);
+        brute_force.set_prefix("x");
+        brute_force.set_suffix(".bin");
+        // small chunks, so the enumeration is split between many ranges (and threads with rayon)
+        brute_force.set_chunk_size(7);
+
+        let naive = naive_candidates("1_ab", 0, 4)
+            .into_iter()
+            .map(|generated| format!("x{}.bin", generated))
+            .collect::<Vec<_>>();
+        assert_eq!(naive.len() as u64, brute_force.candidate_count());
+
+        // search for every third candidate, plus a hash no candidate have
+        let unknown_hashes = naive
+            .iter()
+            .step_by(3)
+            .map(|name| hash_name(name))
+            .chain(std::iter::once(hash_name("not a candidate")))
+            .collect::<HashSet<_>>();
+        let mut expected = naive
+            .iter()
+            .filter(|name| unknown_hashes.contains(&hash_name(name)))
+            .map(|name| (hash_name(name), name.clone()))
+            .collect::<Vec<_>>();
+        expected.sort_unstable();
+
+        assert_eq!(brute_force.run(&unknown_hashes, &|_| ()), expected);
+
+        // resuming from the middle of a length only find the following candidates
+        let start = BruteForcePosition {
+            length: 3,
+            index: 10,
+        };
+        let first_after = naive
+            .iter()
+            .position(|name| name == &brute_force.candidate_at(start))
+            .unwrap();
+        let mut expected_after = naive[first_after..]
+            .iter()
+            .filter(|name| unknown_hashes.contains(&hash_name(name)))
+            .map(|name| (hash_name(name), name.clone()))
+            .collect::<Vec<_>>();
+        expected_after.sort_unstable();
+        assert_eq!(
+            brute_force.run_from(&unknown_hashes, start, &|_| ()),
+            expected_after
+        );
+    }
+}
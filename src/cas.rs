@@ -0,0 +1,277 @@
+use crate::hex::{sha256_from_hex, to_hex};
+use crate::{Farc, FarcError};
+use sha2::{Digest, Sha256};
+use std::fs::{create_dir_all, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// The first line of a content index
+const CONTENT_INDEX_MAGIC: &str = "FARC content index 1";
+
+/// An error that can happen while exporting an archive with a [`CasExporter`], or reading a [`ContentIndex`]
+#[derive(Debug, Error)]
+pub enum CasError {
+    /// An error happened while reading the archive, or reading or writing the store
+    #[error("input/output error")]
+    IOError(#[from] io::Error),
+    /// A file of the archive can't be opened
+    #[error("the archive can't be read")]
+    FarcError(#[from] FarcError),
+    /// The name of an index would be outside of the store
+    #[error("the index name {0:?} isn't a relative path inside the store")]
+    InvalidIndexName(String),
+    /// The index doesn't start with the content index header
+    #[error("the file doesn't start with \"{CONTENT_INDEX_MAGIC}\"")]
+    BadHeader,
+    /// A line of the index can't be parsed
+    #[error("the line {line} of the content index can't be parsed: {reason}")]
+    InvalidLine {
+        /// The number of the line (starting at 1)
+        line: usize,
+        /// Why it can't be parsed
+        reason: String,
+    },
+}
+
+/// A file of an archive listed in a [`ContentIndex`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContentIndexEntry {
+    /// The hash of the name of the file
+    pub hash: u32,
+    /// The length of the file, including the padding
+    pub length: u32,
+    /// The sha256 of the content, which is also the name of the object in the store
+    pub sha256: [u8; 32],
+    /// The name of the file, if known
+    pub name: Option<String>,
+}
+
+/// The files of an archive exported by a [`CasExporter`], with the digest of their content. It is a text file, with
+/// fields separated by tabs:
+/// ```text
+/// FARC content index 1
+/// 0x<hash>    <length>    <sha256>    <name, if known>
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContentIndex {
+    /// The files, sorted by hash
+    pub entries: Vec<ContentIndexEntry>,
+}
+
+impl ContentIndex {
+    /// Write the index
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "{}", CONTENT_INDEX_MAGIC)?;
+        for entry in &self.entries {
+            writeln!(
+                writer,
+                "0x{:08x}\t{}\t{}\t{}",
+                entry.hash,
+                entry.length,
+                to_hex(&entry.sha256),
+                entry.name.as_deref().unwrap_or("")
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Read an index
+    pub fn read<R: BufRead>(reader: R) -> Result<Self, CasError> {
+        let mut lines = reader.lines();
+        match lines.next().transpose()? {
+            Some(line) if line.trim_end() == CONTENT_INDEX_MAGIC => (),
+            _ => return Err(CasError::BadHeader),
+        };
+        let mut entries = Vec::new();
+        for (index, line) in lines.enumerate() {
+            let line = line?;
+            let invalid = |reason: &str| CasError::InvalidLine {
+                line: index + 2,
+                reason: reason.to_string(),
+            };
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() {
+                continue;
+            };
+            match line.splitn(4, '\t').collect::<Vec<_>>().as_slice() {
+                [hash, length, sha256, name] => entries.push(ContentIndexEntry {
+                    hash: hash
+                        .strip_prefix("0x")
+                        .and_then(|hash| u32::from_str_radix(hash, 16).ok())
+                        .ok_or_else(|| invalid("the hash isn't an hexadecimal number"))?,
+                    length: length
+                        .parse()
+                        .map_err(|_| invalid("the length isn't a number"))?,
+                    sha256: sha256_from_hex(sha256).ok_or_else(|| invalid("invalid sha256"))?,
+                    name: if name.is_empty() {
+                        None
+                    } else {
+                        Some((*name).to_string())
+                    },
+                }),
+                _ => return Err(invalid("expected 4 fields")),
+            };
+        }
+        Ok(Self { entries })
+    }
+}
+
+/// What a [`CasExporter`] did so far
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CasStats {
+    /// The number of exported files
+    pub files: u64,
+    /// The number of files whose content wasn't already in the store
+    pub objects_written: u64,
+    /// The number of bytes written to the store
+    pub bytes_written: u64,
+    /// The number of bytes that weren't written, as their content was already in the store
+    pub bytes_deduplicated: u64,
+}
+
+/// Export archives to a content-addressable store: the content of each file is written once, named after its
+/// sha256, so identical files of different archives (like the language variants of an archive, or different versions
+/// of the game) are stored once. For each archive, a [`ContentIndex`] list its files and their digest.
+///
+/// The store is a directory with an ``objects`` directory, where each content is stored at
+/// ``objects/<first 2 hexadecimal digits of the sha256>/<other digits>``, and an ``indexes`` directory, containing the
+/// index of each archive. A store can be reused, the content already in it isn't written again.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, CasExporter, Farc, FarcWriter};
+/// use std::io::Cursor;
+/// let store = std::env::temp_dir().join(format!("pmd_farc_cas_doctest_{}", std::process::id()));
+/// let mut exporter = CasExporter::new(&store).unwrap();
+/// for (language, text) in &[("us", "hello"), ("fr", "bonjour")] {
+///     let mut writer = FarcWriter::default();
+///     writer.add_hashed_file(hash_name("common.bin"), vec![1; 64]);
+///     writer.add_hashed_file(hash_name("text.bin"), text.as_bytes().to_vec());
+///     let mut archive = Cursor::new(Vec::new());
+///     writer.write_hashed(&mut archive).unwrap();
+///     let farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+///     exporter.export(&format!("message_{}.bin", language), &farc).unwrap();
+/// }
+/// // common.bin is only stored once
+/// assert_eq!(exporter.stats().files, 4);
+/// assert_eq!(exporter.stats().objects_written, 3);
+///
+/// let index = exporter.read_index("message_fr.bin").unwrap();
+/// let content = std::fs::read(exporter.object_path(&index.entries[0].sha256)).unwrap();
+/// assert_eq!(content.len() as u32, index.entries[0].length);
+/// # std::fs::remove_dir_all(&store).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct CasExporter {
+    root: PathBuf,
+    stats: CasStats,
+}
+
+impl CasExporter {
+    /// Open the store in the directory ``root``, creating it if needed
+    pub fn new<P: AsRef<Path>>(root: P) -> io::Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        create_dir_all(root.join("objects"))?;
+        create_dir_all(root.join("indexes"))?;
+        Ok(Self {
+            root,
+            stats: CasStats::default(),
+        })
+    }
+
+    /// Return the directory of the store
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Return what was exported since this exporter was created
+    #[must_use]
+    pub const fn stats(&self) -> CasStats {
+        self.stats
+    }
+
+    /// Return the path of the object with this sha256
+    #[must_use]
+    pub fn object_path(&self, sha256: &[u8; 32]) -> PathBuf {
+        let hex = to_hex(sha256);
+        self.root.join("objects").join(&hex[..2]).join(&hex[2..])
+    }
+
+    /// Return the path of the index with this name. Fail if it isn't a relative path without ``..``.
+    pub fn index_path(&self, name: &str) -> Result<PathBuf, CasError> {
+        let invalid = || CasError::InvalidIndexName(name.to_string());
+        if name.is_empty() || name.starts_with('/') || name.contains('\\') {
+            return Err(invalid());
+        };
+        if name
+            .split('/')
+            .any(|part| part.is_empty() || part == "." || part == "..")
+        {
+            return Err(invalid());
+        };
+        Ok(self.root.join("indexes").join(format!("{}.index", name)))
+    }
+
+    /// Write the content of every file of ``farc`` not already in the store, then its index, named ``name``. The
+    /// name may contain ``/``, for example to group the archives of a version of the game.
+    pub fn export<F: Read + Seek>(
+        &mut self,
+        name: &str,
+        farc: &Farc<F>,
+    ) -> Result<ContentIndex, CasError> {
+        let index_path = self.index_path(name)?;
+        let mut index = ContentIndex::default();
+        let mut content = Vec::new();
+        for file in farc.iter_files() {
+            content.clear();
+            farc.get_hashed_file(file.name_hash)?
+                .read_to_end(&mut content)?;
+            let sha256: [u8; 32] = Sha256::digest(&content).into();
+            self.store(&sha256, &content)?;
+            index.entries.push(ContentIndexEntry {
+                hash: file.name_hash,
+                length: file.length,
+                sha256,
+                name: file.name.clone(),
+            });
+        }
+        index.entries.sort_by_key(|entry| entry.hash);
+
+        if let Some(parent) = index_path.parent() {
+            create_dir_all(parent)?;
+        };
+        let mut writer = BufWriter::new(File::create(index_path)?);
+        index.write(&mut writer)?;
+        writer.flush()?;
+        Ok(index)
+    }
+
+    /// Read the index with this name
+    pub fn read_index(&self, name: &str) -> Result<ContentIndex, CasError> {
+        ContentIndex::read(BufReader::new(File::open(self.index_path(name)?)?))
+    }
+
+    fn store(&mut self, sha256: &[u8; 32], content: &[u8]) -> io::Result<()> {
+        self.stats.files += 1;
+        let path = self.object_path(sha256);
+        if path.exists() {
+            self.stats.bytes_deduplicated += content.len() as u64;
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        };
+        // written under another name first, so an interrupted export doesn't leave a truncated object
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, content)?;
+        std::fs::rename(&temporary, &path)?;
+        self.stats.objects_written += 1;
+        self.stats.bytes_written += content.len() as u64;
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+//! Access to the clock, which doesn't exist on ``wasm32-unknown-unknown`` (calling [`SystemTime::now`] or
+//! [`Instant::now`] panic there).
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Instant;
+use std::time::SystemTime;
+
+/// Return the current time, or [`SystemTime::UNIX_EPOCH`] if there is no clock
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub(crate) fn now() -> SystemTime {
+    SystemTime::now()
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub(crate) fn now() -> SystemTime {
+    SystemTime::UNIX_EPOCH
+}
+
+/// Measure the time elapsed since its creation. Always return 0 if there is no clock.
+pub(crate) struct Stopwatch {
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    start: Instant,
+}
+
+impl Stopwatch {
+    pub(crate) fn start() -> Self {
+        Self {
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            start: Instant::now(),
+        }
+    }
+
+    /// Return the elapsed time, in seconds
+    pub(crate) fn elapsed_secs(&self) -> f64 {
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        return self.start.elapsed().as_secs_f64();
+        #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+        return 0.0;
+    }
+}
@@ -0,0 +1,179 @@
+use crate::{Farc, FarcError, FarcWriter};
+use std::io::{self, Read, Seek};
+use thiserror::Error;
+
+/// An error that can happen while decompressing or compressing a file
+#[derive(Debug, Error)]
+pub enum CompressionError {
+    /// An error happened while reading the archive
+    #[error("an error happened while reading the archive")]
+    FarcError(#[from] FarcError),
+    /// An error happened while reading a file
+    #[error("input/output error")]
+    IOError(#[from] io::Error),
+    /// No [`Compression`] with this name is registered
+    #[error("there is no compression named {0:?}")]
+    UnknownCompression(String),
+    /// The [`Compression`] failed
+    #[error("the {compression} compression failed: {message}")]
+    Failed {
+        /// The name of the [`Compression`]
+        compression: String,
+        /// The description of the error
+        message: String,
+    },
+}
+
+/// A compression format used for the files stored in archives, like the PX and AT formats of PMD. Implementations
+/// (usually wrapping a dedicated compression crate) are added to a [`CompressionRegistry`].
+pub trait Compression: Send + Sync {
+    /// The name of the format, like ``"at4px"``
+    fn name(&self) -> &str;
+
+    /// Return true if the content is compressed with this format, usually by checking its magic
+    fn is_compressed(&self, content: &[u8]) -> bool;
+
+    /// Decompress the content. Files read from an archive may end with padding, that should be ignored.
+    fn decompress(&self, content: &[u8]) -> Result<Vec<u8>, CompressionError>;
+
+    /// Compress the content
+    fn compress(&self, content: &[u8]) -> Result<Vec<u8>, CompressionError>;
+}
+
+/// The content of a file, decompressed by a [`CompressionRegistry`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecompressedFile {
+    /// The decompressed content (or the raw content, if it wasn't compressed)
+    pub content: Vec<u8>,
+    /// The name of the [`Compression`] the file was compressed with, if any
+    pub compression: Option<String>,
+}
+
+#[derive(Default)]
+/// An ordered list of [`Compression`], used to detect and decompress the files of a [`Farc`], and to compress them
+/// again when writing them with a [`FarcWriter`].
+///
+/// # Example
+/// ```
+/// use pmd_farc::{Compression, CompressionError, CompressionRegistry, Farc, FarcWriter};
+/// use std::io::Cursor;
+///
+/// /// A toy format, prefixing the content with its length
+/// struct Prefixed;
+///
+/// impl Compression for Prefixed {
+///     fn name(&self) -> &str {
+///         "prefixed"
+///     }
+///     fn is_compressed(&self, content: &[u8]) -> bool {
+///         content.starts_with(b"PRE")
+///     }
+///     fn decompress(&self, content: &[u8]) -> Result<Vec<u8>, CompressionError> {
+///         let length = content[3] as usize;
+///         Ok(content[4..4 + length].to_vec())
+///     }
+///     fn compress(&self, content: &[u8]) -> Result<Vec<u8>, CompressionError> {
+///         Ok([&b"PRE"[..], &[content.len() as u8], content].concat())
+///     }
+/// }
+///
+/// let mut registry = CompressionRegistry::default();
+/// registry.add_compression(Box::new(Prefixed));
+///
+/// let mut writer = FarcWriter::default();
+/// writer.add_compressed_file(1, b"hello", Some("prefixed"), &registry).unwrap();
+/// writer.add_compressed_file(2, b"raw", None, &registry).unwrap();
+/// let mut archive = Cursor::new(Vec::new());
+/// writer.write_hashed(&mut archive).unwrap();
+/// let farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+///
+/// let file = farc.get_decompressed_file(1, &registry).unwrap();
+/// assert_eq!(file.content, b"hello");
+/// assert_eq!(file.compression.as_deref(), Some("prefixed"));
+/// assert_eq!(farc.get_decompressed_file(2, &registry).unwrap().compression, None);
+/// ```
+pub struct CompressionRegistry {
+    compressions: Vec<Box<dyn Compression>>,
+}
+
+impl CompressionRegistry {
+    /// Add a [`Compression`] at the end of this registry. The first one that recognize a content is used.
+    pub fn add_compression(&mut self, compression: Box<dyn Compression>) {
+        self.compressions.push(compression);
+    }
+
+    /// Return the [`Compression`] with this name
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&dyn Compression> {
+        self.compressions
+            .iter()
+            .find(|compression| compression.name() == name)
+            .map(|compression| compression.as_ref())
+    }
+
+    /// Return the first [`Compression`] that recognize this content
+    #[must_use]
+    pub fn detect(&self, content: &[u8]) -> Option<&dyn Compression> {
+        self.compressions
+            .iter()
+            .find(|compression| compression.is_compressed(content))
+            .map(|compression| compression.as_ref())
+    }
+
+    /// Decompress the content if it is compressed with one of the registered [`Compression`]
+    pub fn decompress(&self, content: Vec<u8>) -> Result<DecompressedFile, CompressionError> {
+        Ok(match self.detect(&content) {
+            Some(compression) => DecompressedFile {
+                content: compression.decompress(&content)?,
+                compression: Some(compression.name().to_string()),
+            },
+            None => DecompressedFile {
+                content,
+                compression: None,
+            },
+        })
+    }
+
+    /// Compress the content with the [`Compression`] with this name, or return it unchanged if ``name`` is ``None``
+    pub fn compress(
+        &self,
+        name: Option<&str>,
+        content: &[u8],
+    ) -> Result<Vec<u8>, CompressionError> {
+        match name {
+            Some(name) => self
+                .get(name)
+                .ok_or_else(|| CompressionError::UnknownCompression(name.to_string()))?
+                .compress(content),
+            None => Ok(content.to_vec()),
+        }
+    }
+}
+
+impl<F: Read + Seek> Farc<F> {
+    /// Read the file with this hash, and decompress it if it is compressed with a format of the registry
+    pub fn get_decompressed_file(
+        &self,
+        hash: u32,
+        registry: &CompressionRegistry,
+    ) -> Result<DecompressedFile, CompressionError> {
+        let mut content = Vec::new();
+        self.get_hashed_file(hash)?.read_to_end(&mut content)?;
+        registry.decompress(content)
+    }
+}
+
+impl FarcWriter {
+    /// Add a file with the given hash, compressed with the [`Compression`] of the registry with this name (usually
+    /// the one given by [`Farc::get_decompressed_file`]), or uncompressed if ``compression`` is ``None``
+    pub fn add_compressed_file(
+        &mut self,
+        hash: u32,
+        content: &[u8],
+        compression: Option<&str>,
+        registry: &CompressionRegistry,
+    ) -> Result<(), CompressionError> {
+        self.add_hashed_file(hash, registry.compress(compression, content)?);
+        Ok(())
+    }
+}
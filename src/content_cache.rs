@@ -0,0 +1,187 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// The identifier of the archive (see [`ContentCache::register`]) and the hash of the file
+type CacheKey = (u64, u32);
+
+#[derive(Debug, Default)]
+struct CacheState {
+    budget: usize,
+    used: usize,
+    /// incremented on every access, to order the entries from the least to the most recently used
+    tick: u64,
+    entries: HashMap<CacheKey, (Arc<[u8]>, u64)>,
+    by_tick: BTreeMap<u64, CacheKey>,
+    hits: u64,
+    misses: u64,
+}
+
+impl CacheState {
+    fn touch(&mut self, key: CacheKey) -> Option<Arc<[u8]>> {
+        self.tick += 1;
+        let tick = self.tick;
+        let (content, last_tick) = self.entries.get_mut(&key)?;
+        self.by_tick.remove(last_tick);
+        *last_tick = tick;
+        self.by_tick.insert(tick, key);
+        Some(content.clone())
+    }
+
+    fn remove(&mut self, key: CacheKey) {
+        if let Some((content, tick)) = self.entries.remove(&key) {
+            self.by_tick.remove(&tick);
+            self.used -= content.len();
+        };
+    }
+
+    fn evict_to(&mut self, budget: usize) {
+        while self.used > budget {
+            let key = match self.by_tick.values().next() {
+                Some(key) => *key,
+                None => break,
+            };
+            self.remove(key);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A cache of the content of files, keeping the most recently read ones as long as their total size is under a
+/// budget, in bytes. Cloning it return a handle to the same cache, so one budget can be shared by several
+/// [`Farc`](crate::Farc). See [`Farc::set_content_cache`](crate::Farc::set_content_cache).
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, ContentCache, Farc, FarcWriter};
+/// use std::io::Cursor;
+/// let mut writer = FarcWriter::default();
+/// writer.add_hashed_file(hash_name("a.bgrs"), vec![1; 100]);
+/// writer.add_hashed_file(hash_name("b.bch"), vec![2; 100]);
+/// let mut archive = Cursor::new(Vec::new());
+/// writer.write_hashed(&mut archive).unwrap();
+///
+/// let cache = ContentCache::new(150);
+/// let mut farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+/// farc.set_content_cache(cache.clone());
+///
+/// farc.read_hashed_file(hash_name("a.bgrs")).unwrap();
+/// farc.read_hashed_file(hash_name("a.bgrs")).unwrap();
+/// assert_eq!((cache.hits(), cache.misses()), (1, 1));
+/// // only one file fit in the budget
+/// farc.read_hashed_file(hash_name("b.bch")).unwrap();
+/// assert_eq!(cache.len(), 1);
+/// assert!(cache.used() <= cache.budget());
+/// ```
+pub struct ContentCache {
+    state: Arc<Mutex<CacheState>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl ContentCache {
+    /// Create an empty cache, that keep at most ``budget`` bytes
+    #[must_use]
+    pub fn new(budget: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(CacheState {
+                budget,
+                ..CacheState::default()
+            })),
+            next_id: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CacheState> {
+        // the state is always left consistent, so a poisoned lock can still be used
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Return a new identifier, to distinguish the files of the different archives using this cache
+    pub(crate) fn register(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Return the content of the file with this hash in the archive ``archive``, if cached, marking it as the most
+    /// recently used
+    pub(crate) fn get(&self, archive: u64, hash: u32) -> Option<Arc<[u8]>> {
+        let mut state = self.lock();
+        let content = state.touch((archive, hash));
+        if content.is_some() {
+            state.hits += 1;
+        } else {
+            state.misses += 1;
+        };
+        content
+    }
+
+    /// Add the content of a file to the cache, removing the least recently used ones to stay under the budget. A
+    /// file bigger than the whole budget isn't cached.
+    pub(crate) fn insert(&self, archive: u64, hash: u32, content: Arc<[u8]>) {
+        let mut state = self.lock();
+        if content.len() > state.budget {
+            return;
+        };
+        let key = (archive, hash);
+        state.remove(key);
+        let budget = state.budget - content.len();
+        state.evict_to(budget);
+        state.used += content.len();
+        state.tick += 1;
+        let tick = state.tick;
+        state.by_tick.insert(tick, key);
+        state.entries.insert(key, (content, tick));
+    }
+
+    /// Return the maximum number of bytes kept in the cache
+    #[must_use]
+    pub fn budget(&self) -> usize {
+        self.lock().budget
+    }
+
+    /// Change the maximum number of bytes kept in the cache, removing the least recently used files if needed
+    pub fn set_budget(&self, budget: usize) {
+        let mut state = self.lock();
+        state.budget = budget;
+        state.evict_to(budget);
+    }
+
+    /// Return the number of bytes currently cached
+    #[must_use]
+    pub fn used(&self) -> usize {
+        self.lock().used
+    }
+
+    /// Return the number of files currently cached
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Return true if no file is cached
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lock().entries.is_empty()
+    }
+
+    /// Return the number of reads that were served from the cache
+    #[must_use]
+    pub fn hits(&self) -> u64 {
+        self.lock().hits
+    }
+
+    /// Return the number of reads that weren't served from the cache
+    #[must_use]
+    pub fn misses(&self) -> u64 {
+        self.lock().misses
+    }
+
+    /// Remove every file from the cache
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        state.entries.clear();
+        state.by_tick.clear();
+        state.used = 0;
+    }
+}
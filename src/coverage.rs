@@ -0,0 +1,70 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The key used in [`Coverage::by_strategy`] for names that are stored in the archive itself
+pub const STRATEGY_ARCHIVE: &str = "archive";
+/// The key used in [`Coverage::by_strategy`] for names that weren't found by a [`Dehasher`](crate::Dehasher)
+pub const STRATEGY_OTHER: &str = "other";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// How much of the file names of a [`Farc`](crate::Farc) are known. See [`Farc::coverage`](crate::Farc::coverage).
+pub struct Coverage {
+    /// The total number of files
+    pub total: usize,
+    /// The number of files with a known name, verified against their hash
+    pub named: usize,
+    /// The number of files with a guessed or forced name (see [`NameConfidence`](crate::NameConfidence)), that
+    /// isn't verified. They aren't counted in the other fields.
+    pub unverified: usize,
+    /// The number of files with a known name, by extension (without the dot, empty for names without extension)
+    pub by_extension: BTreeMap<String, usize>,
+    /// The number of files with a known name, by the name of the [`Dehasher`](crate::Dehasher) that found it.
+    /// [`STRATEGY_ARCHIVE`] is used for names stored in the archive, and [`STRATEGY_OTHER`] for names found
+    /// otherwise.
+    pub by_strategy: BTreeMap<String, usize>,
+}
+
+impl Coverage {
+    /// Return the percentage of files with a known name (100 for an empty archive)
+    #[must_use]
+    pub fn percentage(&self) -> f64 {
+        if self.total == 0 {
+            100.0
+        } else {
+            self.named as f64 * 100.0 / self.total as f64
+        }
+    }
+
+    /// Add a named file to this coverage
+    pub(crate) fn add_named(&mut self, name: &str, strategy: &str) {
+        self.named += 1;
+        let extension = name.rfind('.').map_or("", |dot| &name[dot + 1..]);
+        *self.by_extension.entry(extension.to_string()).or_insert(0) += 1;
+        *self.by_strategy.entry(strategy.to_string()).or_insert(0) += 1;
+    }
+}
+
+impl fmt::Display for Coverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}/{} files named ({:.2}%)",
+            self.named,
+            self.total,
+            self.percentage()
+        )?;
+        if self.unverified > 0 {
+            writeln!(f, "{} more files with an unverified name", self.unverified)?;
+        };
+        writeln!(f, "by extension:")?;
+        for (extension, count) in &self.by_extension {
+            writeln!(f, "  {:?}: {}", extension, count)?;
+        }
+        writeln!(f, "by strategy:")?;
+        for (strategy, count) in &self.by_strategy {
+            writeln!(f, "  {}: {}", strategy, count)?;
+        }
+        Ok(())
+    }
+}
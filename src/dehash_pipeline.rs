@@ -0,0 +1,105 @@
+use crate::{DehashError, DehashReport, Dehasher, Farc, NameResolution};
+use std::collections::BTreeMap;
+use std::io::{Read, Seek};
+
+#[derive(Default)]
+/// An ordered list of [`Dehasher`], run one after the other on a [`Farc`]. Each one only receive the hashes that
+/// are still unknown after the previous ones, so the cheapest and most reliable strategies should be added first.
+///
+/// The usual order is: the ``.lst`` file ([`ListDehasher::from_lst`](crate::ListDehasher::from_lst)), then the
+/// content scan (``ContentDehasher``, with the ``bgrs`` or ``bch`` feature), then word lists
+/// ([`ListDehasher::from_wordlist`](crate::ListDehasher::from_wordlist)).
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, DehashPipeline, Farc, FarcWriter, ListDehasher};
+/// use std::io::Cursor;
+/// let mut writer = FarcWriter::default();
+/// writer.add_hashed_file(hash_name("script_001.bin"), Vec::new());
+/// writer.add_hashed_file(hash_name("script_002.bin"), Vec::new());
+/// let mut archive = Cursor::new(Vec::new());
+/// writer.write_hashed(&mut archive).unwrap();
+/// let mut farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+///
+/// let mut pipeline = DehashPipeline::default();
+/// let lst = "message/script_001.bin\n";
+/// pipeline.add_dehasher(Box::new(ListDehasher::from_lst(&mut lst.as_bytes()).unwrap()));
+/// let wordlist = "script_001.bin\nscript_002.bin\n";
+/// pipeline.add_dehasher(Box::new(ListDehasher::from_wordlist(&mut wordlist.as_bytes()).unwrap()));
+///
+/// let report = pipeline.run(&mut farc).unwrap();
+/// assert_eq!(report.resolved_by_strategy(), vec![("lst", 1), ("wordlist", 1)]);
+/// assert_eq!(report.total_resolved(), 2);
+/// assert_eq!(report.attribution[&hash_name("script_002.bin")].strategy, "wordlist");
+/// ```
+pub struct DehashPipeline {
+    dehashers: Vec<Box<dyn Dehasher>>,
+}
+
+impl DehashPipeline {
+    /// Add a [`Dehasher`] at the end of this pipeline
+    pub fn add_dehasher(&mut self, dehasher: Box<dyn Dehasher>) {
+        self.dehashers.push(dehasher);
+    }
+
+    /// Return the number of [`Dehasher`] in this pipeline
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.dehashers.len()
+    }
+
+    /// Return true if this pipeline doesn't contain any [`Dehasher`]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.dehashers.is_empty()
+    }
+
+    /// Run every [`Dehasher`] of this pipeline in order on the given [`Farc`]
+    pub fn run<F: Read + Seek>(&self, farc: &mut Farc<F>) -> Result<PipelineReport, DehashError> {
+        let dehashers = self
+            .dehashers
+            .iter()
+            .map(|dehasher| dehasher.as_ref())
+            .collect::<Vec<_>>();
+        let reports = farc.dehash_with(&dehashers)?;
+        let attribution = reports
+            .iter()
+            .flat_map(|report| report.resolved.iter())
+            .filter_map(|(hash, _)| {
+                farc.name_resolution(*hash)
+                    .map(|resolution| (*hash, resolution.clone()))
+            })
+            .collect();
+        Ok(PipelineReport {
+            reports,
+            attribution,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The result of running a [`DehashPipeline`]
+pub struct PipelineReport {
+    /// The report of each [`Dehasher`], in the order they were run
+    pub reports: Vec<DehashReport>,
+    /// For each hash whose name was found, the name, the strategy that found it, and when
+    pub attribution: BTreeMap<u32, NameResolution>,
+}
+
+impl PipelineReport {
+    /// Return the total number of names found by the pipeline
+    #[must_use]
+    pub fn total_resolved(&self) -> usize {
+        self.reports.iter().map(|r| r.resolved.len()).sum()
+    }
+
+    /// Return the name of each strategy, with the number of names it found
+    #[must_use]
+    pub fn resolved_by_strategy(&self) -> Vec<(&str, usize)> {
+        self.reports
+            .iter()
+            .map(|r| (r.strategy.as_str(), r.resolved.len()))
+            .collect()
+    }
+}
@@ -0,0 +1,95 @@
+use crate::{DehashError, DehashReport, Dehasher, Farc};
+use std::io::{Read, Seek};
+
+/// Return true if the text match the pattern, where ``*`` match any sequence of characters (including an empty one),
+/// and ``?`` match any single character.
+///
+/// # Example
+/// ```
+/// use pmd_farc::wildcard_match;
+/// assert!(wildcard_match("effect*.bin", "effect_us.bin"));
+/// assert!(wildcard_match("message_??.bin", "message_fr.bin"));
+/// assert!(!wildcard_match("effect*.bin", "message.bin"));
+/// ```
+#[must_use]
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    let (mut pattern_pos, mut text_pos) = (0, 0);
+    // position of the last ``*`` in the pattern, and of the text when it was encountered
+    let mut backtrack: Option<(usize, usize)> = None;
+    while text_pos < text.len() {
+        match pattern.get(pattern_pos) {
+            Some('*') => {
+                backtrack = Some((pattern_pos, text_pos));
+                pattern_pos += 1;
+            }
+            Some(c) if *c == '?' || *c == text[text_pos] => {
+                pattern_pos += 1;
+                text_pos += 1;
+            }
+            _ => match backtrack {
+                Some((star_pos, star_text_pos)) => {
+                    pattern_pos = star_pos + 1;
+                    text_pos = star_text_pos + 1;
+                    backtrack = Some((star_pos, star_text_pos + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[pattern_pos..].iter().all(|c| *c == '*')
+}
+
+#[derive(Default)]
+/// Associate archive file name patterns (see [`wildcard_match`]) with [`Dehasher`], so applications can add support
+/// for new archive types, in the same way [`FileHashType::predict_from_file_name`](crate::FileHashType::predict_from_file_name)
+/// does for the built-in ones.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, DehashRegistry, Farc, FarcWriter, script_dehash::ScriptNameRule};
+/// use std::io::Cursor;
+/// let mut writer = FarcWriter::default();
+/// writer.add_hashed_file(hash_name("effect_0001.bin"), Vec::new());
+/// let mut archive = Cursor::new(Vec::new());
+/// writer.write_hashed(&mut archive).unwrap();
+/// let mut farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+///
+/// let mut registry = DehashRegistry::default();
+/// registry.add_handler("effect*.bin", Box::new(ScriptNameRule::new("effect_", 0..100, 4, ".bin")));
+/// assert_eq!(registry.handlers_for("effect_us.bin").count(), 1);
+/// let reports = registry.dehash("effect_us.bin", &mut farc).unwrap();
+/// assert_eq!(reports[0].resolved.len(), 1);
+/// ```
+pub struct DehashRegistry {
+    handlers: Vec<(String, Box<dyn Dehasher>)>,
+}
+
+impl DehashRegistry {
+    /// Associate a [`Dehasher`] with the archives whose file name match the pattern
+    pub fn add_handler(&mut self, pattern: &str, dehasher: Box<dyn Dehasher>) {
+        self.handlers.push((pattern.to_string(), dehasher));
+    }
+
+    /// Iterate over the [`Dehasher`] associated with this archive file name, in the order they were added
+    pub fn handlers_for<'a>(
+        &'a self,
+        file_name: &'a str,
+    ) -> impl Iterator<Item = &'a dyn Dehasher> {
+        self.handlers
+            .iter()
+            .filter(move |(pattern, _)| wildcard_match(pattern, file_name))
+            .map(|(_, dehasher)| dehasher.as_ref())
+    }
+
+    /// Run every [`Dehasher`] associated with this archive file name on the [`Farc`] (see [`Farc::dehash_with`])
+    pub fn dehash<F: Read + Seek>(
+        &self,
+        file_name: &str,
+        farc: &mut Farc<F>,
+    ) -> Result<Vec<DehashReport>, DehashError> {
+        let dehashers = self.handlers_for(file_name).collect::<Vec<_>>();
+        farc.dehash_with(&dehashers)
+    }
+}
@@ -0,0 +1,284 @@
+use crate::{hash_name, BruteForcePosition, Farc};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, BufRead, Read, Seek, Write};
+use thiserror::Error;
+
+/// An error that can happen while reading a [`DehashSession`]
+#[derive(Debug, Error)]
+pub enum DehashSessionError {
+    /// An error happened while reading the file
+    #[error("an error happened while reading the session file")]
+    IOError(#[from] io::Error),
+    /// A line of the session file is invalid
+    #[error("the line {0} of the session file is invalid: {1:?}")]
+    InvalidLine(usize, String),
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+/// Record the progress of a long-running name recovery: the confirmed names, the candidates that were tried without
+/// success, and the position of the brute forces. It can be saved with [`DehashSession::write`] and resumed with
+/// [`DehashSession::read`].
+///
+/// The file format is a text file with one tab-separated record per line:
+/// - ``name\t{hash as 8 hexadecimal digits}\t{name}`` for a confirmed name
+/// - ``failed\t{candidate}`` for a candidate that doesn't match any file
+/// - ``brute_force\t{label}\t{length}\t{index}`` for the position of a brute force
+///
+/// Backslashes, tabs and line breaks in the names, candidates and labels are written as ``\\``, ``\t``, ``\n``
+/// and ``\r``. A name whose hash doesn't match the one written before it is rejected.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, DehashSession, Farc, FarcWriter};
+/// use std::io::Cursor;
+/// let mut writer = FarcWriter::default();
+/// writer.add_hashed_file(hash_name("script_001.bin"), Vec::new());
+/// let mut archive = Cursor::new(Vec::new());
+/// writer.write_hashed(&mut archive).unwrap();
+/// let mut farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+///
+/// let mut session = DehashSession::default();
+/// assert!(session.check_file_name(&mut farc, "script_001.bin"));
+/// assert!(!session.check_file_name(&mut farc, "script_002.bin"));
+/// assert!(session.is_tried("script_002.bin"));
+/// session.record_failed("two\tfields");
+///
+/// let mut saved = Vec::new();
+/// session.write(&mut saved).unwrap();
+/// assert_eq!(DehashSession::read(&mut saved.as_slice()).unwrap(), session);
+/// ```
+pub struct DehashSession {
+    names: BTreeMap<u32, String>,
+    failed: BTreeSet<String>,
+    brute_force_positions: BTreeMap<String, BruteForcePosition>,
+}
+
+impl DehashSession {
+    /// Record a name as confirmed
+    pub fn record_name(&mut self, name: &str) {
+        self.names.insert(hash_name(name), name.to_string());
+    }
+
+    /// Record a candidate as tried without success
+    pub fn record_failed(&mut self, candidate: &str) {
+        self.failed.insert(candidate.to_string());
+    }
+
+    /// Record every name known by the given [`Farc`]
+    pub fn record_farc<F: Read + Seek>(&mut self, farc: &Farc<F>) {
+        for name in farc.iter_name() {
+            self.record_name(name);
+        }
+    }
+
+    /// Return true if this candidate was already tried, successfully or not
+    #[must_use]
+    pub fn is_tried(&self, candidate: &str) -> bool {
+        self.failed.contains(candidate)
+            || self.names.get(&hash_name(candidate)).map(String::as_str) == Some(candidate)
+    }
+
+    /// Iterate over the confirmed names, with their hash
+    pub fn iter_names(&self) -> impl Iterator<Item = (u32, &String)> {
+        self.names.iter().map(|(hash, name)| (*hash, name))
+    }
+
+    /// Return the number of candidates tried without success
+    #[must_use]
+    pub fn failed_count(&self) -> usize {
+        self.failed.len()
+    }
+
+    /// Set the position of the brute force identified by ``label``
+    pub fn set_brute_force_position(&mut self, label: &str, position: BruteForcePosition) {
+        self.brute_force_positions
+            .insert(label.to_string(), position);
+    }
+
+    /// Return the position of the brute force identified by ``label``, if any
+    #[must_use]
+    pub fn brute_force_position(&self, label: &str) -> Option<BruteForcePosition> {
+        self.brute_force_positions.get(label).copied()
+    }
+
+    /// Check the candidate against the [`Farc`] (unless it was already tried), and record the result.
+    /// Return true if a new name was found.
+    pub fn check_file_name<F: Read + Seek>(&mut self, farc: &mut Farc<F>, candidate: &str) -> bool {
+        if self.is_tried(candidate) {
+            return false;
+        };
+        let found = farc.check_file_name(candidate);
+        if found {
+            self.record_name(candidate);
+        } else {
+            self.record_failed(candidate);
+        };
+        found
+    }
+
+    /// Apply the confirmed names to the given [`Farc`]. Return the number of newly found names.
+    pub fn apply<F: Read + Seek>(&self, farc: &mut Farc<F>) -> usize {
+        self.names
+            .values()
+            .filter(|name| farc.check_file_name(name))
+            .count()
+    }
+
+    /// Write this session to a file
+    pub fn write<W: Write>(&self, file: &mut W) -> Result<(), io::Error> {
+        for (hash, name) in &self.names {
+            writeln!(file, "name\t{:08x}\t{}", hash, escape(name))?;
+        }
+        for candidate in &self.failed {
+            writeln!(file, "failed\t{}", escape(candidate))?;
+        }
+        for (label, position) in &self.brute_force_positions {
+            writeln!(
+                file,
+                "brute_force\t{}\t{}\t{}",
+                escape(label),
+                position.length,
+                position.index
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Read a session previously written with [`DehashSession::write`]
+    pub fn read<R: BufRead>(file: &mut R) -> Result<Self, DehashSessionError> {
+        let mut session = Self::default();
+        for (line_number, line) in file.lines().enumerate() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            };
+            let invalid = || DehashSessionError::InvalidLine(line_number + 1, line.clone());
+            let fields = line.split('\t').collect::<Vec<_>>();
+            match fields.as_slice() {
+                ["name", hash, name] => {
+                    let hash = u32::from_str_radix(hash, 16).map_err(|_| invalid())?;
+                    let name = unescape(name).ok_or_else(invalid)?;
+                    if hash_name(&name) != hash {
+                        return Err(invalid());
+                    };
+                    session.names.insert(hash, name);
+                }
+                ["failed", candidate] => {
+                    session.record_failed(&unescape(candidate).ok_or_else(invalid)?)
+                }
+                ["brute_force", label, length, index] => session.set_brute_force_position(
+                    &unescape(label).ok_or_else(invalid)?,
+                    BruteForcePosition {
+                        length: length.parse().map_err(|_| invalid())?,
+                        index: index.parse().map_err(|_| invalid())?,
+                    },
+                ),
+                _ => return Err(invalid()),
+            };
+        }
+        Ok(session)
+    }
+}
+
+/// Escape the backslashes, tabs and line breaks of a field
+fn escape(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for chara in field.chars() {
+        match chara {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            chara => escaped.push(chara),
+        };
+    }
+    escaped
+}
+
+/// Reverse [`escape`]. Return None if there is an unknown escape sequence.
+fn unescape(field: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut charas = field.chars();
+    while let Some(chara) = charas.next() {
+        unescaped.push(if chara == '\\' {
+            match charas.next()? {
+                '\\' => '\\',
+                't' => '\t',
+                'n' => '\n',
+                'r' => '\r',
+                _ => return None,
+            }
+        } else {
+            chara
+        });
+    }
+    Some(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::SyntheticFarc;
+
+    #[test]
+    fn round_trip() {
+        let synthetic = SyntheticFarc::new()
+            .with_hashed_file(hash_name("script_001.bin"), vec![1; 16])
+            .with_hashed_file(hash_name("back\\slash\tand\nbreaks\r.bin"), vec![2; 16])
+            .with_hashed_file(hash_name("unknown.bin"), vec![3; 16]);
+        let mut farc = synthetic.build_farc();
+
+        let mut session = DehashSession::default();
+        assert!(session.check_file_name(&mut farc, "script_001.bin"));
+        assert!(session.check_file_name(&mut farc, "back\\slash\tand\nbreaks\r.bin"));
+        assert!(!session.check_file_name(&mut farc, "script_002.bin"));
+        assert!(!session.check_file_name(&mut farc, "script_001.bin"));
+        session.record_failed("\\t is not a tab");
+        session.set_brute_force_position(
+            "script\t_###.bin",
+            BruteForcePosition {
+                length: 3,
+                index: 42,
+            },
+        );
+
+        let mut saved = Vec::new();
+        session.write(&mut saved).unwrap();
+        let read = DehashSession::read(&mut saved.as_slice()).unwrap();
+        assert_eq!(read, session);
+        assert_eq!(read.iter_names().count(), 2);
+        assert_eq!(read.failed_count(), 2);
+        assert!(read.is_tried("\\t is not a tab"));
+        assert_eq!(
+            read.brute_force_position("script\t_###.bin"),
+            Some(BruteForcePosition {
+                length: 3,
+                index: 42
+            })
+        );
+
+        // the names of the read session can be applied to another copy of the archive
+        let mut other = synthetic.build_farc();
+        assert_eq!(read.apply(&mut other), 2);
+        assert_eq!(read.apply(&mut other), 0);
+        assert_eq!(other.file_unknown_name(), 1);
+    }
+
+    #[test]
+    fn invalid_lines() {
+        let hash = hash_name("a.bin");
+        for (file, line) in [
+            (format!("name\t{:08x}\tb.bin\n", hash), 1),
+            (format!("name\t{:08x}\ta.bin\n\nfailed\tx\\q\n", hash), 3),
+            ("failed\tx\ty\n".to_string(), 1),
+            ("brute_force\tlabel\t3\tnot a number\n".to_string(), 1),
+            ("name\tnot hex\ta.bin\n".to_string(), 1),
+            ("unknown\tx\n".to_string(), 1),
+        ] {
+            match DehashSession::read(&mut file.as_bytes()) {
+                Err(DehashSessionError::InvalidLine(number, _)) => assert_eq!(number, line),
+                other => panic!("{:?} was read as {:?}", file, other),
+            };
+        }
+    }
+}
@@ -1,9 +1,22 @@
+use crate::{FarcError, NameGenerator};
+use std::collections::HashSet;
+use std::error::Error;
+use std::io::{self, Read};
+use std::sync::Arc;
+use std::time::SystemTime;
+use thiserror::Error;
+
 /// This enum store the way we can find the name of the files of the compressed file
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum FileHashType {
     /// In can this file contain files that have translated text. The game include debug information, in the form of an adjacent .lst file, that allow to know their name.
     /// See [`message_dehasher`] for function allowing to parse them
     Message,
+    /// This file contain scripts, whose name are derived from their id (like ``script_0042.bin``).
+    /// See [`script_dehash`] for function allowing to generate them
+    Script,
 }
 
 impl FileHashType {
@@ -15,6 +28,7 @@ impl FileHashType {
     /// ```
     /// use pmd_farc::FileHashType;
     /// assert_eq!(FileHashType::predict_from_file_name("message.bin"), Some(FileHashType::Message));
+    /// assert_eq!(FileHashType::predict_from_file_name("script.bin"), Some(FileHashType::Script));
     /// assert_eq!(FileHashType::predict_from_file_name("unknown.bin"), None);
     /// ```
     #[must_use]
@@ -34,16 +48,186 @@ impl FileHashType {
             | "message_debug_it.bin"
             | "message_debug_sp.bin"
             | "message_debug_us.bin" => Some(Self::Message),
+            "script.bin" | "script_debug.bin" => Some(Self::Script),
             _ => None,
         }
     }
 }
 
+/// An error that can happen while running a [`Dehasher`]
+#[derive(Debug, Error)]
+pub enum DehashError {
+    /// An error happened while accessing a sub-file of the ``Farc``
+    #[error("an error happened while accessing a sub-file of the farc file")]
+    FarcError(#[from] FarcError),
+    /// An error happened while reading a file
+    #[error("an error happened while reading a file")]
+    IOError(#[from] io::Error),
+    /// An error specific to a [`Dehasher`] implementation
+    #[error("an error happened in the dehasher {0}")]
+    Other(String, #[source] Box<dyn Error + Send + Sync>),
+}
+
+/// The information a [`Dehasher`] can use to find names
+pub struct DehashInput<'a> {
+    unknown_hashes: &'a HashSet<u32>,
+    read_file: &'a dyn Fn(u32) -> Result<Arc<[u8]>, FarcError>,
+}
+
+impl<'a> DehashInput<'a> {
+    /// Create a new [`DehashInput`], with the hash of the files whose name is still unknown, and a function returning
+    /// the content of a file from its hash. The content is shared rather than copied, like with
+    /// [`Farc::read_hashed_file`](crate::Farc::read_hashed_file).
+    pub fn new(
+        unknown_hashes: &'a HashSet<u32>,
+        read_file: &'a dyn Fn(u32) -> Result<Arc<[u8]>, FarcError>,
+    ) -> Self {
+        Self {
+            unknown_hashes,
+            read_file,
+        }
+    }
+
+    /// The hash of the files whose name is still unknown
+    #[must_use]
+    pub const fn unknown_hashes(&self) -> &HashSet<u32> {
+        self.unknown_hashes
+    }
+
+    /// Return the content of the file with the given hash
+    pub fn read_file(&self, hash: u32) -> Result<Arc<[u8]>, FarcError> {
+        (self.read_file)(hash)
+    }
+}
+
+/// A strategy to find the name of the files of a [`Farc`](crate::Farc) whose name is unknown. See [`Farc::dehash_with`](crate::Farc::dehash_with).
+///
+/// The strategy only need to return candidate names: they are checked against the hashes afterward, so wrong
+/// guesses are simply ignored.
+pub trait Dehasher {
+    /// A short name identifying this strategy, used in [`DehashReport`]
+    fn name(&self) -> &str;
+
+    /// Return the candidate names for the files whose name is unknown
+    fn find_names(&self, input: &DehashInput) -> Result<Vec<String>, DehashError>;
+}
+
+impl Dehasher for NameGenerator {
+    fn name(&self) -> &str {
+        "name generator"
+    }
+
+    fn find_names(&self, _input: &DehashInput) -> Result<Vec<String>, DehashError> {
+        Ok(self.iter().collect())
+    }
+}
+
+/// A [`Dehasher`] that try every name of a list of names
+#[derive(Debug, Clone)]
+pub struct ListDehasher {
+    name: String,
+    names: Vec<String>,
+}
+
+impl ListDehasher {
+    /// Create a new [`ListDehasher`] from a ``.lst`` file, that contain one path per line (see [`message_dehash`]).
+    /// Only the file name of each path is tried.
+    pub fn from_lst<F: Read>(list_file: &mut F) -> Result<Self, io::Error> {
+        let mut strings = String::new();
+        list_file.read_to_string(&mut strings)?;
+        Ok(Self {
+            name: "lst".to_string(),
+            names: strings
+                .lines()
+                .filter_map(|line| line.rsplit('/').next())
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect(),
+        })
+    }
+
+    /// Create a new [`ListDehasher`] from a word list, that contain one candidate name per line
+    pub fn from_wordlist<F: Read>(wordlist_file: &mut F) -> Result<Self, io::Error> {
+        let mut strings = String::new();
+        wordlist_file.read_to_string(&mut strings)?;
+        Ok(Self {
+            name: "wordlist".to_string(),
+            names: strings
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect(),
+        })
+    }
+}
+
+impl Dehasher for ListDehasher {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn find_names(&self, _input: &DehashInput) -> Result<Vec<String>, DehashError> {
+        Ok(self.names.clone())
+    }
+}
+
+/// The result of running a [`Dehasher`] on a [`Farc`](crate::Farc)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DehashReport {
+    /// The name of the [`Dehasher`], as returned by [`Dehasher::name`]
+    pub strategy: String,
+    /// The number of candidate names returned by the [`Dehasher`]
+    pub candidates_tried: usize,
+    /// The newly found names, with their hash
+    pub resolved: Vec<(u32, String)>,
+    /// The candidates that have the same hash as a name found earlier in the same run, but are a different name.
+    /// The first found name is kept, but it may be wrong.
+    pub collisions: Vec<NameCollision>,
+}
+
+/// How and when the name of a file was found by a [`Dehasher`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NameResolution {
+    /// The found name
+    pub name: String,
+    /// The name of the [`Dehasher`] that found it
+    pub strategy: String,
+    /// When the name was found. This is [`SystemTime::UNIX_EPOCH`] on ``wasm32-unknown-unknown``, which has no clock.
+    pub resolved_at: SystemTime,
+}
+
+/// Two different candidate names with the same hash, found during the same dehash run
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NameCollision {
+    /// The hash of both names
+    pub hash: u32,
+    /// The name that was accepted, because it was found first
+    pub accepted: String,
+    /// The other name with the same hash
+    pub rejected: String,
+}
+
+impl DehashReport {
+    /// Create a new empty [`DehashReport`] for the strategy with the given name
+    #[must_use]
+    pub fn new(strategy: &str) -> Self {
+        Self {
+            strategy: strategy.to_string(),
+            candidates_tried: 0,
+            resolved: Vec::new(),
+            collisions: Vec::new(),
+        }
+    }
+}
+
 /// contain useful function to get the original name of message* farc files.
 pub mod message_dehash {
     use crate::Farc;
     use std::io;
-    use std::io::{Read, Seek};
+    use std::io::{BufRead, BufReader, Read, Seek, Write};
 
     /// get the file name of the list file that should be openened
     ///
@@ -57,6 +241,43 @@ pub mod message_dehash {
         Some(original_file_name.split('.').next()?.to_string() + ".lst")
     }
 
+    /// Write a list file, in the same format as the ones read by [`try_possible_name`], with one line for every file
+    /// whose name is known, in the order of the archive. Each name is prefixed by ``directory`` (like ``message/``),
+    /// as the list files shipped with the game contain the path of the files, and not only their names.
+    ///
+    /// Return the number of files that were skipped because their name is unknown.
+    ///
+    /// # Example
+    /// ```
+    /// use pmd_farc::{hash_name, message_dehash::write_list_file, Farc, FarcWriter};
+    /// use std::io::Cursor;
+    /// let mut writer = FarcWriter::default();
+    /// writer.add_hashed_file(hash_name("script_001.bin"), Vec::new());
+    /// writer.add_hashed_file(hash_name("script_002.bin"), Vec::new());
+    /// let mut archive = Cursor::new(Vec::new());
+    /// writer.write_hashed(&mut archive).unwrap();
+    /// let mut farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+    /// farc.check_file_name("script_001.bin");
+    ///
+    /// let mut list_file = Vec::new();
+    /// assert_eq!(write_list_file(&farc, "message/", &mut list_file).unwrap(), 1);
+    /// assert_eq!(list_file, b"message/script_001.bin\n");
+    /// ```
+    pub fn write_list_file<FT: Read + Seek, W: Write>(
+        farc: &Farc<FT>,
+        directory: &str,
+        list_file: &mut W,
+    ) -> Result<usize, io::Error> {
+        let mut skipped = 0;
+        for (_, name) in farc.iter() {
+            match name {
+                Some(name) => writeln!(list_file, "{}{}", directory, name)?,
+                None => skipped += 1,
+            };
+        }
+        Ok(skipped)
+    }
+
     /// Try to find the name of files in a farc based on a an input file file, that contain lines of path
     /// (sometimes found as adjacent .lst files to .bin files)
     ///
@@ -65,22 +286,219 @@ pub mod message_dehash {
         farc: &mut Farc<FT>,
         list_file: &mut F,
     ) -> Result<(), io::Error> {
-        let mut strings = String::new();
-        list_file.read_to_string(&mut strings)?;
+        try_possible_name_buf_read(farc, BufReader::new(list_file))
+    }
 
-        for line in strings.split('\n') {
-            if line.is_empty() {
-                continue;
-            };
-            if let Some(file_name) = line.split('/').last() {
-                if !farc.check_file_name(file_name) {
-                    debug!(
-                        "the file name {} can't be found in a message farc archive",
-                        file_name
-                    );
-                };
+    /// Same as [`try_possible_name`], but read the list file line by line, so it doesn't need to be fully loaded in memory
+    pub fn try_possible_name_buf_read<F: BufRead, FT: Read + Seek>(
+        farc: &mut Farc<FT>,
+        list_file: F,
+    ) -> Result<(), io::Error> {
+        for line in list_file.lines() {
+            try_possible_name_line(farc, &line?);
+        }
+        Ok(())
+    }
+
+    /// Same as [`try_possible_name`], but take an iterator over the lines of path (either ``&str`` or ``String``), so
+    /// they don't need to be materialized in a single ``String``
+    ///
+    /// # Example
+    /// ```
+    /// use pmd_farc::{hash_name, message_dehash::try_possible_name_iter, Farc, FarcWriter};
+    /// use std::io::Cursor;
+    /// let mut writer = FarcWriter::default();
+    /// writer.add_hashed_file(hash_name("script_001.bin"), Vec::new());
+    /// let mut archive = Cursor::new(Vec::new());
+    /// writer.write_hashed(&mut archive).unwrap();
+    /// let mut farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+    ///
+    /// let lines = (0..1000).map(|id| format!("message/script_{:03}.bin", id));
+    /// try_possible_name_iter(&mut farc, lines);
+    /// try_possible_name_iter(&mut farc, "message/script_002.bin\nmessage/script_003.bin".lines());
+    /// assert_eq!(farc.file_known_name(), 1);
+    /// ```
+    pub fn try_possible_name_iter<S: AsRef<str>, I: Iterator<Item = S>, FT: Read + Seek>(
+        farc: &mut Farc<FT>,
+        lines: I,
+    ) {
+        for line in lines {
+            try_possible_name_line(farc, line.as_ref());
+        }
+    }
+
+    /// The locale suffixes used by the localized message archives
+    pub const LOCALES: [&str; 6] = ["en", "fr", "ge", "it", "sp", "us"];
+
+    /// Return the locale of a localized message archive, based on its name
+    ///
+    /// # Example
+    /// ```
+    /// use pmd_farc::message_dehash::get_locale;
+    /// assert_eq!(get_locale("message_debug_fr.bin"), Some("fr"));
+    /// assert_eq!(get_locale("message.bin"), None);
+    /// ```
+    #[must_use]
+    pub fn get_locale(original_file_name: &str) -> Option<&'static str> {
+        let stem = original_file_name.split('.').next()?;
+        LOCALES
+            .iter()
+            .find(|locale| stem.ends_with(&format!("_{}", locale)))
+            .copied()
+    }
+
+    /// Return the file name in the line, with variant for the given locale: the file name itself, the file name
+    /// without any locale suffix, and the file name with the ``_{locale}`` suffix.
+    ///
+    /// # Example
+    /// ```
+    /// use pmd_farc::message_dehash::expand_locale;
+    /// assert_eq!(
+    ///     expand_locale("message/dungeon_us.bin", "fr"),
+    ///     vec!["dungeon_us.bin", "dungeon.bin", "dungeon_fr.bin"]
+    /// );
+    /// ```
+    #[must_use]
+    pub fn expand_locale(line: &str, locale: &str) -> Vec<String> {
+        let file_name = match line.rsplit('/').next() {
+            Some(file_name) if !file_name.is_empty() => file_name,
+            _ => return Vec::new(),
+        };
+        let (stem, extension) = match file_name.rfind('.') {
+            Some(dot) => file_name.split_at(dot),
+            None => (file_name, ""),
+        };
+        let base = LOCALES
+            .iter()
+            .find_map(|other| stem.strip_suffix(&format!("_{}", other)))
+            .unwrap_or(stem);
+        let mut candidates = vec![file_name.to_string()];
+        for candidate in [
+            format!("{}{}", base, extension),
+            format!("{}_{}{}", base, locale, extension),
+        ] {
+            if !candidates.contains(&candidate) {
+                candidates.push(candidate);
             };
         }
+        candidates
+    }
+
+    /// Same as [`try_possible_name`], but also try the locale variants of each line (see [`expand_locale`]). This
+    /// permit to use a list file of a locale for the archive of another locale, whose list file is missing.
+    ///
+    /// The locale of an archive can be found with [`get_locale`].
+    pub fn try_possible_name_localized<F: BufRead, FT: Read + Seek>(
+        farc: &mut Farc<FT>,
+        list_file: F,
+        locale: &str,
+    ) -> Result<(), io::Error> {
+        for line in list_file.lines() {
+            for candidate in expand_locale(&line?, locale) {
+                try_possible_name_line(farc, &candidate);
+            }
+        }
         Ok(())
     }
+
+    fn try_possible_name_line<FT: Read + Seek>(farc: &mut Farc<FT>, line: &str) {
+        if line.is_empty() {
+            return;
+        };
+        if let Some(file_name) = line.rsplit('/').next() {
+            if !farc.check_file_name(file_name) {
+                debug!(
+                    "the file name {} can't be found in a message farc archive",
+                    file_name
+                );
+            };
+        };
+    }
+}
+
+/// contain useful function to get the original name of script farc files.
+pub mod script_dehash {
+    use crate::{DehashError, DehashInput, Dehasher, Farc};
+    use std::io::{Read, Seek};
+    use std::ops::Range;
+
+    /// A rule deriving script file names from their id, in the form ``{prefix}{id}{suffix}``, where the id is padded
+    /// with zeros to ``digits`` digits.
+    ///
+    /// # Example
+    /// ```
+    /// use pmd_farc::script_dehash::ScriptNameRule;
+    /// let rule = ScriptNameRule::new("script_", 0..3, 4, ".bin");
+    /// assert_eq!(
+    ///     rule.iter().collect::<Vec<_>>(),
+    ///     vec!["script_0000.bin", "script_0001.bin", "script_0002.bin"]
+    /// );
+    /// ```
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ScriptNameRule {
+        /// The text before the id
+        pub prefix: String,
+        /// The range of id to generate
+        pub ids: Range<u32>,
+        /// The minimal number of digits of the id
+        pub digits: usize,
+        /// The text after the id, usually the extension
+        pub suffix: String,
+    }
+
+    impl ScriptNameRule {
+        /// Create a new [`ScriptNameRule`]
+        #[must_use]
+        pub fn new(prefix: &str, ids: Range<u32>, digits: usize, suffix: &str) -> Self {
+            Self {
+                prefix: prefix.to_string(),
+                ids,
+                digits,
+                suffix: suffix.to_string(),
+            }
+        }
+
+        /// Iterate over the names generated by this rule
+        pub fn iter(&self) -> impl Iterator<Item = String> + '_ {
+            self.ids.clone().map(move |id| {
+                format!(
+                    "{}{:0digits$}{}",
+                    self.prefix,
+                    id,
+                    self.suffix,
+                    digits = self.digits
+                )
+            })
+        }
+    }
+
+    impl Default for ScriptNameRule {
+        /// ``script_0000.bin`` to ``script_9999.bin``
+        fn default() -> Self {
+            Self::new("script_", 0..10000, 4, ".bin")
+        }
+    }
+
+    impl Dehasher for ScriptNameRule {
+        fn name(&self) -> &str {
+            "script id"
+        }
+
+        fn find_names(&self, _input: &DehashInput) -> Result<Vec<String>, DehashError> {
+            Ok(self.iter().collect())
+        }
+    }
+
+    /// Try to find the name of the files of a script farc, with the names generated by each rule.
+    /// Return the number of newly found file names.
+    pub fn try_possible_name<FT: Read + Seek>(
+        farc: &mut Farc<FT>,
+        rules: &[ScriptNameRule],
+    ) -> usize {
+        rules
+            .iter()
+            .flat_map(ScriptNameRule::iter)
+            .filter(|name| farc.check_file_name(name))
+            .count()
+    }
 }
@@ -1,9 +1,18 @@
-use crate::{FarcFile, FileNameError, FileNameIndex};
-use binread::{BinRead, BinReaderExt};
-use byteorder::{ReadBytesExt, LE};
+use crate::coverage::{STRATEGY_ARCHIVE, STRATEGY_OTHER};
+use crate::memory::hash_map_bytes;
+use crate::raw::{FarcParser, ParseStep, RawError, RawHeader, RawName, ReadAt};
+use crate::{
+    hash_name, observer::Observer, suggest_near_names, ContentCache, Coverage, DehashError,
+    DehashInput, DehashReport, Dehasher, FarcFile, FarcObserver, FileNameError, FileNameIndex,
+    MemoryUsage, NameCache, NameCollision, NameConfidence, NameResolution, NameSuggestion,
+    Sir0TableError, Sir0TableReader, DEFAULT_SUGGESTION_CHARSET,
+};
 use io_partition::PartitionMutex;
-use pmd_sir0::{Sir0, Sir0Error};
-use std::io::{self, Read, Seek, SeekFrom};
+use pmd_sir0::Sir0Error;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
 use std::string::FromUtf16Error;
 use std::sync::{Arc, Mutex};
 use thiserror::Error;
@@ -35,9 +44,6 @@ pub enum FarcError {
     /// An error happened while creating an utf16 string
     #[error("An error happened while parsing an utf-16 string")]
     FromUtf16Error(#[from] FromUtf16Error),
-    /// An error caused by parsing the header of the file
-    #[error("An error happened while parsing the header of the file")]
-    ReadHeaderError(#[source] binread::Error),
     /// The sir0 header isn't long enought
     #[error("The sir0 header isn't long enought. It should be (at least) 12 bytes, but it only have {0} bytes")]
     Sir0HeaderNotLongEnought(usize),
@@ -50,113 +56,189 @@ pub enum FarcError {
     /// A sub-file doesn't start at an offset that is a multiple of 16. FARC seem to require this.
     #[error("A sub-file doesn't seem to start at an offset that is a multiple of 16. FARC seem to require this.")]
     FileStartBadAlignement,
+    /// The header or the file table is invalid, for example because a magic is wrong
+    #[error("The structure of the file is invalid: {0}")]
+    InvalidStructure(#[source] RawError),
 }
 
-fn read_null_terminated_utf16_string<T: Read>(file: &mut T) -> Result<String, FarcError> {
-    let mut buffer: Vec<u16> = Vec::new();
-    loop {
-        let chara = file.read_u16::<LE>()?;
-        if chara == 0 {
-            break;
-        };
-        buffer.push(chara);
+impl From<RawError> for FarcError {
+    fn from(err: RawError) -> Self {
+        match err {
+            RawError::OutOfBounds { .. } => {
+                Self::IOerror(io::Error::new(io::ErrorKind::UnexpectedEof, err))
+            }
+            RawError::Sir0HeaderTooShort(length) => Self::Sir0HeaderNotLongEnought(length as usize),
+            RawError::UnsupportedFat5Type(fat5_type) => Self::UnsuportedFat5Type(fat5_type),
+            RawError::DataStartOverflow(data_offset, offset) => {
+                Self::DataStartOverflow(data_offset, offset)
+            }
+            RawError::BadAlignment(_) => Self::FileStartBadAlignement,
+            RawError::ReadFailed { .. } | RawError::LengthFailed { .. } => {
+                Self::IOerror(io::Error::other(err))
+            }
+            err => Self::InvalidStructure(err),
+        }
     }
-    Ok(String::from_utf16(&buffer)?)
 }
 
-#[derive(BinRead)]
-#[br(little)]
-enum Sir0Type {
-    #[br(magic = 4u32)]
-    Type4,
-    #[br(magic = 5u32)]
-    Type5,
+impl From<Sir0TableError> for FarcError {
+    fn from(err: Sir0TableError) -> Self {
+        match err {
+            Sir0TableError::IOError(err) => Self::IOerror(err),
+            Sir0TableError::Sir0Error(err) => Self::CreateSir0Error(err),
+            Sir0TableError::FromUtf16Error(err) => Self::FromUtf16Error(err),
+            // only happen while writing
+            err => Self::IOerror(io::Error::other(err)),
+        }
+    }
+}
+
+/// Read ``buffer.len()`` bytes at an offset of the archive
+pub(crate) type ReadAtFn<'a> = dyn FnMut(u64, &mut [u8]) -> Result<(), FarcError> + 'a;
+
+/// Read the sir0 section described by ``header``, and check its header and pointer list, which
+/// [`FarcParser`] doesn't. Return it with its offset.
+fn read_sir0_section(
+    file_length: u64,
+    read_at: &mut ReadAtFn,
+    header: &RawHeader,
+) -> Result<(u64, Vec<u8>), FarcError> {
+    let offset = u64::from(header.sir0_offset);
+    let length = u64::from(header.sir0_length);
+    // checked before allocating, as the length may be corrupted
+    if offset + length > file_length {
+        return Err(FarcError::PartitionCreationError(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "the sir0 section goes past the end of the file",
+        )));
+    };
+    let mut section = vec![0; length as usize];
+    read_at(offset, &mut section)?;
+    Sir0TableReader::new(Cursor::new(&section[..]))?;
+    Ok((offset, section))
+}
+
+/// Parse the header and the file table of an archive of ``file_length`` bytes, read with ``read_at``. Return the
+/// index of the files, and whether their names are stored in the archive.
+pub(crate) fn parse_index(
+    file_length: u64,
+    read_at: &mut ReadAtFn,
+    observer: &Observer,
+) -> Result<(FileNameIndex, bool), FarcError> {
+    let mut parser = FarcParser::new();
+    let mut buffer = Vec::new();
+    // the whole sir0 section, with its offset, read at once once the header is known
+    let mut sir0 = (0, Vec::new());
+    let mut index = FileNameIndex::default();
+    let mut named_in_archive = false;
+    loop {
+        match parser.next_step() {
+            ParseStep::Read { offset, length } => {
+                let in_sir0 = offset.checked_sub(sir0.0).and_then(|start| {
+                    let start = usize::try_from(start).ok()?;
+                    sir0.1.get(start..start.checked_add(length)?)
+                });
+                match in_sir0 {
+                    Some(bytes) => parser.feed(bytes)?,
+                    None => {
+                        buffer.resize(length, 0);
+                        read_at(offset, &mut buffer)?;
+                        parser.feed(&buffer)?;
+                    }
+                };
+            }
+            ParseStep::Header(header) => sir0 = read_sir0_section(file_length, read_at, &header)?,
+            ParseStep::Table { named, .. } => named_in_archive = named,
+            ParseStep::Entry(entry) => {
+                let hash = entry.name.hash();
+                match entry.name {
+                    RawName::Name(name) => {
+                        index.add_file_with_name(name, entry.start, entry.length)?
+                    }
+                    RawName::Hash(hash) => {
+                        index.add_file_with_hash(hash, entry.start, entry.length)?
+                    }
+                };
+                if let Some(file) = index.get_file_by_hash(hash) {
+                    observer.entry_parsed(file);
+                };
+            }
+            // only reported in lenient mode
+            ParseStep::InvalidEntry { .. } | ParseStep::TableTruncated { .. } => (),
+            ParseStep::Done => return Ok((index, named_in_archive)),
+        }
+    }
 }
 
-#[derive(BinRead)]
-#[br(magic = b"FARC", little)]
-struct FarcHeader {
-    _unk_1: [u8; 0x1C],
-    _sir0_type: Sir0Type,
-    sir0_offset: u32,
-    sir0_lenght: u32,
-    all_data_offset: u32,
-    _lenght_of_all_data: u32,
+/// A source the content of the files can be read from without locking the reader, set by
+/// [`Farc::from_storage`]
+#[derive(Clone)]
+pub(crate) struct SharedStorage(pub(crate) Arc<dyn ReadAt + Send + Sync>);
+
+impl fmt::Debug for SharedStorage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SharedStorage")
+    }
 }
 
 #[derive(Debug)]
 /// A parser for a file in the farc format (see the crate root documentation for more information)
 pub struct Farc<F: Read + Seek> {
     file: Arc<Mutex<F>>,
-    index: FileNameIndex,
+    pub(crate) index: FileNameIndex,
+    name_cache: Option<NameCache>,
+    /// the cache, with the identifier of this archive in it
+    content_cache: Option<(ContentCache, u64)>,
+    observer: Observer,
+    resolutions: HashMap<u32, NameResolution>,
+    named_in_archive: bool,
+    pub(crate) storage: Option<SharedStorage>,
 }
 
 impl<F: Read + Seek> Farc<F> {
     /// Create and parse a new ``Farc`` object, with the specified input file
-    pub fn new(mut file: F) -> Result<Self, FarcError> {
-        let farc_header: FarcHeader = file.read_le().map_err(FarcError::ReadHeaderError)?;
-        let file = Arc::new(Mutex::new(file));
-
-        let sir0_partition = PartitionMutex::new(
-            file.clone(),
-            u64::from(farc_header.sir0_offset),
-            u64::from(farc_header.sir0_lenght),
-        )
-        .map_err(FarcError::PartitionCreationError)?;
-        let mut sir0 = Sir0::new(sir0_partition).map_err(FarcError::CreateSir0Error)?;
-        let h = sir0.get_header();
-        if h.len() < 12 {
-            return Err(FarcError::Sir0HeaderNotLongEnought(h.len()));
-        };
-        let sir0_data_offset = u32::from_le_bytes([h[0], h[1], h[2], h[3]]);
-        let file_count = u32::from_le_bytes([h[4], h[5], h[6], h[7]]);
-        let sir0_fat5_type = u32::from_le_bytes([h[8], h[9], h[10], h[11]]);
-
-        let entry_lenght = match sir0_fat5_type {
-            0 => 12, //TODO: difference with the evandixon implementation
-            1 => 12,
-            x => return Err(FarcError::UnsuportedFat5Type(x)),
-        };
+    pub fn new(file: F) -> Result<Self, FarcError> {
+        Self::new_with(file, Observer::default())
+    }
 
-        let mut index = FileNameIndex::default();
-        let mut sir0_file = sir0.get_file();
-        for file_index in 0..(file_count) {
-            sir0_file.seek(SeekFrom::Start(
-                u64::from(sir0_data_offset) + u64::from(file_index * entry_lenght),
-            ))?;
-            let filename_offset_or_hash = sir0_file.read_u32::<LE>()?;
-            let data_offset = sir0_file.read_u32::<LE>()?;
-            let data_length = sir0_file.read_u32::<LE>()?;
-
-            let data_start = farc_header
-                .all_data_offset
-                .checked_add(data_offset)
-                .map_or_else(
-                    || {
-                        Err(FarcError::DataStartOverflow(
-                            farc_header.all_data_offset,
-                            data_offset,
-                        ))
-                    },
-                    Ok,
-                )?;
-            
-            if data_start % 16 != 0 {
-                return Err(FarcError::FileStartBadAlignement);
-            };
+    /// Create and parse a new ``Farc`` object, giving the parsed entries, and later the resolved names and the
+    /// warnings, to ``observer``
+    pub fn new_observed(file: F, observer: Arc<dyn FarcObserver>) -> Result<Self, FarcError> {
+        Self::new_with(file, Observer::new(observer))
+    }
 
-            match sir0_fat5_type {
-                0 => {
-                    sir0_file.seek(SeekFrom::Start(u64::from(filename_offset_or_hash)))?;
-                    let name = read_null_terminated_utf16_string(&mut sir0_file)?;
-                    index.add_file_with_name(name, data_start, data_length)?;
-                }
-                1 => index.add_file_with_hash(filename_offset_or_hash, data_start, data_length)?,
-                x => return Err(FarcError::UnsuportedFat5Type(x)),
-            };
-        }
+    fn new_with(mut file: F, observer: Observer) -> Result<Self, FarcError> {
+        let file_length = file.seek(SeekFrom::End(0))?;
+        let (index, named_in_archive) = parse_index(
+            file_length,
+            &mut |offset, buffer| {
+                file.seek(SeekFrom::Start(offset))?;
+                file.read_exact(buffer)?;
+                Ok(())
+            },
+            &observer,
+        )?;
+        let mut farc = Self::from_index(Arc::new(Mutex::new(file)), index, named_in_archive);
+        farc.observer = observer;
+        Ok(farc)
+    }
 
-        Ok(Self { file, index })
+    /// Create a ``Farc`` from an already parsed index
+    pub(crate) fn from_index(
+        file: Arc<Mutex<F>>,
+        index: FileNameIndex,
+        named_in_archive: bool,
+    ) -> Self {
+        Self {
+            file,
+            index,
+            name_cache: None,
+            content_cache: None,
+            observer: Observer::default(),
+            resolutions: HashMap::new(),
+            named_in_archive,
+            storage: None,
+        }
     }
 
     /// return the number of file contained in this ``Farc`` file
@@ -165,42 +247,91 @@ impl<F: Read + Seek> Farc<F> {
         self.index.len()
     }
 
-    /// return the number of file with an unknown name in this ``Farc`` file
+    /// return true if the file names are stored in this ``Farc`` file, false if only their hash are stored
+    #[must_use]
+    pub const fn is_named_in_archive(&self) -> bool {
+        self.named_in_archive
+    }
+
+    /// return the number of file with an unknown name in this ``Farc`` file. A name that isn't
+    /// [`NameConfidence::Verified`] is still unknown.
     #[must_use]
     pub fn file_unknown_name(&self) -> usize {
-        self.index.iter().filter(|f| f.name.is_none()).count()
+        self.file_count() - self.file_known_name()
     }
 
-    /// return the number of file with a known name in this ``Farc`` file
+    /// return the number of file with a [`NameConfidence::Verified`] name in this ``Farc`` file
     #[must_use]
     pub fn file_known_name(&self) -> usize {
-        self.index.iter().filter(|f| f.name.is_some()).count()
+        self.index
+            .iter()
+            .filter(|f| f.name_confidence == Some(NameConfidence::Verified))
+            .count()
     }
 
     /// iter over the known name of file
-    pub fn iter_name(&self) -> impl Iterator<Item = &String> {
-        self.index.iter().filter_map(|e| e.name.as_ref())
+    pub fn iter_name(&self) -> impl Iterator<Item = &str> {
+        self.index.iter().filter_map(|e| e.name.as_deref())
+    }
+
+    /// iter over the known name of file, with a confidence of at least ``minimum_confidence``
+    ///
+    /// # Example
+    /// ```
+    /// use pmd_farc::{hash_name, Farc, FarcWriter, NameConfidence};
+    /// use std::io::Cursor;
+    /// let mut writer = FarcWriter::default();
+    /// writer.add_hashed_file(hash_name("script_001.bin"), Vec::new());
+    /// writer.add_hashed_file(0x1234, Vec::new());
+    /// let mut archive = Cursor::new(Vec::new());
+    /// writer.write_hashed(&mut archive).unwrap();
+    /// let mut farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+    ///
+    /// farc.check_file_name("script_001.bin");
+    /// farc.set_file_name(0x1234, "probably_a_model.bgrs", NameConfidence::Guessed).unwrap();
+    /// assert_eq!(farc.iter_name().count(), 2);
+    /// assert_eq!(
+    ///     farc.iter_name_with_confidence(NameConfidence::Verified).collect::<Vec<_>>(),
+    ///     vec!["script_001.bin"]
+    /// );
+    /// ```
+    pub fn iter_name_with_confidence(
+        &self,
+        minimum_confidence: NameConfidence,
+    ) -> impl Iterator<Item = &str> {
+        self.index
+            .iter()
+            .filter_map(move |e| match e.name_confidence {
+                Some(confidence) if confidence >= minimum_confidence => e.name.as_deref(),
+                _ => None,
+            })
     }
 
-    /// iter over all the hash without an occording known name
-    pub fn iter_hash_unknown_name(&self) -> impl Iterator<Item = &u32> {
+    /// iter over all the hash without an occording known name. The files with a guessed or forced name are
+    /// included, as their real name is still unknown.
+    pub fn iter_hash_unknown_name(&self) -> impl Iterator<Item = u32> + '_ {
         self.index.iter().filter_map(|e| {
-            if e.name.is_some() {
+            if e.name_confidence == Some(NameConfidence::Verified) {
                 None
             } else {
-                Some(&e.name_hash)
+                Some(e.name_hash)
             }
         })
     }
 
     /// iterate over all the known file, with their hash and (optionaly) their name.
-    pub fn iter(&self) -> impl Iterator<Item = (u32, Option<&String>)> {
-        self.index.iter().map(|f| (f.name_hash, f.name.as_ref()))
+    pub fn iter(&self) -> impl Iterator<Item = (u32, Option<&str>)> {
+        self.index.iter().map(|f| (f.name_hash, f.name.as_deref()))
+    }
+
+    /// iterate over the entry of every file, with their position and length in the ``Farc`` file
+    pub fn iter_files(&self) -> impl Iterator<Item = &FarcFile> {
+        self.index.iter()
     }
 
     /// Iter over all the hash
-    pub fn iter_all_hash(&self) -> impl Iterator<Item = &u32> {
-        self.index.iter().map(|e| &e.name_hash)
+    pub fn iter_all_hash(&self) -> impl Iterator<Item = u32> + '_ {
+        self.index.iter().map(|e| e.name_hash)
     }
 
     /// Return an handle to a file stored in this ``Farc``, from it's name. It will hash the name as necessary.
@@ -212,6 +343,37 @@ impl<F: Read + Seek> Farc<F> {
         self.create_partition_from_data(file_data)
     }
 
+    /// Like [`Farc::get_named_file`], but with the hash of the name already computed by the caller, so it
+    /// isn't hashed again when the name isn't directly known.
+    ///
+    /// ```
+    /// use pmd_farc::{hash_name, Farc, FarcWriter};
+    /// use std::io::{Cursor, Read};
+    /// let mut writer = FarcWriter::default();
+    /// writer.add_hashed_file(hash_name("script_001.bin"), b"content".to_vec());
+    /// let mut archive = Cursor::new(Vec::new());
+    /// writer.write_hashed(&mut archive).unwrap();
+    /// let farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+    ///
+    /// let hash = hash_name("script_001.bin");
+    /// let mut content = Vec::new();
+    /// farc.get_named_file_hashed("script_001.bin", hash).unwrap().read_to_end(&mut content).unwrap();
+    /// assert!(content.starts_with(b"content"));
+    /// assert!(farc.read_named_file_hashed("script_001.bin", hash).unwrap().starts_with(b"content"));
+    /// assert!(farc.get_named_file_hashed("other.bin", hash_name("other.bin")).is_err());
+    /// ```
+    pub fn get_named_file_hashed(
+        &self,
+        name: &str,
+        hash: u32,
+    ) -> Result<PartitionMutex<F>, FarcError> {
+        let file_data = match self.index.get_file_by_name_hashed(name, hash) {
+            Some(value) => value,
+            None => return Err(FarcError::NamedFileNotFound(name.to_string())),
+        };
+        self.create_partition_from_data(file_data)
+    }
+
     /// Return an handle to a file, whether its name is known or not.
     pub fn get_hashed_file(&self, hash: u32) -> Result<PartitionMutex<F>, FarcError> {
         let file_data = match self.index.get_file_by_hash(hash) {
@@ -221,6 +383,73 @@ impl<F: Read + Seek> Farc<F> {
         self.create_partition_from_data(file_data)
     }
 
+    /// Read the whole content of a file, whether its name is known or not. If this ``Farc`` has a
+    /// [`ContentCache`], the content is taken from it if possible, and added to it otherwise.
+    pub fn read_hashed_file(&self, hash: u32) -> Result<Arc<[u8]>, FarcError> {
+        if let Some((cache, archive)) = &self.content_cache {
+            if let Some(content) = cache.get(*archive, hash) {
+                return Ok(content);
+            };
+        };
+        let content: Arc<[u8]> = match &self.storage {
+            // read at the position of the file, without locking the reader
+            Some(SharedStorage(storage)) => {
+                let file = self
+                    .index
+                    .get_file_by_hash(hash)
+                    .ok_or(FarcError::HashedFileNotFound(hash))?;
+                let start = u64::from(file.start);
+                let length = u64::from(file.length);
+                // checked before allocating, as the length may be corrupted
+                if start + length > storage.len()? {
+                    return Err(RawError::OutOfBounds {
+                        offset: start,
+                        length,
+                    }
+                    .into());
+                };
+                let mut content = vec![0; file.length as usize];
+                storage.read_exact_at(start, &mut content)?;
+                content.into()
+            }
+            None => {
+                let mut content = Vec::new();
+                self.get_hashed_file(hash)?.read_to_end(&mut content)?;
+                content.into()
+            }
+        };
+        if let Some((cache, archive)) = &self.content_cache {
+            cache.insert(*archive, hash, content.clone());
+        };
+        Ok(content)
+    }
+
+    /// Read the whole content of a file with this name, like [`Farc::read_hashed_file`]
+    pub fn read_named_file(&self, name: &str) -> Result<Arc<[u8]>, FarcError> {
+        let hash = match self.index.get_file_by_name(name) {
+            Some(file) => file.name_hash,
+            None => return Err(FarcError::NamedFileNotFound(name.to_string())),
+        };
+        self.read_hashed_file(hash)
+    }
+
+    /// Read the whole content of a file with this name, with the hash of the name already computed by the
+    /// caller, like [`Farc::get_named_file_hashed`]
+    pub fn read_named_file_hashed(&self, name: &str, hash: u32) -> Result<Arc<[u8]>, FarcError> {
+        let hash = match self.index.get_file_by_name_hashed(name, hash) {
+            Some(file) => file.name_hash,
+            None => return Err(FarcError::NamedFileNotFound(name.to_string())),
+        };
+        self.read_hashed_file(hash)
+    }
+
+    /// Keep the content of the files read by [`Farc::read_hashed_file`] (and by the dehashers reading files) in a
+    /// [`ContentCache`], that may be shared with other ``Farc``
+    pub fn set_content_cache(&mut self, cache: ContentCache) {
+        let archive = cache.register();
+        self.content_cache = Some((cache, archive));
+    }
+
     fn create_partition_from_data(
         &self,
         file_data: &FarcFile,
@@ -234,8 +463,179 @@ impl<F: Read + Seek> Farc<F> {
     }
 
     /// Check if the file name correspond to an hash. If it is the case, it replace the hash with name.
+    ///
+    /// The found name is also added to the [`NameCache`] of this ``Farc``, if any.
     pub fn check_file_name(&mut self, name: &str) -> bool {
-        self.index.check_file_name(name)
+        self.resolve_name(name, None)
+    }
+
+    fn resolve_name(&mut self, name: &str, strategy: Option<&str>) -> bool {
+        let found = self.index.check_file_name(name);
+        if found {
+            let hash = hash_name(name);
+            if let Some(name_cache) = &self.name_cache {
+                name_cache.insert(hash, name.to_string());
+            };
+            self.observer.name_resolved(hash, name, strategy);
+        };
+        found
+    }
+
+    /// Return the approximate memory held by this ``Farc``, not counting its reader
+    #[must_use]
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let resolutions = self
+            .resolutions
+            .values()
+            .map(|resolution| resolution.name.capacity() + resolution.strategy.capacity())
+            .sum::<usize>();
+        MemoryUsage {
+            index: self.index.memory_usage(),
+            resolutions: hash_map_bytes(&self.resolutions) + resolutions,
+            content_cache: self
+                .content_cache
+                .as_ref()
+                .map_or(0, |(cache, _)| cache.used()),
+            staged: 0,
+        }
+    }
+
+    /// Give the names found from now on and the warnings to ``observer``, replacing the previous one
+    pub fn set_observer(&mut self, observer: Arc<dyn FarcObserver>) {
+        self.observer = Observer::new(observer);
+    }
+
+    /// Return the name of the [`Dehasher`] that found the name of the file with this hash, if it was found with
+    /// [`Farc::dehash_with`]
+    #[must_use]
+    pub fn resolved_by(&self, hash: u32) -> Option<&str> {
+        self.resolutions
+            .get(&hash)
+            .map(|resolution| resolution.strategy.as_str())
+    }
+
+    /// Return how and when the name of the file with this hash was found, if it was found with
+    /// [`Farc::dehash_with`]
+    #[must_use]
+    pub fn name_resolution(&self, hash: u32) -> Option<&NameResolution> {
+        self.resolutions.get(&hash)
+    }
+
+    /// Return how much of the file names are known, by extension and by strategy. Guessed and forced names are only
+    /// counted in [`Coverage::unverified`].
+    ///
+    /// # Example
+    /// ```
+    /// use pmd_farc::{hash_name, Farc, FarcWriter, ListDehasher};
+    /// use std::io::Cursor;
+    /// let mut writer = FarcWriter::default();
+    /// writer.add_hashed_file(hash_name("script_001.bin"), Vec::new());
+    /// writer.add_hashed_file(hash_name("model.bgrs"), Vec::new());
+    /// let mut archive = Cursor::new(Vec::new());
+    /// writer.write_hashed(&mut archive).unwrap();
+    /// let mut farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+    ///
+    /// let wordlist = ListDehasher::from_wordlist(&mut "script_001.bin".as_bytes()).unwrap();
+    /// farc.dehash_with(&[&wordlist]).unwrap();
+    /// let coverage = farc.coverage();
+    /// assert_eq!(coverage.percentage(), 50.0);
+    /// assert_eq!(coverage.by_extension["bin"], 1);
+    /// assert_eq!(coverage.by_strategy["wordlist"], 1);
+    /// ```
+    #[must_use]
+    pub fn coverage(&self) -> Coverage {
+        let mut coverage = Coverage {
+            total: self.file_count(),
+            ..Coverage::default()
+        };
+        for file in self.index.iter() {
+            if file.name_confidence != Some(NameConfidence::Verified) {
+                if file.name.is_some() {
+                    coverage.unverified += 1;
+                };
+                continue;
+            };
+            if let Some(name) = &file.name {
+                let strategy = match self.resolutions.get(&file.name_hash) {
+                    Some(resolution) => resolution.strategy.as_str(),
+                    None if self.named_in_archive => STRATEGY_ARCHIVE,
+                    None => STRATEGY_OTHER,
+                };
+                coverage.add_named(name, strategy);
+            };
+        }
+        coverage
+    }
+
+    /// Set the name of the file with the given hash, without checking that the hash of the name match. The
+    /// confidence should be [`NameConfidence::Forced`] or [`NameConfidence::Guessed`]. A name set this way is
+    /// replaced if a matching name is later found with [`Farc::check_file_name`].
+    pub fn set_file_name(
+        &mut self,
+        hash: u32,
+        name: &str,
+        confidence: NameConfidence,
+    ) -> Result<(), FarcError> {
+        Ok(self.index.set_file_name(hash, name, confidence)?)
+    }
+
+    /// Share resolved names with other ``Farc`` using the same [`NameCache`]. Names already known by this ``Farc``
+    /// are added to the cache, and names in the cache are applied to this ``Farc``.
+    ///
+    /// Names found later with [`Farc::check_file_name`] are automatically added to the cache. Call
+    /// [`Farc::sync_name_cache`] to get names found by other ``Farc`` since then.
+    pub fn set_name_cache(&mut self, name_cache: NameCache) {
+        for (hash, name) in self.iter() {
+            if let Some(name) = name {
+                name_cache.insert(hash, name.to_string());
+            };
+        }
+        self.name_cache = Some(name_cache);
+        self.sync_name_cache();
+    }
+
+    /// Apply the names present in the [`NameCache`] of this ``Farc`` (if any). Return the number of newly found names.
+    pub fn sync_name_cache(&mut self) -> usize {
+        let names = match &self.name_cache {
+            Some(name_cache) => name_cache.to_vec(),
+            None => return 0,
+        };
+        names
+            .iter()
+            .filter(|(_, name)| self.resolve_name(name, Some("name cache")))
+            .count()
+    }
+
+    /// Return the suggestions (see [`suggest_near_names`]) for every candidate that doesn't match any file, using
+    /// [`DEFAULT_SUGGESTION_CHARSET`] and the extensions of the already known names.
+    pub fn suggest_names<S: AsRef<str>, I: Iterator<Item = S>>(
+        &self,
+        candidates: I,
+    ) -> Vec<NameSuggestion> {
+        let unknown_hashes = self.iter_hash_unknown_name().collect::<HashSet<_>>();
+        let extensions = self
+            .iter_name()
+            .filter_map(|name| name.rfind('.').map(|dot| &name[dot + 1..]))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        candidates
+            .filter(|candidate| self.index.get_file_by_name(candidate.as_ref()).is_none())
+            .flat_map(|candidate| {
+                suggest_near_names(
+                    candidate.as_ref(),
+                    &unknown_hashes,
+                    DEFAULT_SUGGESTION_CHARSET,
+                    &extensions,
+                )
+            })
+            .collect()
+    }
+
+    /// Return the [`NameCache`] used by this ``Farc``, if any
+    #[must_use]
+    pub const fn name_cache(&self) -> Option<&NameCache> {
+        self.name_cache.as_ref()
     }
 
     /// Call ``check_file_name`` repeteatelly with an iterator
@@ -244,4 +644,157 @@ impl<F: Read + Seek> Farc<F> {
             self.check_file_name(&value);
         }
     }
+
+    /// Run each [`Dehasher`] in order, checking the names they return. Each one only receive the hashes that are still
+    /// unknown after the previous ones.
+    ///
+    /// Return a [`DehashReport`] for each [`Dehasher`].
+    ///
+    /// # Example
+    /// ```
+    /// use pmd_farc::{hash_name, Farc, FarcWriter, NameGenerator};
+    /// use std::io::Cursor;
+    /// let mut writer = FarcWriter::default();
+    /// writer.add_hashed_file(hash_name("pikachu_00.bgrs"), Vec::new());
+    /// let mut archive = Cursor::new(Vec::new());
+    /// writer.write_hashed(&mut archive).unwrap();
+    /// let mut farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+    ///
+    /// let mut generator = NameGenerator::new_psmd_graphic();
+    /// generator.add_pokemon_name("pikachu");
+    /// generator.add_form_id(0);
+    /// let reports = farc.dehash_with(&[&generator]).unwrap();
+    /// assert_eq!(reports[0].resolved, vec![(hash_name("pikachu_00.bgrs"), "pikachu_00.bgrs".to_string())]);
+    /// ```
+    pub fn dehash_with(
+        &mut self,
+        dehashers: &[&dyn Dehasher],
+    ) -> Result<Vec<DehashReport>, DehashError> {
+        let mut reports = Vec::new();
+        // names accepted during this run, to detect the collisions
+        let mut accepted: HashMap<u32, String> = HashMap::new();
+        for dehasher in dehashers {
+            let unknown_hashes = self.iter_hash_unknown_name().collect::<HashSet<_>>();
+            let candidates = {
+                let read_file = |hash| self.read_hashed_file(hash);
+                dehasher.find_names(&DehashInput::new(&unknown_hashes, &read_file))?
+            };
+            let mut report = DehashReport::new(dehasher.name());
+            for name in candidates {
+                report.candidates_tried += 1;
+                let hash = hash_name(&name);
+                if let Some(accepted_name) = accepted.get(&hash) {
+                    if accepted_name != &name {
+                        self.observer.warning(&format!(
+                            "the names {:?} and {:?} have the same hash {:08x}",
+                            accepted_name, name, hash
+                        ));
+                        report.collisions.push(NameCollision {
+                            hash,
+                            accepted: accepted_name.clone(),
+                            rejected: name,
+                        });
+                    };
+                    continue;
+                };
+                // skip the names of already resolved files without going through the index
+                if unknown_hashes.contains(&hash) && self.resolve_name(&name, Some(dehasher.name()))
+                {
+                    self.resolutions.insert(
+                        hash,
+                        NameResolution {
+                            name: name.clone(),
+                            strategy: dehasher.name().to_string(),
+                            resolved_at: crate::clock::now(),
+                        },
+                    );
+                    accepted.insert(hash, name.clone());
+                    report.resolved.push((hash, name));
+                };
+            }
+            reports.push(report);
+        }
+        Ok(reports)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::SyntheticFarc;
+    use crate::{ListDehasher, NameCollision, UnknownHashes};
+
+    #[test]
+    fn guessed_names_stay_unknown() {
+        let mut farc = SyntheticFarc::new()
+            .with_hashed_file(hash_name("a.bin"), vec![1; 16])
+            .with_hashed_file(hash_name("b.bin"), vec![2; 16])
+            .build_farc();
+        farc.check_file_name("b.bin");
+        farc.set_file_name(hash_name("a.bin"), "guess.bin", NameConfidence::Guessed)
+            .unwrap();
+
+        assert_eq!(farc.file_unknown_name(), 1);
+        assert_eq!(farc.file_known_name(), 1);
+        assert_eq!(
+            farc.iter_hash_unknown_name().collect::<Vec<_>>(),
+            vec![hash_name("a.bin")]
+        );
+        assert!(UnknownHashes::new_from_farc(&farc).contains(hash_name("a.bin")));
+        let coverage = farc.coverage();
+        assert_eq!((coverage.named, coverage.unverified), (1, 1));
+        assert_eq!(coverage.by_extension["bin"], 1);
+
+        // the verified name replace the guessed one
+        let wordlist = ListDehasher::from_wordlist(&mut "a.bin".as_bytes()).unwrap();
+        let reports = farc.dehash_with(&[&wordlist]).unwrap();
+        assert_eq!(
+            reports[0].resolved,
+            vec![(hash_name("a.bin"), "a.bin".to_string())]
+        );
+        let mut names = farc.iter_name().collect::<Vec<_>>();
+        names.sort_unstable();
+        assert_eq!(names, vec!["a.bin", "b.bin"]);
+        assert_eq!(farc.file_unknown_name(), 0);
+        let coverage = farc.coverage();
+        assert_eq!((coverage.named, coverage.unverified), (2, 0));
+        assert_eq!(coverage.by_strategy["wordlist"], 1);
+    }
+
+    #[test]
+    fn dehash_reports_collisions() {
+        // two different names with the same crc32
+        let (first, second) = ("akrpnryykm.bin", "qjtppzjhvo.bin");
+        let hash = hash_name(first);
+        assert_eq!(hash_name(second), hash);
+        let collision = NameCollision {
+            hash,
+            accepted: first.to_string(),
+            rejected: second.to_string(),
+        };
+        let synthetic = SyntheticFarc::new()
+            .with_hashed_file(hash, vec![1; 16])
+            .with_hashed_file(hash_name("other.bin"), vec![2; 16]);
+
+        // in the candidates of a single dehasher, the repeated name isn't a collision
+        let mut farc = synthetic.build_farc();
+        let wordlist = ListDehasher::from_wordlist(
+            &mut format!("{}\n{}\n{}\n", first, first, second).as_bytes(),
+        )
+        .unwrap();
+        let reports = farc.dehash_with(&[&wordlist]).unwrap();
+        assert_eq!(reports[0].resolved, vec![(hash, first.to_string())]);
+        assert_eq!(reports[0].collisions, vec![collision.clone()]);
+        assert_eq!(farc.iter_name().collect::<Vec<_>>(), vec![first]);
+
+        // between two dehashers of the same run
+        let mut farc = synthetic.build_farc();
+        let first_list = ListDehasher::from_wordlist(&mut first.as_bytes()).unwrap();
+        let second_list = ListDehasher::from_wordlist(&mut second.as_bytes()).unwrap();
+        let reports = farc.dehash_with(&[&first_list, &second_list]).unwrap();
+        assert_eq!(reports[0].collisions, Vec::new());
+        assert_eq!(reports[1].resolved, Vec::new());
+        assert_eq!(reports[1].collisions, vec![collision]);
+        assert_eq!(farc.iter_name().collect::<Vec<_>>(), vec![first]);
+    }
 }
@@ -1,5 +1,32 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// How much a file name can be trusted. Variants are ordered from the least to the most trusted.
+pub enum NameConfidence {
+    /// The name was guessed heuristically, and doesn't match the hash of the file
+    Guessed,
+    /// The name was assigned by the user, without checking it against the hash of the file
+    Forced,
+    /// The hash of the name match the hash of the file (this is always the case for names stored in the archive)
+    Verified,
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Represent a file stored in a farc file
+///
+/// With the ``serde`` feature, it (and the other metadata and report types) can be serialized:
+/// ```
+/// # #[cfg(feature = "serde")]
+/// # {
+/// use pmd_farc::FarcFile;
+/// let file = FarcFile::new(0x100, 16, 0x1234abcd, Some("a.bin".to_string()));
+/// let json = serde_json::to_string(&file).unwrap();
+/// assert_eq!(
+///     json,
+///     r#"{"start":256,"length":16,"name_hash":305441741,"name":"a.bin","name_confidence":"Verified"}"#
+/// );
+/// # }
+/// ```
 pub struct FarcFile {
     /// The offset since the beggining of the farc file this subfile is present
     pub start: u32,
@@ -8,18 +35,37 @@ pub struct FarcFile {
     /// the crc32 of the name of this subfile
     pub name_hash: u32,
     /// The name of this subfile
-    pub name: Option<String>,
+    pub(crate) name: Option<String>,
+    /// How much the name of this subfile can be trusted. ``None`` if and only if the name is unknown.
+    pub(crate) name_confidence: Option<NameConfidence>,
 }
 
 impl FarcFile {
-    /// Create a new [`FarcFile`] with the given parameter
+    /// Create a new [`FarcFile`] with the given parameter. The name, if any, is considered [`NameConfidence::Verified`].
     #[must_use]
     pub const fn new(start: u32, length: u32, name_hash: u32, name: Option<String>) -> Self {
+        let name_confidence = match &name {
+            Some(_) => Some(NameConfidence::Verified),
+            None => None,
+        };
         Self {
             start,
             length,
             name_hash,
             name,
+            name_confidence,
         }
     }
+
+    /// Return the name of this subfile, if known
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Return how much the name of this subfile can be trusted, or ``None`` if the name is unknown
+    #[must_use]
+    pub const fn name_confidence(&self) -> Option<NameConfidence> {
+        self.name_confidence
+    }
 }
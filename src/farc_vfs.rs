@@ -0,0 +1,140 @@
+use crate::{Farc, FarcFile};
+use std::fmt::Debug;
+use std::io::{Read, Seek, Write};
+use vfs::error::VfsErrorKind;
+use vfs::{FileSystem, SeekAndRead, VfsFileType, VfsMetadata, VfsResult};
+
+/// A read-only [`vfs::FileSystem`] exposing the files of a [`Farc`] in its root directory (requires the ``vfs``
+/// feature).
+///
+/// Files are named after their name if it is known, and ``0x{hash}.bin`` otherwise, with the hash written as 8
+/// lowercase hexadecimal digits.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, Farc, FarcFs, FarcWriter};
+/// use std::io::Cursor;
+/// use vfs::VfsPath;
+/// let mut writer = FarcWriter::default();
+/// writer.add_hashed_file(hash_name("hello.txt"), b"hello".to_vec());
+/// writer.add_hashed_file(0x12345678, Vec::new());
+/// let mut archive = Cursor::new(Vec::new());
+/// writer.write_hashed(&mut archive).unwrap();
+/// let mut farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+/// farc.check_file_name("hello.txt");
+///
+/// let root: VfsPath = FarcFs::new(farc).into();
+/// let mut file_names = root.read_dir().unwrap().map(|path| path.filename()).collect::<Vec<_>>();
+/// file_names.sort();
+/// assert_eq!(file_names, vec!["0x12345678.bin", "hello.txt"]);
+/// let content = root.join("hello.txt").unwrap().read_to_string().unwrap();
+/// assert_eq!(content.trim_end_matches('\0'), "hello");
+/// ```
+#[derive(Debug)]
+pub struct FarcFs<F: Read + Seek> {
+    farc: Farc<F>,
+}
+
+impl<F: Read + Seek> FarcFs<F> {
+    /// Create a [`FarcFs`] exposing the files of this [`Farc`]
+    #[must_use]
+    pub fn new(farc: Farc<F>) -> Self {
+        Self { farc }
+    }
+
+    /// Return the [`Farc`] exposed by this [`FarcFs`]
+    #[must_use]
+    pub fn farc(&self) -> &Farc<F> {
+        &self.farc
+    }
+
+    /// Return the [`Farc`] exposed by this [`FarcFs`], destroying it
+    #[must_use]
+    pub fn into_inner(self) -> Farc<F> {
+        self.farc
+    }
+
+    fn find_file(&self, path: &str) -> VfsResult<&FarcFile> {
+        let file_name = path.strip_prefix('/').unwrap_or(path);
+        self.farc
+            .iter_files()
+            .find(|entry| vfs_file_name(entry) == file_name)
+            .ok_or_else(|| VfsErrorKind::FileNotFound.into())
+    }
+}
+
+/// Return the name of a file in the file system. A name that can't be used as a vfs path component is replaced
+/// by the hash.
+fn vfs_file_name(entry: &FarcFile) -> String {
+    match &entry.name {
+        Some(name) if !name.is_empty() && name != "." && name != ".." && !name.contains('/') => {
+            name.clone()
+        }
+        _ => format!("0x{:08x}.bin", entry.name_hash),
+    }
+}
+
+fn read_only<T>() -> VfsResult<T> {
+    Err(VfsErrorKind::NotSupported.into())
+}
+
+impl<F: Read + Seek + Send + Debug + 'static> FileSystem for FarcFs<F> {
+    fn read_dir(&self, path: &str) -> VfsResult<Box<dyn Iterator<Item = String> + Send>> {
+        if !path.is_empty() {
+            self.find_file(path)?;
+            return Err(VfsErrorKind::Other("not a directory".to_string()).into());
+        };
+        let file_names = self
+            .farc
+            .iter_files()
+            .map(vfs_file_name)
+            .collect::<Vec<_>>();
+        Ok(Box::new(file_names.into_iter()))
+    }
+
+    fn create_dir(&self, _path: &str) -> VfsResult<()> {
+        read_only()
+    }
+
+    fn open_file(&self, path: &str) -> VfsResult<Box<dyn SeekAndRead + Send>> {
+        let hash = self.find_file(path)?.name_hash;
+        let file = self
+            .farc
+            .get_hashed_file(hash)
+            .map_err(|err| VfsErrorKind::Other(err.to_string()))?;
+        Ok(Box::new(file))
+    }
+
+    fn create_file(&self, _path: &str) -> VfsResult<Box<dyn Write + Send>> {
+        read_only()
+    }
+
+    fn append_file(&self, _path: &str) -> VfsResult<Box<dyn Write + Send>> {
+        read_only()
+    }
+
+    fn metadata(&self, path: &str) -> VfsResult<VfsMetadata> {
+        if path.is_empty() {
+            return Ok(VfsMetadata {
+                file_type: VfsFileType::Directory,
+                len: 0,
+            });
+        };
+        Ok(VfsMetadata {
+            file_type: VfsFileType::File,
+            len: u64::from(self.find_file(path)?.length),
+        })
+    }
+
+    fn exists(&self, path: &str) -> VfsResult<bool> {
+        Ok(path.is_empty() || self.find_file(path).is_ok())
+    }
+
+    fn remove_file(&self, _path: &str) -> VfsResult<()> {
+        read_only()
+    }
+
+    fn remove_dir(&self, _path: &str) -> VfsResult<()> {
+        read_only()
+    }
+}
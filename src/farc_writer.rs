@@ -1,12 +1,20 @@
 use byteorder::{WriteBytesExt, LE};
-use io::{copy, SeekFrom};
-use pmd_sir0::{write_sir0_footer, write_sir0_header, Sir0WriteFooterError};
+use io::{IoSlice, SeekFrom};
+use pmd_sir0::Sir0WriteFooterError;
 use thiserror::Error;
 
-use crate::{Farc, FarcError};
+use crate::memory::hash_map_bytes;
+use crate::observer::Observer;
+use crate::raw::RawName;
+use crate::{
+    hash_name, Farc, FarcError, FarcObserver, Fat5Entry, Fat5Table, Fat5TableError, MemoryUsage,
+    Sir0TableError,
+};
 use std::io::{Read, Seek, Write};
+use std::mem::size_of;
+use std::sync::Arc;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     convert::TryInto,
     io::{self, Cursor},
     num::TryFromIntError,
@@ -24,27 +32,76 @@ pub enum FarcWriterError {
     /// A [`FarcError`] occured
     #[error("an error originated from the Farc this struct is build from")]
     FarcError(#[from] FarcError),
+    /// A file doesn't have a name, but the archive is written with the file names
+    #[error("the file with the hash {0:08x} doesn't have a name, but the archive is written with the file names")]
+    MissingName(u32),
+    /// The alignment given to [`FarcWriter::set_alignment`] isn't a non-zero multiple of 16
+    #[error("the alignment should be a non-zero multiple of 16, found {0}")]
+    InvalidAlignment(u32),
     /// Too much content are tried to be compressed resulting in an (probably) u32 overflow.
     #[error("The archive is too big. There may be a number of limiting factor. This is usually caused if the result file would take more than 4GiB. You should remove or reduce the size of big files...")]
     TooBig(#[from] TryFromIntError), // alia to TryFromIntError for convenience
 }
 
-#[derive(Default, Debug)]
-/// Represent the content to be written to a FARC file. IT can only create hash-indexed file.
+impl From<Fat5TableError> for FarcWriterError {
+    fn from(err: Fat5TableError) -> Self {
+        match err {
+            Fat5TableError::Sir0TableError(err) => err.into(),
+            Fat5TableError::MissingName(hash) => Self::MissingName(hash),
+            // only happen while reading
+            err => Self::IOError(io::Error::other(err)),
+        }
+    }
+}
+
+impl From<Sir0TableError> for FarcWriterError {
+    fn from(err: Sir0TableError) -> Self {
+        match err {
+            Sir0TableError::IOError(err) => Self::IOError(err),
+            Sir0TableError::Sir0WriteFooterError(err) => Self::Sir0WriteFooterError(err),
+            Sir0TableError::TooBig(err) => Self::TooBig(err),
+            // only happen while reading
+            err => Self::IOError(io::Error::other(err)),
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Represent the content to be written to a FARC file. It can create hash-indexed file with [`FarcWriter::write_hashed`],
+/// or name-indexed file with [`FarcWriter::write_named`] if the name of every file is known.
 pub struct FarcWriter {
     hashed_files: HashMap<u32, Vec<u8>>,
+    names: HashMap<u32, String>,
+    alignment: u32,
+    order: Vec<u32>,
+    observer: Observer,
+}
+
+impl Default for FarcWriter {
+    fn default() -> Self {
+        Self {
+            hashed_files: HashMap::new(),
+            names: HashMap::new(),
+            alignment: 16,
+            order: Vec::new(),
+            observer: Observer::default(),
+        }
+    }
 }
 
 impl FarcWriter {
-    /// Create a new [`FarcWriter`] from an extracted [`Farc`] file
+    /// Create a new [`FarcWriter`] from an extracted [`Farc`] file. The known names are kept.
     pub fn new_from_farc<FT: Read + Seek>(farc: &Farc<FT>) -> Result<Self, FarcWriterError> {
         let mut farc_writer = Self::default();
 
-        for file_hash in farc.iter_all_hash() {
-            let mut file = farc.get_hashed_file(*file_hash)?;
+        for (file_hash, name) in farc.iter() {
+            let mut file = farc.get_hashed_file(file_hash)?;
             let mut buffer = Vec::new();
             file.read_to_end(&mut buffer)?;
-            farc_writer.add_hashed_file(*file_hash, buffer);
+            farc_writer.add_hashed_file(file_hash, buffer);
+            if let Some(name) = name {
+                farc_writer.names.insert(file_hash, name.to_string());
+            };
         }
 
         Ok(farc_writer)
@@ -55,19 +112,157 @@ impl FarcWriter {
         self.hashed_files.insert(hash, content);
     }
 
+    /// Add a file to be written with the given name. Its hash is computed with [`hash_name`].
+    pub fn add_named_file(&mut self, name: String, content: Vec<u8>) {
+        let hash = hash_name(&name);
+        self.hashed_files.insert(hash, content);
+        self.names.insert(hash, name);
+    }
+
+    /// Return the name of the file with the given hash, if it is known
+    #[must_use]
+    pub fn get_name(&self, hash: u32) -> Option<&str> {
+        self.names.get(&hash).map(String::as_str)
+    }
+
+    /// Remove the file with the given hash, returning its content if it was present
+    pub fn remove_hashed_file(&mut self, hash: u32) -> Option<Vec<u8>> {
+        self.names.remove(&hash);
+        self.hashed_files.remove(&hash)
+    }
+
+    /// Return true if a file with the given hash will be written
+    #[must_use]
+    pub fn contains_hash(&self, hash: u32) -> bool {
+        self.hashed_files.contains_key(&hash)
+    }
+
+    /// Iterate over the files that will be written, in no particular order, with their hash, name (if known) and
+    /// content
+    pub fn iter(&self) -> impl Iterator<Item = (u32, Option<&str>, &[u8])> {
+        self.hashed_files
+            .iter()
+            .map(move |(hash, content)| (*hash, self.get_name(*hash), content.as_slice()))
+    }
+
+    /// Return the number of files that will be written
+    #[must_use]
+    pub fn file_count(&self) -> usize {
+        self.hashed_files.len()
+    }
+
+    /// Set the alignment of the start of each file, in bytes. It is 16 by default, like in the archives of the game.
+    ///
+    /// Fail with [`FarcWriterError::InvalidAlignment`] if ``alignment`` isn't a non-zero multiple of 16, as [`Farc`]
+    /// require files to start at a multiple of 16.
+    pub fn set_alignment(&mut self, alignment: u32) -> Result<(), FarcWriterError> {
+        if alignment == 0 || !alignment.is_multiple_of(16) {
+            return Err(FarcWriterError::InvalidAlignment(alignment));
+        };
+        self.alignment = alignment;
+        Ok(())
+    }
+
+    /// Return the alignment of the start of each file, in bytes
+    #[must_use]
+    pub fn alignment(&self) -> u32 {
+        self.alignment
+    }
+
+    /// Set the order the content of the files is stored in, with their hashes. The files that aren't listed are
+    /// stored after them, sorted by hash. The file table is always sorted by hash, as it is searched by dichotomy.
+    ///
+    /// # Example
+    /// ```
+    /// use pmd_farc::{Farc, FarcWriter};
+    /// use std::io::Cursor;
+    /// let mut writer = FarcWriter::default();
+    /// writer.add_hashed_file(1, vec![1; 4]);
+    /// writer.add_hashed_file(2, vec![2; 4]);
+    /// writer.set_order(vec![2, 1]);
+    /// let mut archive = Cursor::new(Vec::new());
+    /// writer.write_hashed(&mut archive).unwrap();
+    /// let farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+    /// let start = |hash| farc.iter_files().find(|file| file.name_hash == hash).unwrap().start;
+    /// assert!(start(2) < start(1));
+    /// ```
+    pub fn set_order(&mut self, order: impl IntoIterator<Item = u32>) {
+        self.order = order.into_iter().collect();
+    }
+
+    /// Return the approximate memory held by this writer, mostly the content of its files
+    #[must_use]
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let names = self.names.values().map(String::capacity).sum::<usize>();
+        MemoryUsage {
+            index: hash_map_bytes(&self.hashed_files)
+                + hash_map_bytes(&self.names)
+                + names
+                + self.order.capacity() * size_of::<u32>(),
+            staged: self.hashed_files.values().map(Vec::capacity).sum(),
+            ..MemoryUsage::default()
+        }
+    }
+
+    /// Tell ``observer`` about each file written from now on
+    pub fn set_observer(&mut self, observer: Arc<dyn FarcObserver>) {
+        self.observer = Observer::new(observer);
+    }
+
     /// Write an hashed Farc file to the given writer, with the content of this struct
     pub fn write_hashed<T: Write + Seek>(&self, file: &mut T) -> Result<(), FarcWriterError> {
-        // sort the hash, as this is a binary tree search
-        let mut hash_sorted = self.hashed_files.iter().collect::<Vec<_>>();
-        hash_sorted.sort();
+        self.write_with_fat5_type(file, false)
+    }
 
-        let mut storage_file: Cursor<Vec<u8>> = Cursor::new(Vec::new());
-        let mut meta_file: Cursor<Vec<u8>> = Cursor::new(Vec::new());
-        meta_file.write_all(&[0; 12])?; // reserve sir0 header space
-        meta_file.write_all(&[0; 4])?; // 0x10 padding
-        let mut meta_pointer = vec![4, 8];
+    /// Write a Farc file storing the file names to the given writer, with the content of this struct. Fail with
+    /// [`FarcWriterError::MissingName`] if the name of a file isn't known.
+    ///
+    /// # Example
+    /// ```
+    /// use pmd_farc::{Farc, FarcWriter};
+    /// use std::io::Cursor;
+    /// let mut writer = FarcWriter::default();
+    /// writer.add_named_file("script_001.bin".to_string(), vec![1, 2, 3]);
+    /// let mut archive = Cursor::new(Vec::new());
+    /// writer.write_named(&mut archive).unwrap();
+    /// let farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+    /// assert!(farc.is_named_in_archive());
+    /// assert!(farc.get_named_file("script_001.bin").is_ok());
+    /// ```
+    pub fn write_named<T: Write + Seek>(&self, file: &mut T) -> Result<(), FarcWriterError> {
+        self.write_with_fat5_type(file, true)
+    }
 
-        for (file_hash, file_content) in hash_sorted {
+    fn write_with_fat5_type<T: Write + Seek>(
+        &self,
+        file: &mut T,
+        named: bool,
+    ) -> Result<(), FarcWriterError> {
+        // the files are stored in the requested order, followed by the others sorted by hash
+        let mut stored = HashSet::with_capacity(self.order.len());
+        let mut storage_order = self
+            .order
+            .iter()
+            .filter(|hash| self.hashed_files.contains_key(hash) && stored.insert(**hash))
+            .filter_map(|hash| self.hashed_files.get_key_value(hash))
+            .collect::<Vec<_>>();
+        let mut others = self
+            .hashed_files
+            .iter()
+            .filter(|(hash, _)| !stored.contains(*hash))
+            .collect::<Vec<_>>();
+        others.sort();
+        storage_order.extend(others);
+
+        // the content of the files, with at most ``alignment`` bytes of padding each
+        let storage_capacity = storage_order
+            .iter()
+            .map(|(_, content)| content.len() + self.alignment as usize)
+            .sum();
+        let mut storage_file: Cursor<Vec<u8>> = Cursor::new(Vec::with_capacity(storage_capacity));
+        let mut entries = Vec::with_capacity(storage_order.len());
+
+        for (file_hash, file_content) in storage_order {
             let file_start = storage_file.position();
             let mut file_lenght = file_content.len();
             storage_file.write_all(file_content)?;
@@ -75,49 +270,50 @@ impl FarcWriter {
             let position = storage_file.position();
             // this padding, althougt being added by the farc file format, seem to be counted in the file lenght.
             //TODO: check this on reading too
-            let padding_lenght = if position % 16 == 0 {
-                16
+            let alignment = u64::from(self.alignment);
+            let padding_lenght = if position.is_multiple_of(alignment) {
+                alignment
             } else {
-                16 - storage_file.position() as usize % 16
-            };
+                alignment - position % alignment
+            } as usize;
             storage_file.write_all(&vec![0; padding_lenght])?;
             file_lenght += padding_lenght;
+            self.observer
+                .file_written(*file_hash, self.get_name(*file_hash), file_lenght as u64);
 
-            meta_file.write_u32::<LE>(*file_hash)?;
-            //TODO: check transformation, resulting in error for too big file
-            meta_file.write_u32::<LE>(file_start.try_into()?)?;
-            //TODO: idem as upper
-            meta_file.write_u32::<LE>(file_lenght.try_into()?)?;
+            let name = if named {
+                let name = self
+                    .names
+                    .get(file_hash)
+                    .ok_or(FarcWriterError::MissingName(*file_hash))?;
+                RawName::Name(name.clone())
+            } else {
+                RawName::Hash(*file_hash)
+            };
+            entries.push((
+                *file_hash,
+                Fat5Entry {
+                    name,
+                    //TODO: check transformation, resulting in error for too big file
+                    offset: file_start.try_into()?,
+                    //TODO: idem as upper
+                    length: file_lenght.try_into()?,
+                },
+            ));
         }
 
-        meta_pointer.push(meta_file.position().try_into()?);
-
-        if meta_file.position() % 16 != 0 {
-            meta_file.write_all(&vec![0; 16 - meta_file.position() as usize % 16])?;
-        };
-
-        let sir0_header_position = meta_file.position().try_into()?;
-        meta_file.write_u32::<LE>(0x10)?; // the start of the sir0 data
-        meta_file.write_u32::<LE>(self.hashed_files.len().try_into()?)?; // number of file //TODO: overflow (unlikely to happen actually)
-        meta_file.write_u32::<LE>(1)?; // meta type -- 1 for hashed name
-
-        if meta_file.position() % 16 != 0 {
-            meta_file.write_all(&vec![0; 16 - meta_file.position() as usize % 16])?;
-        };
-
-        let sir0_footer_position = meta_file.position().try_into()?;
-        write_sir0_footer(&mut meta_file, &meta_pointer)?;
-
-        if meta_file.position() % 16 != 0 {
-            meta_file.write_all(&vec![0; 16 - meta_file.position() as usize % 16])?;
+        // sort the hash, as this is a binary tree search (named files are sorted the same way)
+        entries.sort_by_key(|(hash, _)| *hash);
+        let table = Fat5Table {
+            named,
+            entries: entries.into_iter().map(|(_, entry)| entry).collect(),
         };
 
-        meta_file.seek(SeekFrom::Start(0))?;
-        write_sir0_header(&mut meta_file, sir0_header_position, sir0_footer_position)?;
+        let meta_file = table.to_sir0()?;
 
         //TODO: check for padding after the sir0 file
 
-        let meta_file_lenght = meta_file.seek(SeekFrom::End(0))?.try_into()?;
+        let meta_file_lenght = meta_file.len().try_into()?;
         let storage_file_lenght: u32 = storage_file.seek(SeekFrom::End(0))?.try_into()?;
         let no_padding_storage_start = 0x80 + meta_file_lenght;
         let padding_size_storage_start = if no_padding_storage_start % 256 != 0 {
@@ -128,30 +324,201 @@ impl FarcWriter {
 
         let storage_start = no_padding_storage_start + padding_size_storage_start;
 
-        file.write_all(b"FARC")?; //0x0, magic
-        file.write_u32::<LE>(13434880)?; //0x4, unknown
-        file.write_u32::<LE>(4848240)?; //0x8, idem
-        file.write_u32::<LE>(2)?; //0xC, idem
-        file.write_u32::<LE>(3670016)?; //0x10, idem
-        file.write_u32::<LE>(0)?; //0x14, idem
-        file.write_u32::<LE>(7)?; //0x18, idem
-        file.write_all(&[0xA4, 0x3C, 0xEA, 0x77])?; //0x1C, idem
-        file.write_u32::<LE>(5)?; //0x20, sir 0 type
-        file.write_u32::<LE>(0x80)?; //0x24, offset of the start of the sir0 file
-        file.write_u32::<LE>(meta_file_lenght)?; //0x28, the lenght of the sir0 file.
-        file.write_u32::<LE>(storage_start)?; //0x2C, the offset of the true data.
-        file.write_u32::<LE>(storage_file_lenght + 112)?; //0x30, the lenght of the true data
-                                                          //TODO: why +112
-        file.write_all(&[0; 0x80 - 0x34])?; //0x34 -- padding
-
-        meta_file.seek(SeekFrom::Start(0))?;
-        copy(&mut meta_file, file)?;
-
-        file.write_all(&vec![0; padding_size_storage_start as usize])?;
-
-        storage_file.seek(SeekFrom::Start(0))?;
-        copy(&mut storage_file, file)?;
+        // the header is staged, to be written with the rest in as few calls as possible
+        let mut header = Vec::with_capacity(0x80);
+        header.write_all(b"FARC")?; //0x0, magic
+        header.write_u32::<LE>(13434880)?; //0x4, unknown
+        header.write_u32::<LE>(4848240)?; //0x8, idem
+        header.write_u32::<LE>(2)?; //0xC, idem
+        header.write_u32::<LE>(3670016)?; //0x10, idem
+        header.write_u32::<LE>(0)?; //0x14, idem
+        header.write_u32::<LE>(7)?; //0x18, idem
+        header.write_all(&[0xA4, 0x3C, 0xEA, 0x77])?; //0x1C, idem
+        header.write_u32::<LE>(5)?; //0x20, sir 0 type
+        header.write_u32::<LE>(0x80)?; //0x24, offset of the start of the sir0 file
+        header.write_u32::<LE>(meta_file_lenght)?; //0x28, the lenght of the sir0 file.
+        header.write_u32::<LE>(storage_start)?; //0x2C, the offset of the true data.
+        header.write_u32::<LE>(storage_file_lenght + 112)?; //0x30, the lenght of the true data
+                                                            //TODO: why +112
+        header.write_all(&[0; 0x80 - 0x34])?; //0x34 -- padding
+
+        write_all_vectored(
+            file,
+            &mut [
+                IoSlice::new(&header),
+                IoSlice::new(&meta_file),
+                IoSlice::new(&vec![0; padding_size_storage_start as usize]),
+                IoSlice::new(storage_file.get_ref()),
+            ],
+        )?;
 
         Ok(())
     }
 }
+
+/// Write all the ``buffers`` to ``file``, with as few calls to ``write_vectored`` as possible
+fn write_all_vectored<T: Write>(file: &mut T, mut buffers: &mut [IoSlice]) -> io::Result<()> {
+    IoSlice::advance_slices(&mut buffers, 0);
+    while !buffers.is_empty() {
+        match file.write_vectored(buffers) {
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write the whole archive",
+                ))
+            }
+            Ok(written) => IoSlice::advance_slices(&mut buffers, written),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+            Err(err) => return Err(err),
+        };
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FarcFile;
+
+    /// Write the archive, and parse it back
+    fn round_trip(writer: &FarcWriter, named: bool) -> Farc<Cursor<Vec<u8>>> {
+        let mut archive = Cursor::new(Vec::new());
+        if named {
+            writer.write_named(&mut archive).unwrap();
+        } else {
+            writer.write_hashed(&mut archive).unwrap();
+        };
+        Farc::new(Cursor::new(archive.into_inner())).unwrap()
+    }
+
+    /// Return the hash of the files, in the order their content is stored
+    fn storage_order<F: Read + Seek>(farc: &Farc<F>) -> Vec<u32> {
+        let mut files = farc.iter_files().collect::<Vec<&FarcFile>>();
+        files.sort_by_key(|file| file.start);
+        files.iter().map(|file| file.name_hash).collect()
+    }
+
+    #[test]
+    fn named() {
+        let mut writer = FarcWriter::default();
+        writer.add_named_file("script_001.bin".to_string(), vec![1; 5]);
+        writer.add_named_file("model.bgrs".to_string(), vec![2; 40]);
+        let farc = round_trip(&writer, true);
+        assert!(farc.is_named_in_archive());
+        assert_eq!(farc.file_known_name(), 2);
+        assert!(farc
+            .read_named_file("script_001.bin")
+            .unwrap()
+            .starts_with(&[1; 5]));
+        assert!(farc
+            .read_named_file("model.bgrs")
+            .unwrap()
+            .starts_with(&[2; 40]));
+
+        writer.add_hashed_file(0x1234, Vec::new());
+        assert!(matches!(
+            writer.write_named(&mut Cursor::new(Vec::new())),
+            Err(FarcWriterError::MissingName(0x1234))
+        ));
+        // the name are only needed for a named archive
+        assert!(!round_trip(&writer, false).is_named_in_archive());
+    }
+
+    #[test]
+    fn order() {
+        let mut writer = FarcWriter::default();
+        for hash in [5, 1, 4, 2, 3] {
+            writer.add_hashed_file(hash, vec![hash as u8; 20]);
+        }
+        // the unknown and repeated hashes are ignored, the missing ones are stored after, sorted by hash
+        writer.set_order(vec![4, 9, 2, 4]);
+        let farc = round_trip(&writer, false);
+        assert_eq!(storage_order(&farc), vec![4, 2, 1, 3, 5]);
+        for hash in 1..=5 {
+            assert!(farc
+                .read_hashed_file(hash)
+                .unwrap()
+                .starts_with(&[hash as u8; 20]));
+        }
+    }
+
+    #[test]
+    fn alignment() {
+        let mut writer = FarcWriter::default();
+        for invalid in [0, 8, 24] {
+            assert!(matches!(
+                writer.set_alignment(invalid),
+                Err(FarcWriterError::InvalidAlignment(alignment)) if alignment == invalid
+            ));
+        }
+        assert_eq!(writer.alignment(), 16);
+
+        for (hash, length) in [(1, 0), (2, 1), (3, 31), (4, 32), (5, 100)] {
+            writer.add_hashed_file(hash, vec![hash as u8; length]);
+        }
+        for alignment in [32, 64] {
+            writer.set_alignment(alignment).unwrap();
+            let farc = round_trip(&writer, false);
+            let data_start = farc.iter_files().map(|file| file.start).min().unwrap();
+            for file in farc.iter_files() {
+                assert!((file.start - data_start).is_multiple_of(alignment));
+                assert!(file.length.is_multiple_of(alignment));
+            }
+            for (hash, length) in [(1, 0), (2, 1), (3, 31), (4, 32), (5, 100)] {
+                let content = farc.read_hashed_file(hash).unwrap();
+                assert!(content.starts_with(&vec![hash as u8; length]));
+                assert!(content[length..].iter().all(|byte| *byte == 0));
+            }
+        }
+    }
+
+    /// A writer accepting at most ``limit`` bytes per call, and interrupted every other call
+    struct SlowWriter {
+        written: Vec<u8>,
+        limit: usize,
+        interrupt: bool,
+    }
+
+    impl Write for SlowWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.write_vectored(&[IoSlice::new(buf)])
+        }
+
+        fn write_vectored(&mut self, bufs: &[IoSlice]) -> io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Err(io::ErrorKind::Interrupted.into());
+            };
+            let mut written = 0;
+            for buf in bufs {
+                let length = buf.len().min(self.limit - written);
+                self.written.extend_from_slice(&buf[..length]);
+                written += length;
+            }
+            Ok(written)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn vectored() {
+        let buffers: [&[u8]; 4] = [b"FARC", b"", b"table", b"content"];
+        let mut slices = buffers.map(IoSlice::new);
+        let mut file = SlowWriter {
+            written: Vec::new(),
+            limit: 3,
+            interrupt: false,
+        };
+        write_all_vectored(&mut file, &mut slices).unwrap();
+        assert_eq!(file.written, b"FARCtablecontent");
+
+        let mut slices = buffers.map(IoSlice::new);
+        let mut full = [0; 6];
+        let err = write_all_vectored(&mut full.as_mut_slice(), &mut slices).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+        assert_eq!(&full, b"FARCta");
+    }
+}
@@ -0,0 +1,205 @@
+use crate::raw::{FarcParser, ParseStep, RawError, RawName};
+use crate::{Sir0TableError, Sir0TableReader, Sir0TableWriter};
+use std::convert::TryInto;
+use std::io::{Read, Seek};
+use thiserror::Error;
+
+/// An error that can happen while reading or writing a [`Fat5Table`]
+#[derive(Debug, Error)]
+pub enum Fat5TableError {
+    /// An error happened while reading or writing the sir0 section
+    #[error("error with the sir0 section containing the fat5 table")]
+    Sir0TableError(#[from] Sir0TableError),
+    /// The content header should contain the table offset, the number of entries and the table type
+    #[error("the content header should be (at least) 12 bytes long, but it only have {0} bytes")]
+    HeaderTooShort(usize),
+    /// The table type is neither 0 (named) nor 1 (hashed)
+    #[error("the fat5 type is not supported: found {0}")]
+    UnsupportedType(u32),
+    /// The entries or the names can't be decoded
+    #[error("the table can't be decoded")]
+    RawError(#[from] RawError),
+    /// An entry of a named table only have the hash of its name
+    #[error("the entry with the hash {0:08x} doesn't have a name, but the table is written with the names")]
+    MissingName(u32),
+}
+
+/// An entry of a [`Fat5Table`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fat5Entry {
+    /// The name of the file (pointed by the entry), or its hash
+    pub name: RawName,
+    /// The offset of the file, relative to the data section of the container
+    pub offset: u32,
+    /// The length of the file
+    pub length: u32,
+}
+
+/// The fat5 file table, stored in a sir0 section. It is used by FARC files, but isn't tied to their header, so it can
+/// also be used to implement other containers using the same table. Each entry is 12 bytes long: a pointer to the
+/// utf-16 name of the file (or the crc32 hash of the name, see [`hash_name`](crate::hash_name)), the offset and the
+/// length of the file.
+///
+/// # Example
+/// ```
+/// use pmd_farc::raw::RawName;
+/// use pmd_farc::{Fat5Entry, Fat5Table, Sir0TableReader};
+/// use std::io::Cursor;
+/// let table = Fat5Table {
+///     named: true,
+///     entries: vec![Fat5Entry {
+///         name: RawName::Name("a.bin".to_string()),
+///         offset: 0,
+///         length: 16,
+///     }],
+/// };
+/// let section = table.to_sir0().unwrap();
+///
+/// let mut reader = Sir0TableReader::new(Cursor::new(section)).unwrap();
+/// assert_eq!(Fat5Table::read(&mut reader).unwrap(), table);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fat5Table {
+    /// True if the entries point to the names of the files, false if they contain their hash
+    pub named: bool,
+    /// The entries, in the order they are stored
+    pub entries: Vec<Fat5Entry>,
+}
+
+impl Fat5Table {
+    /// Read the table described by the content header of ``sir0``: the offset of the table, the number of entries
+    /// and the table type, as in FARC files
+    pub fn read<F: Read + Seek>(sir0: &mut Sir0TableReader<F>) -> Result<Self, Fat5TableError> {
+        match (
+            sir0.content_header_u32(0),
+            sir0.content_header_u32(1),
+            sir0.content_header_u32(2),
+        ) {
+            (Some(table), Some(count), Some(fat5_type)) => {
+                let named = match fat5_type {
+                    0 => true,
+                    1 => false,
+                    x => return Err(Fat5TableError::UnsupportedType(x)),
+                };
+                Self::read_at(sir0, table, count, named)
+            }
+            _ => Err(Fat5TableError::HeaderTooShort(sir0.content_header().len())),
+        }
+    }
+
+    /// Read the ``count`` entries starting at ``table`` in ``sir0``. They are decoded by the same
+    /// [`FarcParser`] as the file table of FARC files, but the offsets don't need to be aligned.
+    pub fn read_at<F: Read + Seek>(
+        sir0: &mut Sir0TableReader<F>,
+        table: u32,
+        count: u32,
+        named: bool,
+    ) -> Result<Self, Fat5TableError> {
+        let mut parser = FarcParser::from_table(u64::from(table), count, named, sir0.len()?);
+        let mut buffer = Vec::new();
+        let mut entries = Vec::new();
+        loop {
+            match parser.next_step() {
+                ParseStep::Read { offset, length } => {
+                    buffer.resize(length, 0);
+                    sir0.read_exact_at(offset, &mut buffer)?;
+                    parser.feed(&buffer)?;
+                }
+                ParseStep::Entry(entry) => entries.push(Fat5Entry {
+                    name: entry.name,
+                    offset: entry.start,
+                    length: entry.length,
+                }),
+                ParseStep::Done => return Ok(Self { named, entries }),
+                _ => (),
+            };
+        }
+    }
+
+    /// Write the entries, followed by the names for a named table, to ``sir0``, and return the position of the
+    /// table. Entries with a name are written with its hash in a hashed table.
+    pub fn write(&self, sir0: &mut Sir0TableWriter) -> Result<u32, Fat5TableError> {
+        let table = sir0.position()?;
+        // the position of the name pointer of each entry, with the name to write
+        let mut name_pointers = Vec::new();
+        for entry in &self.entries {
+            match (&entry.name, self.named) {
+                (RawName::Name(name), true) => name_pointers.push((sir0.write_pointer(0)?, name)),
+                (RawName::Hash(hash), true) => return Err(Fat5TableError::MissingName(*hash)),
+                (name, false) => sir0.write_u32(name.hash())?,
+            };
+            sir0.write_u32(entry.offset)?;
+            sir0.write_u32(entry.length)?;
+        }
+
+        for (pointer_position, name) in name_pointers {
+            let name_position = sir0.position()?;
+            sir0.write_utf16(name)?;
+            sir0.set_pointer(pointer_position, name_position)?;
+        }
+        Ok(table)
+    }
+
+    /// Return a whole sir0 section containing the table, with the same content header as in FARC files
+    pub fn to_sir0(&self) -> Result<Vec<u8>, Fat5TableError> {
+        let mut sir0 = Sir0TableWriter::default();
+        let table = self.write(&mut sir0)?;
+        let count = self
+            .entries
+            .len()
+            .try_into()
+            .map_err(Sir0TableError::TooBig)?;
+        Ok(sir0.finish(table, &[count, if self.named { 0 } else { 1 }])?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn round_trip(table: &Fat5Table) -> Fat5Table {
+        let mut reader = Sir0TableReader::new(Cursor::new(table.to_sir0().unwrap())).unwrap();
+        Fat5Table::read(&mut reader).unwrap()
+    }
+
+    #[test]
+    fn unaligned_entries() {
+        // the offsets of the entries don't need to be aligned, contrary to the files of a FARC archive
+        for named in [false, true] {
+            let table = Fat5Table {
+                named,
+                entries: (0..600_u32)
+                    .map(|index| Fat5Entry {
+                        name: if named {
+                            RawName::Name(format!(
+                                "{:03}_{}",
+                                index,
+                                "é".repeat(index as usize % 37)
+                            ))
+                        } else {
+                            RawName::Hash(index * 7)
+                        },
+                        offset: index * 3,
+                        length: index,
+                    })
+                    .collect(),
+            };
+            assert_eq!(round_trip(&table), table);
+        }
+    }
+
+    #[test]
+    fn name_out_of_the_section() {
+        let mut sir0 = Sir0TableWriter::default();
+        let table = sir0.position().unwrap();
+        sir0.write_u32(0x1000).unwrap();
+        sir0.write_u32(0).unwrap();
+        sir0.write_u32(0).unwrap();
+        let section = sir0.finish(table, &[1, 0]).unwrap();
+        let mut reader = Sir0TableReader::new(Cursor::new(section)).unwrap();
+        assert!(Fat5Table::read(&mut reader).is_err());
+    }
+}
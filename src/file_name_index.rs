@@ -1,22 +1,9 @@
-use crate::FarcFile;
-use crc::crc32;
+use crate::memory::hash_map_bytes;
+use crate::{hash_name, FarcFile, NameConfidence};
 use std::collections::HashMap;
+use std::mem::size_of;
 use thiserror::Error;
 
-fn string_to_utf16(to_transform: &str) -> Vec<u8> {
-    to_transform
-        .encode_utf16()
-        .flat_map(|chara| chara.to_le_bytes().to_vec())
-        .collect()
-}
-
-/// Hash a name, first transforming it into utf16, then applying the ieee crc32 checksum
-#[must_use]
-pub fn hash_name(name: &str) -> u32 {
-    let name_encoded_utf16 = string_to_utf16(name);
-    crc32::checksum_ieee(&name_encoded_utf16)
-}
-
 #[derive(Error, Debug)]
 /// Any error that may happend due to name conflict
 pub enum FileNameError {
@@ -32,6 +19,9 @@ pub enum FileNameError {
     /// two file with the same name
     #[error("there is already a file named {0:?} in the farc file.")]
     NameAlreadyPresent(String),
+    /// no file with this hash
+    #[error("there is no file with the hash {0} in the farc file.")]
+    HashNotFound(u32),
 }
 
 #[derive(Debug, Default)]
@@ -114,35 +104,106 @@ impl FileNameIndex {
     /// If a file is found in the index that have a file name hash that correspond to the given name.
     /// If it does, return true, and save this name. otherwise, return false.
     ///
+    /// A name that isn't [`NameConfidence::Verified`] is replaced by the new name.
+    ///
     /// If there is a conflict found, do nothing and return false
     pub fn check_file_name(&mut self, name: &str) -> bool {
         let hash = hash_name(name);
-        if let Some(id) = self.file_id_by_crc32.get(&hash) {
-            let file = &mut self.file_data[*id];
-            if file.name.is_none() {
-                file.name = Some(name.to_string());
-                self.file_id_by_string.insert(name.to_string(), *id);
-                true
-            } else {
-                false
-            }
-        } else {
-            false
-        }
+        let id = match self.file_id_by_crc32.get(&hash) {
+            Some(id) => *id,
+            None => return false,
+        };
+        if self.file_data[id].name_confidence == Some(NameConfidence::Verified) {
+            return false;
+        };
+        if let Some(other_id) = self.file_id_by_string.get(name) {
+            if *other_id != id {
+                return false;
+            };
+        };
+        self.replace_name(id, name, NameConfidence::Verified);
+        true
+    }
+
+    /// Set the name of the file with the given hash, even if the hash of the name doesn't match. This replace the
+    /// previous name, if any.
+    pub fn set_file_name(
+        &mut self,
+        hash: u32,
+        name: &str,
+        confidence: NameConfidence,
+    ) -> Result<(), FileNameError> {
+        let id = match self.file_id_by_crc32.get(&hash) {
+            Some(id) => *id,
+            None => return Err(FileNameError::HashNotFound(hash)),
+        };
+        if let Some(other_id) = self.file_id_by_string.get(name) {
+            if *other_id != id {
+                return Err(FileNameError::NameAlreadyPresent(name.to_string()));
+            };
+        };
+        self.replace_name(id, name, confidence);
+        Ok(())
+    }
+
+    fn replace_name(&mut self, id: usize, name: &str, confidence: NameConfidence) {
+        let file = &mut self.file_data[id];
+        if let Some(old_name) = file.name.take() {
+            self.file_id_by_string.remove(&old_name);
+        };
+        file.name = Some(name.to_string());
+        file.name_confidence = Some(confidence);
+        self.file_id_by_string.insert(name.to_string(), id);
     }
 
     /// Return the file with the given name (the hash of the name is also tested, but not saved).
-    /// If there is a conflict with the hash value, None is returned.
+    /// If the file with this hash has another [`NameConfidence::Verified`] name, None is returned.
+    ///
+    /// # Example
+    /// ```
+    /// use pmd_farc::{hash_name, FileNameIndex, NameConfidence};
+    /// let mut index = FileNameIndex::default();
+    /// index.add_file_with_hash(hash_name("a.bin"), 0, 16).unwrap();
+    /// index
+    ///     .set_file_name(hash_name("a.bin"), "guess.bin", NameConfidence::Guessed)
+    ///     .unwrap();
+    /// // the guessed name doesn't hide the real one
+    /// assert!(index.get_file_by_name("a.bin").is_some());
+    /// ```
     #[must_use]
     pub fn get_file_by_name(&self, name: &str) -> Option<&FarcFile> {
+        self.get_file_by_name_with(name, || hash_name(name))
+    }
+
+    /// Like [`FileNameIndex::get_file_by_name`], with the already known hash of the name, to avoid computing it
+    /// again in loops
+    ///
+    /// # Example
+    /// ```
+    /// use pmd_farc::{hash_name, FileNameIndex};
+    /// let mut index = FileNameIndex::default();
+    /// index.add_file_with_hash(hash_name("a.bin"), 0, 16).unwrap();
+    /// let hash = hash_name("a.bin");
+    /// for _ in 0..3 {
+    ///     assert_eq!(index.get_file_by_name_hashed("a.bin", hash).unwrap().start, 0);
+    /// }
+    /// ```
+    #[must_use]
+    pub fn get_file_by_name_hashed(&self, name: &str, hash: u32) -> Option<&FarcFile> {
+        self.get_file_by_name_with(name, || hash)
+    }
+
+    /// Return the file with the given name, calling ``hash`` only if the name isn't directly known
+    fn get_file_by_name_with(&self, name: &str, hash: impl FnOnce() -> u32) -> Option<&FarcFile> {
         if let Some(direct) = self.file_id_by_string.get(name) {
             Some(&self.file_data[*direct])
         } else {
-            let hash = hash_name(name);
+            let hash = hash();
             #[allow(clippy::option_if_let_else)]
             if let Some(file_id) = self.file_id_by_crc32.get(&hash) {
                 let file = &self.file_data[*file_id];
-                if file.name.is_some() {
+                // a verified name is the real one, but a guessed or forced name may be wrong
+                if file.name_confidence == Some(NameConfidence::Verified) {
                     None
                 } else {
                     Some(file)
@@ -177,4 +238,25 @@ impl FileNameIndex {
     pub fn iter(&self) -> impl Iterator<Item = &FarcFile> {
         self.file_data.iter()
     }
+
+    /// Return the approximate number of bytes allocated by this index
+    #[must_use]
+    pub fn memory_usage(&self) -> usize {
+        let names = self
+            .file_data
+            .iter()
+            .filter_map(|file| file.name.as_ref())
+            .map(String::capacity)
+            .sum::<usize>();
+        let names_by_string = self
+            .file_id_by_string
+            .keys()
+            .map(String::capacity)
+            .sum::<usize>();
+        self.file_data.capacity() * size_of::<FarcFile>()
+            + names
+            + hash_map_bytes(&self.file_id_by_crc32)
+            + hash_map_bytes(&self.file_id_by_string)
+            + names_by_string
+    }
 }
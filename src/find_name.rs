@@ -0,0 +1,316 @@
+//! Find the name of the files contained in the ``pokemon_graphic.bin`` farc file, by looking at the content of its subfiles.
+//!
+//! The sub-files refer to each other by name: the ``.bgrs`` files list the animations of a model, and the ``.bch`` files
+//! contain a string table with the name of the animations they use. The corresponding ``.bchmata`` and ``.bchskla``
+//! files are named after those animations.
+//!
+//! This isn't support for these formats: the functions here are heuristic scanners, that only read the few fields
+//! needed to find strings, and return every string that looks like a name. They don't validate the files, and may
+//! return strings that aren't names. As every candidate is checked against the hash of the files, a wrong guess is
+//! simply ignored.
+use crate::{DehashError, DehashInput, Dehasher, Farc, FarcError};
+use std::io::{self, Read, Seek};
+use thiserror::Error;
+
+/// The extension of the files that contain the animation of a model
+const ANIMATION_EXTENSIONS: [&str; 2] = ["bchmata", "bchskla"];
+
+/// An error that can happen while trying to find the name of files
+#[derive(Debug, Error)]
+pub enum FindNameError {
+    /// An error happened while accessing a sub-file of the ``Farc``
+    #[error("an error happened while accessing a sub-file of the farc file")]
+    FarcError(#[from] FarcError),
+    /// An error happened while reading a sub-file
+    #[error("an error happened while reading a sub-file")]
+    IOError(#[from] io::Error),
+}
+
+/// Read a null-terminated ascii string, returning None if it is empty or contain non-printable character
+fn read_ascii_string(data: &[u8]) -> Option<String> {
+    let end = data.iter().position(|c| *c == 0).unwrap_or(data.len());
+    let string = &data[..end];
+    if string.is_empty() || !string.iter().all(|c| c.is_ascii_graphic()) {
+        return None;
+    };
+    Some(String::from_utf8_lossy(string).to_string())
+}
+
+/// Return every null-terminated printable string of at least 2 characters contained in the data
+fn scan_ascii_strings(data: &[u8]) -> Vec<String> {
+    data.split(|c| *c == 0)
+        .filter(|s| s.len() >= 2)
+        .filter_map(read_ascii_string)
+        .collect()
+}
+
+#[cfg(feature = "bgrs")]
+/// The names found in a ``.bgrs`` file by [`BgrsNames::parse`], useful to find file names
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BgrsNames {
+    /// The name of the model
+    pub model_name: String,
+    /// The name of the animations referenced by this model
+    pub animation_names: Vec<String>,
+}
+
+#[cfg(feature = "bgrs")]
+impl BgrsNames {
+    /// Scan a ``.bgrs`` file for the names it contains. Return ``None`` if the data doesn't start like a ``.bgrs``
+    /// file.
+    ///
+    /// This is a heuristic, not a parser of the format: only the model name, stored as a 0x40 bytes null-padded
+    /// string at offset 0x8, is read from a known position. The layout of the rest of the file isn't fully
+    /// documented, so every printable string found after the header is considered to be an animation name.
+    ///
+    /// # Example
+    /// ```
+    /// use pmd_farc::find_name::BgrsNames;
+    /// let mut bgrs = b"BGRS\0\0\0\0".to_vec();
+    /// bgrs.extend_from_slice(&[b"pikachu_00".as_ref(), &[0; 0x36]].concat());
+    /// bgrs.extend_from_slice(b"\0\0\0\0pikachu_00_wait\0\0");
+    /// let names = BgrsNames::parse(&bgrs).unwrap();
+    /// assert_eq!(names.model_name, "pikachu_00");
+    /// assert_eq!(names.animation_names, vec!["pikachu_00_wait".to_string()]);
+    /// assert!(BgrsNames::parse(b"BCH\0").is_none());
+    /// ```
+    #[must_use]
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < 0x48 || &data[0..4] != b"BGRS" {
+            return None;
+        };
+        let model_name = read_ascii_string(&data[0x8..0x48])?;
+        let mut animation_names = scan_ascii_strings(&data[0x48..]);
+        animation_names.retain(|name| name != &model_name);
+        animation_names.dedup();
+        Some(Self {
+            model_name,
+            animation_names,
+        })
+    }
+}
+
+#[cfg(feature = "bch")]
+/// Return the strings contained in the string table of a ``.bch`` file. Return ``None`` if the data doesn't look like a
+/// ``.bch`` file, or if the string table is outside of the file.
+///
+/// This is a heuristic, not a parser of the format: only the position of the string table is read from the header,
+/// and every printable string of the table is returned.
+///
+/// # Example
+/// ```
+/// use pmd_farc::find_name::bch_strings;
+/// let mut bch = b"BCH\0\x21\x21\0\0".to_vec();
+/// bch.extend_from_slice(&0x28u32.to_le_bytes()); // main header offset
+/// bch.extend_from_slice(&0x28u32.to_le_bytes()); // string table offset
+/// bch.extend_from_slice(&[0; 16]);
+/// bch.extend_from_slice(&0u32.to_le_bytes()); // main header length
+/// bch.extend_from_slice(&10u32.to_le_bytes()); // string table length
+/// bch.extend_from_slice(b"walk\0idle\0");
+/// assert_eq!(bch_strings(&bch), Some(vec!["walk".to_string(), "idle".to_string()]));
+/// ```
+#[must_use]
+pub fn bch_strings(data: &[u8]) -> Option<Vec<String>> {
+    fn read_u32(data: &[u8], offset: usize) -> Option<usize> {
+        let bytes = data.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    }
+
+    if data.len() < 0x8 || &data[0..4] != b"BCH\0" {
+        return None;
+    };
+    let backward_compatibility = data[4];
+    let string_table_offset = read_u32(data, 0xC)?;
+    // newer version have an additional "extended data offset" field
+    let string_table_lenght_offset = if backward_compatibility > 0x20 {
+        0x24
+    } else {
+        0x20
+    };
+    let string_table_lenght = read_u32(data, string_table_lenght_offset)?;
+    let string_table =
+        data.get(string_table_offset..string_table_offset.checked_add(string_table_lenght)?)?;
+    Some(scan_ascii_strings(string_table))
+}
+
+/// Check every name formed by a stem and one of the extensions against the ``Farc``. Return the number of newly found file names.
+fn check_names_with_extensions<F: Read + Seek>(
+    farc: &mut Farc<F>,
+    stems: &[String],
+    extensions: &[&str],
+) -> usize {
+    let mut found = 0;
+    for stem in stems {
+        for extension in extensions {
+            let name = format!("{}.{}", stem, extension);
+            if farc.check_file_name(&name) {
+                debug!("found the name {}", name);
+                found += 1;
+            };
+        }
+    }
+    found
+}
+
+/// Read the content of every file with the given hashes, and collect the values returned by ``extract`` for each of them
+fn scan_files<F: Read + Seek, T, I: IntoIterator<Item = T>, E: Fn(&[u8]) -> I>(
+    farc: &Farc<F>,
+    hashes: &[u32],
+    extract: E,
+) -> Result<Vec<T>, FindNameError> {
+    let mut result = Vec::new();
+    for hash in hashes {
+        result.extend(extract(&farc.read_hashed_file(*hash)?));
+    }
+    Ok(result)
+}
+
+#[cfg(feature = "bgrs")]
+/// Try to find the name of the ``.bgrs`` files, by reading the model name stored in every unnamed file that look like
+/// a ``.bgrs`` file, and checking ``{model_name}.bgrs``.
+///
+/// This doesn't need any external information (like the graphic database), only the archive itself.
+///
+/// Return the number of newly found file names.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{find_name::find_bgrs_model_names, hash_name, Farc, FarcWriter};
+/// use std::io::Cursor;
+/// let mut bgrs = b"BGRS\0\0\0\0".to_vec();
+/// bgrs.extend_from_slice(&[b"pikachu_00".as_ref(), &[0; 0x36]].concat());
+///
+/// let mut writer = FarcWriter::default();
+/// writer.add_hashed_file(hash_name("pikachu_00.bgrs"), bgrs);
+/// let mut archive = Cursor::new(Vec::new());
+/// writer.write_hashed(&mut archive).unwrap();
+///
+/// let mut farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+/// assert_eq!(find_bgrs_model_names(&mut farc).unwrap(), 1);
+/// assert_eq!(farc.iter_name().collect::<Vec<_>>(), vec!["pikachu_00.bgrs"]);
+/// ```
+pub fn find_bgrs_model_names<F: Read + Seek>(farc: &mut Farc<F>) -> Result<usize, FindNameError> {
+    let unnamed_hashes = farc.iter_hash_unknown_name().collect::<Vec<_>>();
+    let model_names = scan_files(farc, &unnamed_hashes, |content| {
+        BgrsNames::parse(content).map(|bgrs| bgrs.model_name)
+    })?;
+    Ok(check_names_with_extensions(farc, &model_names, &["bgrs"]))
+}
+
+#[cfg(feature = "bch")]
+/// Try to find the name of the animation files (``.bchmata`` and ``.bchskla``), by reading the string table of every
+/// unnamed file that look like a ``.bch`` file, and checking ``{string}.bchmata`` and ``{string}.bchskla``.
+///
+/// Return the number of newly found file names.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{find_name::find_bch_animation_names, hash_name, Farc, FarcWriter};
+/// use std::io::Cursor;
+/// let mut bch = b"BCH\0\x21\x21\0\0".to_vec();
+/// bch.extend_from_slice(&0x28u32.to_le_bytes());
+/// bch.extend_from_slice(&0x28u32.to_le_bytes());
+/// bch.extend_from_slice(&[0; 20]);
+/// bch.extend_from_slice(&5u32.to_le_bytes());
+/// bch.extend_from_slice(b"walk\0");
+///
+/// let mut writer = FarcWriter::default();
+/// writer.add_hashed_file(hash_name("model.bch"), bch);
+/// writer.add_hashed_file(hash_name("walk.bchskla"), Vec::new());
+/// let mut archive = Cursor::new(Vec::new());
+/// writer.write_hashed(&mut archive).unwrap();
+///
+/// let mut farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+/// assert_eq!(find_bch_animation_names(&mut farc).unwrap(), 1);
+/// assert_eq!(farc.iter_name().collect::<Vec<_>>(), vec!["walk.bchskla"]);
+/// ```
+pub fn find_bch_animation_names<F: Read + Seek>(
+    farc: &mut Farc<F>,
+) -> Result<usize, FindNameError> {
+    let unnamed_hashes = farc.iter_hash_unknown_name().collect::<Vec<_>>();
+    let animation_names = scan_files(farc, &unnamed_hashes, |content| {
+        bch_strings(content).unwrap_or_default()
+    })?;
+    Ok(check_names_with_extensions(
+        farc,
+        &animation_names,
+        &ANIMATION_EXTENSIONS,
+    ))
+}
+
+/// Try to find the name of the files of a ``pokemon_graphic.bin`` farc file.
+///
+/// With the ``bgrs`` feature, the name of ``.bgrs`` files are found with [`find_bgrs_model_names`].
+///
+/// Then, every sub-file is read, and the animation names found in the ``.bgrs`` files (with the ``bgrs`` feature) and in
+/// the string table of the ``.bch`` files (with the ``bch`` feature) are used to find the name of the animation files
+/// (``.bchmata`` and ``.bchskla``).
+///
+/// Return the number of newly found file names.
+pub fn find_name_monster_graphic<F: Read + Seek>(
+    farc: &mut Farc<F>,
+) -> Result<usize, FindNameError> {
+    let mut found = 0;
+    #[cfg(feature = "bgrs")]
+    {
+        found += find_bgrs_model_names(farc)?;
+    }
+
+    let hashes = farc.iter_all_hash().collect::<Vec<_>>();
+    let animation_names = scan_files(farc, &hashes, |content| {
+        let mut names = Vec::new();
+        #[cfg(feature = "bgrs")]
+        if let Some(bgrs) = BgrsNames::parse(content) {
+            names.extend(bgrs.animation_names);
+        };
+        #[cfg(feature = "bch")]
+        if let Some(strings) = bch_strings(content) {
+            names.extend(strings);
+        };
+        names
+    })?;
+
+    found += check_names_with_extensions(farc, &animation_names, &ANIMATION_EXTENSIONS);
+    Ok(found)
+}
+
+/// Return the candidate names that can be deduced from the content of a single file
+fn content_candidates(content: &[u8]) -> Vec<String> {
+    let mut animation_names = Vec::new();
+    let mut candidates = Vec::new();
+    #[cfg(feature = "bgrs")]
+    if let Some(bgrs) = BgrsNames::parse(content) {
+        candidates.push(format!("{}.bgrs", bgrs.model_name));
+        animation_names.extend(bgrs.animation_names);
+    };
+    #[cfg(feature = "bch")]
+    if let Some(strings) = bch_strings(content) {
+        animation_names.extend(strings);
+    };
+    for animation_name in animation_names {
+        for extension in &ANIMATION_EXTENSIONS {
+            candidates.push(format!("{}.{}", animation_name, extension));
+        }
+    }
+    candidates
+}
+
+/// A [`Dehasher`] that read every unnamed file, and return the names that can be deduced from their content (the
+/// same as [`find_bgrs_model_names`] and [`find_bch_animation_names`], depending on the enabled features).
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ContentDehasher;
+
+impl Dehasher for ContentDehasher {
+    fn name(&self) -> &str {
+        "content scan"
+    }
+
+    fn find_names(&self, input: &DehashInput) -> Result<Vec<String>, DehashError> {
+        let mut candidates = Vec::new();
+        for hash in input.unknown_hashes() {
+            candidates.extend(content_candidates(&input.read_file(*hash)?));
+        }
+        Ok(candidates)
+    }
+}
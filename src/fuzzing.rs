@@ -0,0 +1,109 @@
+//! [`Arbitrary`] implementations, to generate structured archives when fuzzing, rather than only random bytes that
+//! are almost never a valid archive.
+//!
+//! # Example
+//! ```
+//! use arbitrary::{Arbitrary, Unstructured};
+//! use pmd_farc::{Farc, FarcWriter};
+//! use std::io::Cursor;
+//!
+//! let bytes = [7; 256];
+//! let writer = FarcWriter::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+//! let mut archive = Cursor::new(Vec::new());
+//! writer.write_hashed(&mut archive).unwrap();
+//! let farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+//! assert_eq!(farc.file_count(), writer.file_count());
+//! ```
+
+use crate::FarcWriter;
+use arbitrary::{Arbitrary, Result, Unstructured};
+use std::ops::ControlFlow;
+
+/// The maximum number of files in a generated archive, to keep each fuzzing run fast
+const MAX_FILES: u32 = 64;
+
+/// Generate a name that can be written in an archive (UTF-16 names are null-terminated)
+fn arbitrary_name(u: &mut Unstructured) -> Result<String> {
+    Ok(String::arbitrary(u)?.replace('\0', ""))
+}
+
+/// Files with an arbitrary content, either with an arbitrary name or an arbitrary hash, and an arbitrary alignment
+/// (a multiple of 16, up to 256).
+///
+/// When some file were added by hash, the archive can only be written with [`FarcWriter::write_hashed`].
+impl<'a> Arbitrary<'a> for FarcWriter {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut writer = Self::default();
+        writer
+            .set_alignment(16 * u.int_in_range(1..=16)?)
+            .map_err(|_| arbitrary::Error::IncorrectFormat)?;
+        u.arbitrary_loop(None, Some(MAX_FILES), |u| {
+            let content = Vec::arbitrary(u)?;
+            if u.arbitrary()? {
+                writer.add_named_file(arbitrary_name(u)?, content);
+            } else {
+                writer.add_hashed_file(u.arbitrary()?, content);
+            };
+            Ok(ControlFlow::Continue(()))
+        })?;
+        Ok(writer)
+    }
+}
+
+#[cfg(feature = "test-util")]
+mod test_util {
+    use super::{arbitrary_name, MAX_FILES};
+    use crate::test_util::{Pathology, SyntheticFarc};
+    use arbitrary::{Arbitrary, Result, Unstructured};
+    use std::collections::HashSet;
+
+    impl<'a> Arbitrary<'a> for Pathology {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            Ok(*u.choose(&[
+                Self::Truncated,
+                Self::BadMagic,
+                Self::UnsupportedFat5Type,
+                Self::MisalignedFile,
+                Self::DataStartOverflow,
+                Self::DuplicateEntry,
+                Self::TruncatedTable,
+                Self::OversizedFileCount,
+            ])?)
+        }
+    }
+
+    /// Always generate a builder whose [`SyntheticFarc::build`] doesn't panic
+    impl<'a> Arbitrary<'a> for SyntheticFarc {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            let named = u.arbitrary()?;
+            let mut farc = Self::new()
+                .named(named)
+                .alignment(16 * u.int_in_range(1..=16)?)
+                .with_files(u.int_in_range(0..=MAX_FILES as usize / 2)?);
+            for _ in 0..u.int_in_range(0..=MAX_FILES / 2)? {
+                if named || u.arbitrary()? {
+                    farc = farc.with_file(&arbitrary_name(u)?, u.arbitrary()?);
+                } else {
+                    farc = farc.with_hashed_file(u.arbitrary()?, u.arbitrary()?);
+                };
+            }
+            // files with the same hash are only written once
+            let file_count = farc
+                .contents()
+                .map(|(hash, _)| hash)
+                .collect::<HashSet<_>>()
+                .len();
+            let pathology: Option<Pathology> = u.arbitrary()?;
+            Ok(match pathology {
+                Some(Pathology::DuplicateEntry) if file_count < 2 => farc,
+                Some(
+                    Pathology::MisalignedFile
+                    | Pathology::DataStartOverflow
+                    | Pathology::TruncatedTable,
+                ) if file_count < 1 => farc,
+                Some(pathology) => farc.pathology(pathology),
+                None => farc,
+            })
+        }
+    }
+}
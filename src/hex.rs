@@ -0,0 +1,25 @@
+//! Hexadecimal encoding of digests and signatures, for the text formats
+
+use std::convert::TryInto;
+use std::fmt::Write;
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    })
+}
+
+pub(crate) fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    };
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&hex[index..index + 2], 16).ok())
+        .collect()
+}
+
+pub(crate) fn sha256_from_hex(hex: &str) -> Option<[u8; 32]> {
+    from_hex(hex)?.try_into().ok()
+}
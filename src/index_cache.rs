@@ -0,0 +1,408 @@
+use crate::raw::{RawHeader, HEADER_LENGTH};
+use crate::{Farc, FarcError, FarcFile, FileNameError, FileNameIndex, NameConfidence};
+use crc::crc32;
+use std::convert::TryInto;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+use thiserror::Error;
+
+/// The first line of an index cache
+const INDEX_CACHE_MAGIC: &str = "FARC index cache 1";
+
+/// An error that can happen while creating, reading or using an [`IndexCache`]
+#[derive(Debug, Error)]
+pub enum IndexCacheError {
+    /// An error happened while reading the archive or the cache
+    #[error("input/output error")]
+    IOError(#[from] io::Error),
+    /// The archive can't be parsed
+    #[error("the archive can't be parsed")]
+    FarcError(#[from] FarcError),
+    /// The files of the cache can't be added to the index
+    #[error("the files of the cache are inconsistent")]
+    FileNameError(#[from] FileNameError),
+    /// The cache doesn't start with the index cache header
+    #[error("the file doesn't start with \"{INDEX_CACHE_MAGIC}\"")]
+    BadHeader,
+    /// A line of the cache can't be parsed
+    #[error("the line {line} of the index cache can't be parsed: {reason}")]
+    InvalidLine {
+        /// The number of the line (starting at 1)
+        line: usize,
+        /// Why it can't be parsed
+        reason: String,
+    },
+}
+
+/// What identify a version of an archive file: its length, its modification time and the crc32 of its header and
+/// file table. Computing it only read the start of the archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArchiveStamp {
+    /// The length of the archive file
+    pub length: u64,
+    /// The modification time, in nanoseconds since the unix epoch, if the file system provide it
+    pub modified: Option<u128>,
+    /// The crc32 of the header and of the file table
+    pub table_crc32: u32,
+}
+
+impl ArchiveStamp {
+    /// Compute the stamp of the archive at ``path``
+    pub fn of_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let metadata = file.metadata()?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_nanos());
+
+        let mut table = Vec::new();
+        (&mut file)
+            .take(HEADER_LENGTH as u64)
+            .read_to_end(&mut table)?;
+        // the file table is a sir0 section, whose position is in the header
+        let table_end = match table.as_slice().try_into() {
+            Ok(header) => {
+                let header = RawHeader::decode(header);
+                u64::from(header.sir0_offset) + u64::from(header.sir0_length)
+            }
+            Err(_) => 0,
+        };
+        let header_length = table.len() as u64;
+        file.take(table_end.saturating_sub(header_length))
+            .read_to_end(&mut table)?;
+
+        Ok(Self {
+            length: metadata.len(),
+            modified,
+            table_crc32: crc32::checksum_ieee(&table),
+        })
+    }
+}
+
+/// The parsed index of an archive, with the names found so far, that can be saved in a file next to the archive to
+/// reopen it without parsing it or finding the names again. It is only used while the [`ArchiveStamp`] of the
+/// archive doesn't change.
+///
+/// It is a text file, starting with ``FARC index cache 1``, followed by the ``stamp`` line (the length, the
+/// modification time in nanoseconds or ``-``, and the crc32), the ``named`` line (``0`` or ``1``), and a line for each
+/// file: its hash (as ``0x`` followed by 8 hexadecimal digits), start, length, name confidence (``-`` if the name is
+/// unknown) and name, separated by tabulations.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, Farc, FarcWriter};
+/// let directory = std::env::temp_dir().join(format!("pmd_farc_index_cache_doctest_{}", std::process::id()));
+/// std::fs::create_dir_all(&directory).unwrap();
+/// let archive = directory.join("message_us.bin");
+/// let cache = directory.join("message_us.bin.index");
+/// let mut writer = FarcWriter::default();
+/// writer.add_hashed_file(hash_name("script_001.bin"), b"hello".to_vec());
+/// writer.write_hashed(&mut std::fs::File::create(&archive).unwrap()).unwrap();
+///
+/// let mut farc = Farc::open_cached(&archive, &cache).unwrap();
+/// farc.check_file_name("script_001.bin");
+/// farc.save_index_cache(&archive, &cache).unwrap();
+///
+/// // the name is known without checking it again
+/// let farc = Farc::open_cached(&archive, &cache).unwrap();
+/// assert_eq!(farc.file_known_name(), 1);
+/// assert!(farc.get_named_file("script_001.bin").is_ok());
+/// # std::fs::remove_dir_all(&directory).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct IndexCache {
+    /// The stamp of the archive this index was created from
+    pub stamp: ArchiveStamp,
+    /// Whether the archive store the names of its files
+    pub named_in_archive: bool,
+    /// The files of the archive
+    pub files: Vec<FarcFile>,
+}
+
+impl IndexCache {
+    /// Create the index cache of a parsed archive, whose stamp is ``stamp``
+    pub fn new<F: Read + Seek>(farc: &Farc<F>, stamp: ArchiveStamp) -> Self {
+        Self {
+            stamp,
+            named_in_archive: farc.is_named_in_archive(),
+            files: farc.iter_files().cloned().collect(),
+        }
+    }
+
+    /// Write the index cache
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "{}", INDEX_CACHE_MAGIC)?;
+        let modified = self
+            .stamp
+            .modified
+            .map_or_else(|| "-".to_string(), |modified| modified.to_string());
+        writeln!(
+            writer,
+            "stamp\t{}\t{}\t{:08x}",
+            self.stamp.length, modified, self.stamp.table_crc32
+        )?;
+        writeln!(writer, "named\t{}", u8::from(self.named_in_archive))?;
+        for file in &self.files {
+            let confidence = match file.name_confidence {
+                Some(NameConfidence::Verified) => "verified",
+                Some(NameConfidence::Forced) => "forced",
+                Some(NameConfidence::Guessed) => "guessed",
+                None => "-",
+            };
+            writeln!(
+                writer,
+                "0x{:08x}\t{}\t{}\t{}\t{}",
+                file.name_hash,
+                file.start,
+                file.length,
+                confidence,
+                file.name.as_deref().unwrap_or("")
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Read an index cache written by [`IndexCache::write`]
+    pub fn read<R: BufRead>(reader: R) -> Result<Self, IndexCacheError> {
+        let mut lines = reader.lines();
+        match lines.next().transpose()? {
+            Some(line) if line.trim_end() == INDEX_CACHE_MAGIC => (),
+            _ => return Err(IndexCacheError::BadHeader),
+        };
+        let mut stamp = None;
+        let mut named_in_archive = None;
+        let mut files = Vec::new();
+        for (index, line) in lines.enumerate() {
+            let line = line?;
+            let invalid = |reason: &str| IndexCacheError::InvalidLine {
+                line: index + 2,
+                reason: reason.to_string(),
+            };
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() {
+                continue;
+            };
+            let number = |value: &str| -> Result<u32, IndexCacheError> {
+                value.parse().map_err(|_| invalid("a field isn't a number"))
+            };
+            let fields = line.splitn(5, '\t').collect::<Vec<_>>();
+            match fields.as_slice() {
+                ["stamp", length, modified, table_crc32] => {
+                    stamp =
+                        Some(ArchiveStamp {
+                            length: length
+                                .parse()
+                                .map_err(|_| invalid("the length isn't a number"))?,
+                            modified: match *modified {
+                                "-" => None,
+                                modified => Some(modified.parse().map_err(|_| {
+                                    invalid("the modification time isn't a number")
+                                })?),
+                            },
+                            table_crc32: u32::from_str_radix(table_crc32, 16)
+                                .map_err(|_| invalid("the crc32 isn't hexadecimal"))?,
+                        })
+                }
+                ["named", named] => named_in_archive = Some(*named == "1"),
+                [hash, start, length, confidence, name] => {
+                    let name_hash = hash
+                        .strip_prefix("0x")
+                        .and_then(|hash| u32::from_str_radix(hash, 16).ok())
+                        .ok_or_else(|| invalid("the hash isn't an hexadecimal number"))?;
+                    let name_confidence = match *confidence {
+                        "verified" => Some(NameConfidence::Verified),
+                        "forced" => Some(NameConfidence::Forced),
+                        "guessed" => Some(NameConfidence::Guessed),
+                        "-" => None,
+                        _ => return Err(invalid("unknown name confidence")),
+                    };
+                    files.push(FarcFile {
+                        start: number(start)?,
+                        length: number(length)?,
+                        name_hash,
+                        name: name_confidence.map(|_| (*name).to_string()),
+                        name_confidence,
+                    });
+                }
+                _ => return Err(invalid("unknown line")),
+            };
+        }
+        match (stamp, named_in_archive) {
+            (Some(stamp), Some(named_in_archive)) => Ok(Self {
+                stamp,
+                named_in_archive,
+                files,
+            }),
+            _ => Err(IndexCacheError::InvalidLine {
+                line: 1,
+                reason: "the stamp or named line is missing".to_string(),
+            }),
+        }
+    }
+}
+
+impl<F: Read + Seek> Farc<F> {
+    /// Create a ``Farc`` from an [`IndexCache`], without parsing the file table of ``file``. The cache should have
+    /// been created from this archive.
+    pub fn from_index_cache(file: F, cache: &IndexCache) -> Result<Self, IndexCacheError> {
+        let mut index = FileNameIndex::default();
+        for file in &cache.files {
+            index.add_file_with_hash(file.name_hash, file.start, file.length)?;
+        }
+        for file in &cache.files {
+            match (&file.name, file.name_confidence) {
+                (Some(name), Some(NameConfidence::Verified)) => {
+                    index.check_file_name(name);
+                }
+                (Some(name), Some(confidence)) => {
+                    index.set_file_name(file.name_hash, name, confidence)?
+                }
+                _ => (),
+            };
+        }
+        Ok(Self::from_index(
+            Arc::new(Mutex::new(file)),
+            index,
+            cache.named_in_archive,
+        ))
+    }
+
+    /// Save the index of this archive, read from ``archive_path``, and the names found so far to ``cache_path``, to
+    /// reopen it later with [`Farc::open_cached`]
+    pub fn save_index_cache<P: AsRef<Path>, C: AsRef<Path>>(
+        &self,
+        archive_path: P,
+        cache_path: C,
+    ) -> Result<(), IndexCacheError> {
+        let cache = IndexCache::new(self, ArchiveStamp::of_file(archive_path)?);
+        let mut file = io::BufWriter::new(File::create(cache_path)?);
+        cache.write(&mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+impl Farc<BufReader<File>> {
+    /// Open the archive at ``archive_path``, using the index saved at ``cache_path`` by [`Farc::save_index_cache`] if
+    /// it exists and the archive didn't change since, and parsing the archive otherwise. The cache isn't updated.
+    pub fn open_cached<P: AsRef<Path>, C: AsRef<Path>>(
+        archive_path: P,
+        cache_path: C,
+    ) -> Result<Self, IndexCacheError> {
+        let file = BufReader::new(File::open(&archive_path)?);
+        if let Ok(cache_file) = File::open(cache_path) {
+            match IndexCache::read(BufReader::new(cache_file)) {
+                Ok(cache) if cache.stamp == ArchiveStamp::of_file(&archive_path)? => {
+                    return Self::from_index_cache(file, &cache);
+                }
+                Ok(_) => debug!("the index cache of {:?} is outdated", archive_path.as_ref()),
+                Err(err) => warn!(
+                    "ignoring the index cache of {:?}: {}",
+                    archive_path.as_ref(),
+                    err
+                ),
+            };
+        };
+        Ok(Self::new(file)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_name;
+    use crate::test_util::SyntheticFarc;
+
+    /// The hash, start, length, name and name confidence of a file
+    type FileFields<'a> = (u32, u32, u32, Option<&'a str>, Option<NameConfidence>);
+
+    /// The fields of the files of an index, to compare them
+    fn files_of(files: &[FarcFile]) -> Vec<FileFields<'_>> {
+        files
+            .iter()
+            .map(|file| {
+                (
+                    file.name_hash,
+                    file.start,
+                    file.length,
+                    file.name(),
+                    file.name_confidence(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn write_then_read() {
+        let synthetic = SyntheticFarc::new()
+            .with_hashed_file(hash_name("verified.bin"), vec![1; 16])
+            .with_hashed_file(hash_name("forced.bin"), vec![2; 16])
+            .with_hashed_file(hash_name("guessed.bin"), vec![3; 16])
+            .with_hashed_file(hash_name("unknown.bin"), vec![4; 16]);
+        let mut farc = synthetic.build_farc();
+        farc.check_file_name("verified.bin");
+        farc.set_file_name(
+            hash_name("forced.bin"),
+            "a name\twith a tab",
+            NameConfidence::Forced,
+        )
+        .unwrap();
+        farc.set_file_name(hash_name("guessed.bin"), "", NameConfidence::Guessed)
+            .unwrap();
+        let stamp = ArchiveStamp {
+            length: 1234,
+            modified: None,
+            table_crc32: 0xdead_beef,
+        };
+        let cache = IndexCache::new(&farc, stamp);
+
+        let mut saved = Vec::new();
+        cache.write(&mut saved).unwrap();
+        let read = IndexCache::read(saved.as_slice()).unwrap();
+        assert_eq!(read.stamp, stamp);
+        assert_eq!(read.named_in_archive, cache.named_in_archive);
+        assert_eq!(files_of(&read.files), files_of(&cache.files));
+
+        // the archive reopened from the cache has the same files and names
+        let reopened = Farc::from_index_cache(io::Cursor::new(synthetic.build()), &read).unwrap();
+        let mut original = files_of(&cache.files);
+        let mut files = reopened.iter_files().cloned().collect::<Vec<_>>();
+        files.sort_by_key(|file| file.name_hash);
+        original.sort_by_key(|file| file.0);
+        assert_eq!(files_of(&files), original);
+        assert!(reopened
+            .read_named_file("verified.bin")
+            .unwrap()
+            .starts_with(&[1; 16]));
+        assert_eq!(reopened.file_known_name(), 1);
+    }
+
+    #[test]
+    fn invalid_cache() {
+        assert!(matches!(
+            IndexCache::read("FARC index cache 0\n".as_bytes()),
+            Err(IndexCacheError::BadHeader)
+        ));
+        for (cache, line) in [
+            ("stamp\t1\t-\tnot hex\nnamed\t0\n", 2),
+            (
+                "stamp\t1\t-\t0\nnamed\t0\n\n0x00000001\t0\t16\tsure\tname\n",
+                5,
+            ),
+            ("stamp\t1\t-\t0\nnamed\t0\n00000001\t0\t16\t-\t\n", 4),
+            ("stamp\t1\t-\t0\nnamed\t0\n0x00000001\t-1\t16\t-\t\n", 4),
+            ("stamp\t1\t-\t0\n", 1),
+        ] {
+            match IndexCache::read(format!("{}\n{}", INDEX_CACHE_MAGIC, cache).as_bytes()) {
+                Err(IndexCacheError::InvalidLine { line: number, .. }) => assert_eq!(number, line),
+                other => panic!("{:?} was read as {:?}", cache, other),
+            };
+        }
+    }
+}
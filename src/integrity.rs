@@ -0,0 +1,461 @@
+use crate::hex::{from_hex, sha256_from_hex, to_hex};
+use crate::{Farc, FarcError};
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
+use thiserror::Error;
+
+/// The first line of an integrity manifest
+const INTEGRITY_MAGIC: &str = "FARC integrity 1";
+
+/// An error that can happen while creating, reading or verifying an [`IntegrityManifest`]
+#[derive(Debug, Error)]
+pub enum IntegrityError {
+    /// An error happened while reading the archive or the manifest
+    #[error("input/output error")]
+    IOError(#[from] io::Error),
+    /// The archive can't be parsed
+    #[error("the archive can't be parsed")]
+    FarcError(#[from] FarcError),
+    /// The manifest doesn't start with the integrity manifest header
+    #[error("the file doesn't start with \"{INTEGRITY_MAGIC}\"")]
+    BadHeader,
+    /// A line of the manifest can't be parsed
+    #[error("the line {line} of the integrity manifest can't be parsed: {reason}")]
+    InvalidLine {
+        /// The number of the line (starting at 1)
+        line: usize,
+        /// Why it can't be parsed
+        reason: String,
+    },
+}
+
+/// Sign the content of an [`IntegrityManifest`], for example with an ed25519 private key
+pub trait IntegritySigner {
+    /// The name of the signature algorithm, like ``"ed25519"``
+    fn algorithm(&self) -> &str;
+
+    /// Return the signature of ``message``
+    fn sign(&self, message: &[u8]) -> Vec<u8>;
+}
+
+/// Check the signature of an [`IntegrityManifest`], for example with an ed25519 public key
+pub trait IntegrityVerifier {
+    /// Return true if ``signature``, created with ``algorithm``, is a valid signature of ``message``
+    fn verify(&self, algorithm: &str, message: &[u8], signature: &[u8]) -> bool;
+}
+
+/// The digest of a file of the archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IntegrityEntry {
+    /// The hash of the name of the file
+    pub hash: u32,
+    /// The length of the file in the archive
+    pub length: u32,
+    /// The sha256 of the content of the file
+    pub sha256: [u8; 32],
+}
+
+/// The signature of an [`IntegrityManifest`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IntegritySignature {
+    /// The name of the signature algorithm (see [`IntegritySigner::algorithm`])
+    pub algorithm: String,
+    /// The signature
+    pub value: Vec<u8>,
+}
+
+/// The result of [`IntegrityManifest::verify`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IntegrityReport {
+    /// Whether the digest of the whole archive match
+    pub archive_matches: bool,
+    /// The hash of the files whose content differ
+    pub mismatched: Vec<u32>,
+    /// The hash of the files of the manifest missing from the archive
+    pub missing: Vec<u32>,
+    /// The hash of the files of the archive not listed in the manifest
+    pub unexpected: Vec<u32>,
+    /// Whether the signature is valid. ``None`` if the manifest isn't signed or no verifier was given.
+    pub signature_valid: Option<bool>,
+}
+
+impl IntegrityReport {
+    /// Return true if the archive match the manifest, and the signature (if checked) is valid
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.archive_matches
+            && self.mismatched.is_empty()
+            && self.missing.is_empty()
+            && self.unexpected.is_empty()
+            && self.signature_valid != Some(false)
+    }
+}
+
+/// A detached manifest, listing the sha256 of a whole archive and of each of its files, to check a mod was
+/// distributed without modification. It can be signed with an [`IntegritySigner`].
+///
+/// Files are hashed while they are read, so they are never fully loaded in memory.
+///
+/// The manifest is a text file, with fields separated by tabs:
+/// ```text
+/// FARC integrity 1
+/// archive     <length>    <sha256>
+/// 0x<hash>    <length>    <sha256>
+/// signature   <algorithm> <signature in hexadecimal>
+/// ```
+/// The signature, if any, is the last line, and sign every line before it (with their line break).
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, FarcWriter, IntegrityManifest, IntegritySigner, IntegrityVerifier};
+/// use std::io::Cursor;
+///
+/// /// A toy signature, that must not be used in practice
+/// struct Xor;
+/// impl IntegritySigner for Xor {
+///     fn algorithm(&self) -> &str {
+///         "xor"
+///     }
+///     fn sign(&self, message: &[u8]) -> Vec<u8> {
+///         vec![message.iter().fold(0, |acc, byte| acc ^ byte)]
+///     }
+/// }
+/// impl IntegrityVerifier for Xor {
+///     fn verify(&self, algorithm: &str, message: &[u8], signature: &[u8]) -> bool {
+///         algorithm == "xor" && self.sign(message) == signature
+///     }
+/// }
+///
+/// let mut writer = FarcWriter::default();
+/// writer.add_hashed_file(hash_name("a.bin"), b"hello".to_vec());
+/// let mut archive = Cursor::new(Vec::new());
+/// writer.write_hashed(&mut archive).unwrap();
+///
+/// let mut manifest = IntegrityManifest::create(&mut archive).unwrap();
+/// manifest.sign(&Xor);
+/// let mut text = Vec::new();
+/// manifest.write(&mut text).unwrap();
+/// let manifest = IntegrityManifest::read(text.as_slice()).unwrap();
+/// assert!(manifest.verify(&mut archive, Some(&Xor)).unwrap().is_ok());
+///
+/// writer.add_hashed_file(hash_name("a.bin"), b"modified".to_vec());
+/// let mut modified = Cursor::new(Vec::new());
+/// writer.write_hashed(&mut modified).unwrap();
+/// let report = manifest.verify(&mut modified, Some(&Xor)).unwrap();
+/// assert_eq!(report.mismatched, vec![hash_name("a.bin")]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IntegrityManifest {
+    /// The length of the whole archive
+    pub archive_length: u64,
+    /// The sha256 of the whole archive
+    pub archive_sha256: [u8; 32],
+    /// The files, sorted by hash
+    pub entries: Vec<IntegrityEntry>,
+    /// The signature of the manifest, if signed
+    pub signature: Option<IntegritySignature>,
+}
+
+/// Return the length and the sha256 of everything ``reader`` contain
+fn digest<R: Read>(mut reader: R) -> io::Result<(u64, [u8; 32])> {
+    let mut hasher = Sha256::new();
+    let length = io::copy(&mut reader, &mut hasher)?;
+    Ok((length, hasher.finalize().into()))
+}
+
+/// Return the length and the sha256 of the whole archive, then parse it
+fn digest_archive<R: Read + Seek>(
+    mut archive: R,
+) -> Result<(u64, [u8; 32], Farc<R>), IntegrityError> {
+    archive.seek(SeekFrom::Start(0))?;
+    let (length, sha256) = digest(&mut archive)?;
+    archive.seek(SeekFrom::Start(0))?;
+    Ok((length, sha256, Farc::new(archive)?))
+}
+
+impl IntegrityManifest {
+    /// Create the (unsigned) manifest of an archive
+    pub fn create<R: Read + Seek>(archive: R) -> Result<Self, IntegrityError> {
+        let (archive_length, archive_sha256, farc) = digest_archive(archive)?;
+        let mut entries = Vec::new();
+        for file in farc.iter_files() {
+            let (_, sha256) = digest(farc.get_hashed_file(file.name_hash)?)?;
+            entries.push(IntegrityEntry {
+                hash: file.name_hash,
+                length: file.length,
+                sha256,
+            });
+        }
+        entries.sort_by_key(|entry| entry.hash);
+        Ok(Self {
+            archive_length,
+            archive_sha256,
+            entries,
+            signature: None,
+        })
+    }
+
+    /// Return the signed part of the manifest: every line except the signature
+    #[must_use]
+    pub fn signed_content(&self) -> Vec<u8> {
+        let mut content = format!(
+            "{}\narchive\t{}\t{}\n",
+            INTEGRITY_MAGIC,
+            self.archive_length,
+            to_hex(&self.archive_sha256)
+        );
+        for entry in &self.entries {
+            let _ = writeln!(
+                content,
+                "0x{:08x}\t{}\t{}",
+                entry.hash,
+                entry.length,
+                to_hex(&entry.sha256)
+            );
+        }
+        content.into_bytes()
+    }
+
+    /// Sign the manifest, replacing the previous signature
+    pub fn sign(&mut self, signer: &dyn IntegritySigner) {
+        self.signature = Some(IntegritySignature {
+            algorithm: signer.algorithm().to_string(),
+            value: signer.sign(&self.signed_content()),
+        });
+    }
+
+    /// Check an archive against this manifest, and the signature of the manifest with ``verifier`` (if given). Only
+    /// the files whose length match are read.
+    pub fn verify<R: Read + Seek>(
+        &self,
+        archive: R,
+        verifier: Option<&dyn IntegrityVerifier>,
+    ) -> Result<IntegrityReport, IntegrityError> {
+        let (archive_length, archive_sha256, farc) = digest_archive(archive)?;
+        let mut report = IntegrityReport {
+            archive_matches: archive_length == self.archive_length
+                && archive_sha256 == self.archive_sha256,
+            ..IntegrityReport::default()
+        };
+        for entry in &self.entries {
+            match farc.index.get_file_by_hash(entry.hash) {
+                None => report.missing.push(entry.hash),
+                Some(file) if file.length != entry.length => report.mismatched.push(entry.hash),
+                Some(_) => {
+                    let (_, sha256) = digest(farc.get_hashed_file(entry.hash)?)?;
+                    if sha256 != entry.sha256 {
+                        report.mismatched.push(entry.hash);
+                    };
+                }
+            };
+        }
+        report.unexpected = farc
+            .iter_all_hash()
+            .filter(|hash| {
+                self.entries
+                    .binary_search_by_key(hash, |entry| entry.hash)
+                    .is_err()
+            })
+            .collect();
+        report.unexpected.sort_unstable();
+        report.signature_valid = match (&self.signature, verifier) {
+            (Some(signature), Some(verifier)) => Some(verifier.verify(
+                &signature.algorithm,
+                &self.signed_content(),
+                &signature.value,
+            )),
+            _ => None,
+        };
+        Ok(report)
+    }
+
+    /// Write the manifest
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&self.signed_content())?;
+        if let Some(signature) = &self.signature {
+            writeln!(
+                writer,
+                "signature\t{}\t{}",
+                signature.algorithm,
+                to_hex(&signature.value)
+            )?;
+        };
+        Ok(())
+    }
+
+    /// Read a manifest
+    pub fn read<R: BufRead>(reader: R) -> Result<Self, IntegrityError> {
+        let mut lines = reader.lines();
+        match lines.next().transpose()? {
+            Some(line) if line.trim_end() == INTEGRITY_MAGIC => (),
+            _ => return Err(IntegrityError::BadHeader),
+        };
+        let mut archive = None;
+        let mut entries = Vec::new();
+        let mut signature = None;
+        for (index, line) in lines.enumerate() {
+            let line = line?;
+            let invalid = |reason: &str| IntegrityError::InvalidLine {
+                line: index + 2,
+                reason: reason.to_string(),
+            };
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() {
+                continue;
+            };
+            if signature.is_some() {
+                return Err(invalid("the signature should be the last line"));
+            };
+            let fields = line.split('\t').collect::<Vec<_>>();
+            match fields.as_slice() {
+                ["archive", length, sha256] => {
+                    archive = Some((
+                        length
+                            .parse()
+                            .map_err(|_| invalid("the length isn't a number"))?,
+                        sha256_from_hex(sha256).ok_or_else(|| invalid("invalid sha256"))?,
+                    ));
+                }
+                ["signature", algorithm, value] => {
+                    signature = Some(IntegritySignature {
+                        algorithm: (*algorithm).to_string(),
+                        value: from_hex(value)
+                            .ok_or_else(|| invalid("the signature isn't hexadecimal"))?,
+                    });
+                }
+                [hash, length, sha256] => entries.push(IntegrityEntry {
+                    hash: hash
+                        .strip_prefix("0x")
+                        .and_then(|hash| u32::from_str_radix(hash, 16).ok())
+                        .ok_or_else(|| invalid("the hash isn't an hexadecimal number"))?,
+                    length: length
+                        .parse()
+                        .map_err(|_| invalid("the length isn't a number"))?,
+                    sha256: sha256_from_hex(sha256).ok_or_else(|| invalid("invalid sha256"))?,
+                }),
+                _ => return Err(invalid("unexpected number of fields")),
+            };
+        }
+        let (archive_length, archive_sha256) = archive.ok_or(IntegrityError::InvalidLine {
+            line: 2,
+            reason: "the archive line is missing".to_string(),
+        })?;
+        entries.sort_by_key(|entry| entry.hash);
+        Ok(Self {
+            archive_length,
+            archive_sha256,
+            entries,
+            signature,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_name;
+    use crate::test_util::{Pathology, SyntheticFarc};
+    use std::io::Cursor;
+
+    const SHA256: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+    fn read(text: &str) -> Result<IntegrityManifest, IntegrityError> {
+        IntegrityManifest::read(text.as_bytes())
+    }
+
+    /// Return the line and the reason of an [`IntegrityError::InvalidLine`]
+    fn invalid_line(text: &str) -> (usize, String) {
+        match read(text) {
+            Err(IntegrityError::InvalidLine { line, reason }) => (line, reason),
+            result => panic!("expected an invalid line, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn read_invalid() {
+        assert!(matches!(read(""), Err(IntegrityError::BadHeader)));
+        assert!(matches!(
+            read("FARC integrity 2\n"),
+            Err(IntegrityError::BadHeader)
+        ));
+        let header = format!("{}\narchive\t16\t{}\n", INTEGRITY_MAGIC, SHA256);
+        assert!(read(&header).is_ok());
+
+        assert_eq!(invalid_line(&format!("{}\n", INTEGRITY_MAGIC)).0, 2);
+        for (line, reason) in [
+            (
+                format!("archive\tx\t{}", SHA256),
+                "the length isn't a number",
+            ),
+            ("archive\t16\t00".to_string(), "invalid sha256"),
+            (
+                format!("1234\t16\t{}", SHA256),
+                "the hash isn't an hexadecimal number",
+            ),
+            (
+                format!("0x1234\t-1\t{}", SHA256),
+                "the length isn't a number",
+            ),
+            ("0x1234\t16".to_string(), "unexpected number of fields"),
+            (
+                "signature\txor\txyz".to_string(),
+                "the signature isn't hexadecimal",
+            ),
+        ] {
+            assert_eq!(
+                invalid_line(&format!("{}{}\n", header, line)),
+                (3, reason.to_string())
+            );
+        }
+        assert_eq!(
+            invalid_line(&format!(
+                "{}signature\txor\t00\n0x1234\t16\t{}\n",
+                header, SHA256
+            )),
+            (4, "the signature should be the last line".to_string())
+        );
+    }
+
+    #[test]
+    fn verify_other_archive() {
+        let original = SyntheticFarc::new()
+            .with_file("a.bin", vec![1; 16])
+            .with_file("b.bin", vec![2; 16])
+            .build();
+        let manifest = IntegrityManifest::create(Cursor::new(&original)).unwrap();
+        assert!(manifest
+            .verify(Cursor::new(&original), None)
+            .unwrap()
+            .is_ok());
+
+        let other = SyntheticFarc::new()
+            .with_file("a.bin", vec![3; 16])
+            .with_file("c.bin", vec![2; 16])
+            .build();
+        let report = manifest.verify(Cursor::new(&other), None).unwrap();
+        assert!(!report.is_ok());
+        assert!(!report.archive_matches);
+        assert_eq!(report.mismatched, vec![hash_name("a.bin")]);
+        assert_eq!(report.missing, vec![hash_name("b.bin")]);
+        assert_eq!(report.unexpected, vec![hash_name("c.bin")]);
+        assert_eq!(report.signature_valid, None);
+
+        let broken = SyntheticFarc::new()
+            .with_files(2)
+            .pathology(Pathology::UnsupportedFat5Type)
+            .build();
+        assert!(matches!(
+            manifest.verify(Cursor::new(&broken), None),
+            Err(IntegrityError::FarcError(_))
+        ));
+        assert!(matches!(
+            IntegrityManifest::create(Cursor::new(&broken)),
+            Err(IntegrityError::FarcError(_))
+        ));
+    }
+}
@@ -1,26 +1,263 @@
 #![warn(missing_docs)]
+#![cfg_attr(not(feature = "std"), no_std)]
 //! This library permit to have a read-only access to Farc file format used in the 3ds game of pokemon mystery dungeon.
 //!
 //! The ``pmd_farc::Farc`` file format is a packed file format, like tar. It doesn't have a notion of subdirectory. There is two type of ``pmd_farc::Farc`` file:
 //! - A version with file index by their name.
 //! - A version with file index by the crc32 hash of their name.
+//!
 //! This library automatically identify the ``pmd_farc::Farc`` type. For type without full file name, you can test if a ``String`` correspond to a file name.
+//!
+//! Except for [`RomFs`], the library doesn't access the file system, and only need a ``Read + Seek`` source, like a ``Cursor`` over a
+//! byte slice. It thus also work on ``wasm32-unknown-unknown`` (without the ``rayon`` feature, which need threads),
+//! for example to build an archive viewer running in a browser:
+//!
+//! ```
+//! use pmd_farc::{hash_name, Farc, FarcWriter};
+//! use std::io::{Cursor, Read};
+//! # let mut writer = FarcWriter::default();
+//! # writer.add_hashed_file(hash_name("a.bin"), b"content".to_vec());
+//! # let mut archive = Cursor::new(Vec::new());
+//! # writer.write_hashed(&mut archive).unwrap();
+//! # let bytes = archive.into_inner();
+//! // bytes is a &[u8], for example received from javascript
+//! let farc = Farc::new(Cursor::new(&bytes[..])).unwrap();
+//! let mut content = String::new();
+//! farc.get_named_file("a.bin").unwrap().read_to_string(&mut content).unwrap();
+//! // the length of the files include their padding
+//! assert_eq!(content.trim_end_matches('\0'), "content");
+//! ```
+//!
+//! Everything except the [`raw`] module need the ``std`` feature, enabled by default. Without it, the crate is
+//! ``no_std`` (but need ``alloc``).
 
-#[macro_use]
+#[cfg_attr(feature = "std", macro_use)]
 extern crate log;
 
+extern crate alloc;
+
+pub mod raw;
+pub use raw::hash_name;
+
+#[cfg(feature = "std")]
+mod clock;
+
+#[cfg(feature = "std")]
 mod farc;
+#[cfg(feature = "std")]
 pub use farc::{Farc, FarcError};
 
+#[cfg(feature = "std")]
 mod dehasher;
+#[cfg(feature = "std")]
 pub use dehasher::message_dehash;
-pub use dehasher::FileHashType;
+#[cfg(feature = "std")]
+pub use dehasher::script_dehash;
+#[cfg(feature = "std")]
+pub use dehasher::{
+    DehashError, DehashInput, DehashReport, Dehasher, FileHashType, ListDehasher, NameCollision,
+    NameResolution,
+};
+
+#[cfg(feature = "std")]
+mod dehash_pipeline;
+#[cfg(feature = "std")]
+pub use dehash_pipeline::{DehashPipeline, PipelineReport};
+
+#[cfg(feature = "std")]
+mod dehash_registry;
+#[cfg(feature = "std")]
+pub use dehash_registry::{wildcard_match, DehashRegistry};
+
+#[cfg(feature = "std")]
+mod dehash_session;
+#[cfg(feature = "std")]
+pub use dehash_session::{DehashSession, DehashSessionError};
+
+#[cfg(feature = "std")]
+mod sir0_table;
+#[cfg(feature = "std")]
+pub use sir0_table::{Sir0TableError, Sir0TableReader, Sir0TableWriter};
+
+#[cfg(feature = "std")]
+mod fat5_table;
+#[cfg(feature = "std")]
+pub use fat5_table::{Fat5Entry, Fat5Table, Fat5TableError};
 
+#[cfg(feature = "std")]
+mod storage;
+#[cfg(feature = "std")]
+pub use storage::{BoxedFarc, ReadSeekSend, StorageCursor, StorageRange};
+
+#[cfg(feature = "std")]
 mod farc_writer;
+#[cfg(feature = "std")]
 pub use farc_writer::{FarcWriter, FarcWriterError};
 
+#[cfg(feature = "std")]
 mod file_name_index;
-pub use file_name_index::{hash_name, FileNameError, FileNameIndex};
+#[cfg(feature = "std")]
+pub use file_name_index::{FileNameError, FileNameIndex};
 
+#[cfg(feature = "std")]
 mod farc_file;
-pub use farc_file::FarcFile;
+#[cfg(feature = "std")]
+pub use farc_file::{FarcFile, NameConfidence};
+
+#[cfg(feature = "std")]
+mod brute_force;
+#[cfg(feature = "std")]
+pub use brute_force::{BruteForce, BruteForcePosition, BruteForceProgress};
+
+#[cfg(feature = "std")]
+mod name_cache;
+#[cfg(feature = "std")]
+pub use name_cache::NameCache;
+
+#[cfg(feature = "std")]
+mod memory;
+#[cfg(feature = "std")]
+pub use memory::MemoryUsage;
+
+#[cfg(feature = "std")]
+mod observer;
+#[cfg(feature = "std")]
+pub use observer::FarcObserver;
+
+#[cfg(feature = "std")]
+mod content_cache;
+#[cfg(feature = "std")]
+pub use content_cache::ContentCache;
+
+#[cfg(feature = "std")]
+mod binary_strings;
+#[cfg(feature = "std")]
+pub use binary_strings::{extract_file_names, BinaryStringDehasher};
+
+#[cfg(feature = "std")]
+mod unknown_hashes;
+#[cfg(feature = "std")]
+pub use unknown_hashes::{UnknownHashes, UnknownHashesError};
+
+#[cfg(feature = "std")]
+mod manifest;
+#[cfg(feature = "std")]
+pub use manifest::{Manifest, ManifestEntry, ManifestError};
+
+#[cfg(feature = "std")]
+mod compression;
+#[cfg(feature = "std")]
+pub use compression::{Compression, CompressionError, CompressionRegistry, DecompressedFile};
+
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(feature = "std")]
+pub use stream::{FarcStream, StreamError, STREAM_MAGIC};
+
+#[cfg(feature = "std")]
+mod transform;
+#[cfg(feature = "std")]
+pub use transform::{Transform, TransformError, TransformRegistry, TransformedFile};
+
+#[cfg(feature = "std")]
+mod merged_farc;
+#[cfg(feature = "std")]
+pub use merged_farc::MergedFarc;
+
+#[cfg(feature = "std")]
+mod romfs;
+#[cfg(feature = "std")]
+pub use romfs::{RomFs, RomFsError};
+
+#[cfg(feature = "std")]
+mod index_cache;
+#[cfg(feature = "std")]
+pub use index_cache::{ArchiveStamp, IndexCache, IndexCacheError};
+
+#[cfg(feature = "std")]
+mod batch;
+#[cfg(feature = "std")]
+pub use batch::{Batch, BatchError, BatchReport};
+
+#[cfg(feature = "std")]
+mod archive_read;
+#[cfg(feature = "std")]
+pub use archive_read::{ArchiveEntry, ArchiveRead};
+
+#[cfg(feature = "std")]
+mod search;
+#[cfg(feature = "std")]
+pub use search::{search_hash, search_name, HashLocation, HashSearch};
+
+#[cfg(feature = "std")]
+mod patch;
+#[cfg(feature = "std")]
+pub use patch::{DeltaInstruction, FarcPatch, PatchEntry, PatchError, PatchOperation};
+
+#[cfg(feature = "std")]
+mod name_list;
+#[cfg(feature = "std")]
+pub use name_list::{NameList, NameListEntry, NameListError, NameListReport};
+
+#[cfg(feature = "std")]
+mod coverage;
+#[cfg(feature = "std")]
+pub use coverage::{Coverage, STRATEGY_ARCHIVE, STRATEGY_OTHER};
+
+#[cfg(feature = "std")]
+mod suggestion;
+#[cfg(feature = "std")]
+pub use suggestion::{suggest_near_names, NameSuggestion, DEFAULT_SUGGESTION_CHARSET};
+
+#[cfg(feature = "std")]
+mod repair;
+#[cfg(feature = "std")]
+pub use repair::{repair_farc, RepairAction, RepairError, RepairReport, RepairedFarc};
+
+#[cfg(feature = "std")]
+mod validation;
+#[cfg(feature = "std")]
+pub use validation::{validate_farc, FarcProblem};
+
+#[cfg(feature = "std")]
+mod name_generator;
+#[cfg(feature = "std")]
+pub use name_generator::{NameGenerator, PSMD_GRAPHIC_SUFFIXES};
+
+#[cfg(any(all(test, feature = "std"), feature = "test-util"))]
+pub mod test_util;
+
+#[cfg(feature = "project")]
+mod project;
+#[cfg(feature = "project")]
+pub use project::{Project, ProjectError, ProjectFile, PROJECT_FILES_DIRECTORY, PROJECT_FILE_NAME};
+
+#[cfg(any(feature = "integrity", feature = "cas"))]
+mod hex;
+
+#[cfg(feature = "cas")]
+mod cas;
+#[cfg(feature = "cas")]
+pub use cas::{CasError, CasExporter, CasStats, ContentIndex, ContentIndexEntry};
+
+#[cfg(feature = "integrity")]
+mod integrity;
+#[cfg(feature = "integrity")]
+pub use integrity::{
+    IntegrityEntry, IntegrityError, IntegrityManifest, IntegrityReport, IntegritySignature,
+    IntegritySigner, IntegrityVerifier,
+};
+
+#[cfg(feature = "arbitrary")]
+mod fuzzing;
+
+#[cfg(feature = "proptest")]
+pub mod strategies;
+
+#[cfg(feature = "vfs")]
+mod farc_vfs;
+#[cfg(feature = "vfs")]
+pub use farc_vfs::FarcFs;
+
+#[cfg(any(feature = "bgrs", feature = "bch"))]
+pub mod find_name;
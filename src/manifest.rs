@@ -0,0 +1,413 @@
+use crate::{Farc, FarcFile};
+use std::io::{self, BufRead, Read, Seek, Write};
+use thiserror::Error;
+
+/// The first line of a manifest file
+const MANIFEST_MAGIC: &str = "FARC manifest 1";
+
+/// An error that can happen while reading a [`Manifest`]
+#[derive(Debug, Error)]
+pub enum ManifestError {
+    /// An error happened while reading the file
+    #[error("an error happened while reading the manifest")]
+    IOError(#[from] io::Error),
+    /// The file doesn't start with the manifest header, or use an unsupported version
+    #[error("the file doesn't start with \"{MANIFEST_MAGIC}\"")]
+    BadHeader,
+    /// A line can't be parsed
+    #[error("the line {line} of the manifest can't be parsed: {reason}")]
+    InvalidLine {
+        /// The number of the line (starting at 1)
+        line: usize,
+        /// Why it can't be parsed
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A file of an extracted archive, listed in a [`Manifest`]
+pub struct ManifestEntry {
+    /// The hash of the file in the archive
+    pub hash: u32,
+    /// The name of the file in the extracted directory
+    pub file_name: String,
+    /// The name of the file in the archive, if known. It may differ from ``file_name``, for example if it
+    /// contains characters that can't be used on the file system.
+    pub name: Option<String>,
+    /// The length of the file in the archive, including the padding
+    pub length: u32,
+    /// The position of the file in the original archive, recorded for a lossless repack
+    pub offset: Option<u32>,
+    /// The bytes between the end of the file and the next one (or the end of the archive) in the original archive,
+    /// recorded for a lossless repack
+    pub padding: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The description of an extracted archive, written alongside the extracted files so they can be packed again
+/// (by this crate or another tool) with the same hashes and names.
+///
+/// The manifest is a text file, with one line per field or entry, and fields separated by a tab (shown as
+/// spaces here):
+/// ```text
+/// FARC manifest 1
+/// named       false
+/// alignment   16
+/// 0x22089cae  16  0x22089cae.bin
+/// 0xc93f27ad  32  a.bin           a.bin
+/// ```
+/// - the first line is always ``FARC manifest 1``
+/// - ``named`` tell whether the archive stores the file names (``true``) or only their hashes (``false``)
+/// - ``alignment`` is the alignment of the start of each file, in bytes
+/// - every other line is a file, in the order they are stored in the archive, with its hash (in hexadecimal, with
+///   ``0x``), its length in the archive, its name in the extracted directory, and its name in the archive if known
+///
+/// To rebuild the archive byte for byte, the manifest can also contain a ``metadata`` line, with the name of a file
+/// containing everything before the first file of the original archive (the header and the file table). Each file
+/// then has two more fields, after its (possibly empty) name in the archive: its offset in the original archive,
+/// and the bytes following it, in hexadecimal (or ``-`` if there are none):
+/// ```text
+/// metadata    layout.metadata.bin
+/// 0x22089cae  16  0x22089cae.bin      256 00000000
+/// ```
+///
+/// Empty lines and lines starting with ``#`` are ignored. File names can't contain tabs or line breaks.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, Manifest, ManifestEntry};
+/// let mut manifest = Manifest::default();
+/// manifest.entries.push(ManifestEntry {
+///     hash: hash_name("a.bin"),
+///     file_name: "a.bin".to_string(),
+///     name: Some("a.bin".to_string()),
+///     length: 32,
+///     offset: None,
+///     padding: None,
+/// });
+/// let mut text = Vec::new();
+/// manifest.write(&mut text).unwrap();
+/// assert_eq!(Manifest::read(text.as_slice()).unwrap(), manifest);
+/// assert_eq!(manifest.hash_of("a.bin"), Some(hash_name("a.bin")));
+/// ```
+pub struct Manifest {
+    /// Whether the archive stores the file names
+    pub named: bool,
+    /// The alignment of the start of each file, in bytes
+    pub alignment: u32,
+    /// The file containing the header and the file table of the original archive, recorded for a lossless repack
+    pub metadata: Option<String>,
+    /// The files, in the order they are stored in the archive
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self {
+            named: false,
+            alignment: 16,
+            metadata: None,
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl Manifest {
+    /// Create the manifest of a [`Farc`]. ``file_name`` return the name of each file in the extracted directory.
+    pub fn from_farc<F: Read + Seek>(
+        farc: &Farc<F>,
+        mut file_name: impl FnMut(&FarcFile) -> String,
+    ) -> Self {
+        let mut files = farc.iter_files().collect::<Vec<_>>();
+        files.sort_by_key(|file| (file.start, file.name_hash));
+        Self {
+            named: farc.is_named_in_archive(),
+            alignment: 16,
+            metadata: None,
+            entries: files
+                .into_iter()
+                .map(|file| ManifestEntry {
+                    hash: file.name_hash,
+                    file_name: file_name(file),
+                    name: file.name.clone(),
+                    length: file.length,
+                    offset: None,
+                    padding: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Return the entry of the file with this name in the extracted directory
+    #[must_use]
+    pub fn get(&self, file_name: &str) -> Option<&ManifestEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.file_name == file_name)
+    }
+
+    /// Return the hash of the file with this name in the extracted directory
+    #[must_use]
+    pub fn hash_of(&self, file_name: &str) -> Option<u32> {
+        self.get(file_name).map(|entry| entry.hash)
+    }
+
+    /// Read a manifest
+    pub fn read<R: BufRead>(reader: R) -> Result<Self, ManifestError> {
+        let mut lines = reader.lines();
+        match lines.next().transpose()? {
+            Some(line) if line.trim_end() == MANIFEST_MAGIC => (),
+            _ => return Err(ManifestError::BadHeader),
+        };
+        let mut manifest = Self::default();
+        for (index, line) in lines.enumerate() {
+            let line = line?;
+            let invalid = |reason: &str| ManifestError::InvalidLine {
+                line: index + 2,
+                reason: reason.to_string(),
+            };
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            };
+            let fields = line.split('\t').collect::<Vec<_>>();
+            match fields.as_slice() {
+                ["named", value] => {
+                    manifest.named = value
+                        .parse()
+                        .map_err(|_| invalid("expected true or false"))?
+                }
+                ["alignment", value] => {
+                    manifest.alignment = value
+                        .parse()
+                        .map_err(|_| invalid("the alignment isn't a number"))?
+                }
+                ["metadata", file_name] => manifest.metadata = Some((*file_name).to_string()),
+                [hash, length, file_name, rest @ ..] if rest.len() <= 1 || rest.len() == 3 => {
+                    let hash = hash
+                        .strip_prefix("0x")
+                        .and_then(|hash| u32::from_str_radix(hash, 16).ok())
+                        .ok_or_else(|| invalid("the hash isn't an hexadecimal number"))?;
+                    let length = length
+                        .parse()
+                        .map_err(|_| invalid("the length isn't a number"))?;
+                    let (offset, padding) = match rest {
+                        [_, offset, padding] => (
+                            Some(
+                                offset
+                                    .parse()
+                                    .map_err(|_| invalid("the offset isn't a number"))?,
+                            ),
+                            Some(
+                                parse_padding(padding)
+                                    .ok_or_else(|| invalid("the padding isn't hexadecimal"))?,
+                            ),
+                        ),
+                        _ => (None, None),
+                    };
+                    manifest.entries.push(ManifestEntry {
+                        hash,
+                        file_name: (*file_name).to_string(),
+                        name: rest
+                            .first()
+                            .filter(|name| !name.is_empty())
+                            .map(|name| (*name).to_string()),
+                        length,
+                        offset,
+                        padding,
+                    });
+                }
+                _ => return Err(invalid("unexpected number of fields")),
+            };
+        }
+        Ok(manifest)
+    }
+
+    /// Write this manifest. Return an [`io::ErrorKind::InvalidInput`] error if a name contains a tab or a line
+    /// break, or if only one of the offset and the padding of an entry is known.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let invalid_name = |name: &String| name.contains(&['\t', '\n', '\r'][..]);
+        if self.metadata.iter().any(invalid_name) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the name of the metadata file contains a tab or a line break",
+            ));
+        };
+        for entry in &self.entries {
+            if std::iter::once(&entry.file_name)
+                .chain(&entry.name)
+                .any(invalid_name)
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "the name of the file {:08x} contains a tab or a line break",
+                        entry.hash
+                    ),
+                ));
+            };
+            if entry.offset.is_some() != entry.padding.is_some() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "the file {:08x} should have both an offset and a padding, or none of them",
+                        entry.hash
+                    ),
+                ));
+            };
+        }
+        writeln!(writer, "{}", MANIFEST_MAGIC)?;
+        writeln!(writer, "named\t{}", self.named)?;
+        writeln!(writer, "alignment\t{}", self.alignment)?;
+        if let Some(metadata) = &self.metadata {
+            writeln!(writer, "metadata\t{}", metadata)?;
+        };
+        for entry in &self.entries {
+            write!(
+                writer,
+                "0x{:08x}\t{}\t{}",
+                entry.hash, entry.length, entry.file_name
+            )?;
+            if let (Some(offset), Some(padding)) = (entry.offset, &entry.padding) {
+                write!(
+                    writer,
+                    "\t{}\t{}\t{}",
+                    entry.name.as_deref().unwrap_or(""),
+                    offset,
+                    write_padding(padding)
+                )?;
+            } else if let Some(name) = &entry.name {
+                write!(writer, "\t{}", name)?;
+            };
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Return the padding as hexadecimal, or ``-`` if it is empty
+fn write_padding(padding: &[u8]) -> String {
+    if padding.is_empty() {
+        return "-".to_string();
+    };
+    padding.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Parse a padding written by [`write_padding`]
+fn parse_padding(text: &str) -> Option<Vec<u8>> {
+    if text == "-" {
+        return Some(Vec::new());
+    };
+    if text.is_empty() || !text.len().is_multiple_of(2) {
+        return None;
+    };
+    (0..text.len())
+        .step_by(2)
+        .map(|position| u8::from_str_radix(text.get(position..position + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(offset: Option<u32>, padding: Option<Vec<u8>>) -> ManifestEntry {
+        ManifestEntry {
+            hash: 0x22089cae,
+            file_name: "0x22089cae.bin".to_string(),
+            name: None,
+            length: 16,
+            offset,
+            padding,
+        }
+    }
+
+    fn round_trip(manifest: &Manifest) -> Manifest {
+        let mut text = Vec::new();
+        manifest.write(&mut text).unwrap();
+        Manifest::read(text.as_slice()).unwrap()
+    }
+
+    fn invalid_line(text: &str) -> (usize, String) {
+        match Manifest::read(text.as_bytes()) {
+            Err(ManifestError::InvalidLine { line, reason }) => (line, reason),
+            result => panic!("expected an invalid line, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn lossless_fields() {
+        let manifest = Manifest {
+            metadata: Some("layout.metadata.bin".to_string()),
+            entries: vec![
+                entry(Some(256), Some(vec![0, 0xAB])),
+                ManifestEntry {
+                    name: Some("a.bin".to_string()),
+                    ..entry(Some(272), Some(Vec::new()))
+                },
+            ],
+            ..Manifest::default()
+        };
+        assert_eq!(round_trip(&manifest), manifest);
+    }
+
+    #[test]
+    fn write_invalid() {
+        let mut manifest = Manifest {
+            entries: vec![entry(Some(256), None)],
+            ..Manifest::default()
+        };
+        let error = manifest.write(&mut Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+        manifest.entries = vec![ManifestEntry {
+            file_name: "a\tb.bin".to_string(),
+            ..entry(None, None)
+        }];
+        let error = manifest.write(&mut Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+        manifest.entries.clear();
+        manifest.metadata = Some("layout\n.bin".to_string());
+        let error = manifest.write(&mut Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn read_invalid() {
+        assert!(matches!(
+            Manifest::read(&b"FARC manifest 2\n"[..]),
+            Err(ManifestError::BadHeader)
+        ));
+        for (line, reason) in [
+            ("named\tyes", "expected true or false"),
+            ("alignment\tx", "the alignment isn't a number"),
+            (
+                "22089cae\t16\ta.bin",
+                "the hash isn't an hexadecimal number",
+            ),
+            ("0x22089cae\tx\ta.bin", "the length isn't a number"),
+            ("0x22089cae\t16\ta.bin\t\tx\t-", "the offset isn't a number"),
+            (
+                "0x22089cae\t16\ta.bin\t\t256\t0",
+                "the padding isn't hexadecimal",
+            ),
+            (
+                "0x22089cae\t16\ta.bin\t\t256\tzz",
+                "the padding isn't hexadecimal",
+            ),
+            // the offset without the padding
+            (
+                "0x22089cae\t16\ta.bin\t\t256",
+                "unexpected number of fields",
+            ),
+        ] {
+            assert_eq!(
+                invalid_line(&format!("{}\n\n{}\n", MANIFEST_MAGIC, line)),
+                (3, reason.to_string())
+            );
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+use std::mem::size_of;
+
+/// Return the approximate number of bytes allocated by a ``HashMap`` for its entries (not what the entries own)
+pub(crate) fn hash_map_bytes<K, V>(map: &HashMap<K, V>) -> usize {
+    // one control byte per bucket
+    map.capacity() * (size_of::<(K, V)>() + 1)
+}
+
+/// The approximate memory held by a [`Farc`](crate::Farc) or a [`FarcWriter`](crate::FarcWriter), in bytes, as
+/// returned by [`Farc::memory_usage`](crate::Farc::memory_usage) and
+/// [`FarcWriter::memory_usage`](crate::FarcWriter::memory_usage). It doesn't count the reader of the archive.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, ContentCache, Farc, FarcWriter};
+/// use std::io::Cursor;
+/// let mut writer = FarcWriter::default();
+/// writer.add_hashed_file(hash_name("a.bin"), vec![0; 1000]);
+/// assert!(writer.memory_usage().staged >= 1000);
+///
+/// let mut archive = Cursor::new(Vec::new());
+/// writer.write_hashed(&mut archive).unwrap();
+/// let mut farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+/// farc.set_content_cache(ContentCache::new(1 << 20));
+/// let before = farc.memory_usage();
+/// farc.read_hashed_file(hash_name("a.bin")).unwrap();
+/// let after = farc.memory_usage();
+/// assert!(after.content_cache >= 1000);
+/// assert!(after.total() > before.total());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoryUsage {
+    /// The index of the files: their position, hash and name
+    pub index: usize,
+    /// How each name was found (see [`NameResolution`](crate::NameResolution))
+    pub resolutions: usize,
+    /// The content of the files kept in the [`ContentCache`](crate::ContentCache). The cache may be shared with other
+    /// archives, in which case it is counted for each of them.
+    pub content_cache: usize,
+    /// The content of the files added to a [`FarcWriter`](crate::FarcWriter), not yet written
+    pub staged: usize,
+}
+
+impl MemoryUsage {
+    /// Return the sum of every category
+    #[must_use]
+    pub const fn total(&self) -> usize {
+        self.index + self.resolutions + self.content_cache + self.staged
+    }
+}
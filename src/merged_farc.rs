@@ -0,0 +1,139 @@
+use crate::{hash_name, Farc, FarcError, FarcWriter, FarcWriterError};
+use io_partition::PartitionMutex;
+use std::collections::BTreeMap;
+use std::io::{Read, Seek};
+
+/// A view over an ordered list of [`Farc`], where the files of later archives override the files with the same hash
+/// of earlier ones. This is how mods can be stacked over the original archive.
+///
+/// The name of a file is taken from the archive providing it, or from an earlier archive if it isn't known there.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, Farc, FarcWriter, MergedFarc};
+/// use std::io::{Cursor, Read};
+/// let build = |files: &[(&str, &[u8])]| {
+///     let mut writer = FarcWriter::default();
+///     for (name, content) in files {
+///         writer.add_hashed_file(hash_name(name), content.to_vec());
+///     }
+///     let mut archive = Cursor::new(Vec::new());
+///     writer.write_hashed(&mut archive).unwrap();
+///     Farc::new(Cursor::new(archive.into_inner())).unwrap()
+/// };
+/// let original = build(&[("a.bin", b"original"), ("b.bin", b"original")]);
+/// let mod_layer = build(&[("b.bin", b"modded"), ("c.bin", b"new")]);
+///
+/// let merged = MergedFarc::new(vec![original, mod_layer]);
+/// assert_eq!(merged.file_count(), 3);
+/// assert_eq!(merged.layer_of(hash_name("a.bin")), Some(0));
+/// assert_eq!(merged.layer_of(hash_name("b.bin")), Some(1));
+/// let mut content = Vec::new();
+/// merged.get_named_file("b.bin").unwrap().read_to_end(&mut content).unwrap();
+/// assert!(content.starts_with(b"modded"));
+///
+/// // flatten the result in a single archive
+/// let mut archive = Cursor::new(Vec::new());
+/// merged.to_writer().unwrap().write_hashed(&mut archive).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct MergedFarc<F: Read + Seek> {
+    layers: Vec<Farc<F>>,
+    /// for each hash, the index of the layer providing it
+    providers: BTreeMap<u32, usize>,
+}
+
+impl<F: Read + Seek> MergedFarc<F> {
+    /// Create a [`MergedFarc`] from archives, from the lowest to the highest priority
+    #[must_use]
+    pub fn new(layers: Vec<Farc<F>>) -> Self {
+        let mut merged = Self {
+            layers: Vec::new(),
+            providers: BTreeMap::new(),
+        };
+        for layer in layers {
+            merged.push_layer(layer);
+        }
+        merged
+    }
+
+    /// Add an archive on top of the others. Its files override the ones with the same hash.
+    pub fn push_layer(&mut self, layer: Farc<F>) {
+        let index = self.layers.len();
+        for hash in layer.iter_all_hash() {
+            self.providers.insert(hash, index);
+        }
+        self.layers.push(layer);
+    }
+
+    /// Return the archives, from the lowest to the highest priority
+    #[must_use]
+    pub fn layers(&self) -> &[Farc<F>] {
+        &self.layers
+    }
+
+    /// Return the number of distinct files
+    #[must_use]
+    pub fn file_count(&self) -> usize {
+        self.providers.len()
+    }
+
+    /// Return the index of the layer providing the file with this hash
+    #[must_use]
+    pub fn layer_of(&self, hash: u32) -> Option<usize> {
+        self.providers.get(&hash).copied()
+    }
+
+    /// Return the name of the file with this hash, searching from the layer providing it down to the first layer
+    #[must_use]
+    pub fn name_of(&self, hash: u32) -> Option<&str> {
+        let provider = self.layer_of(hash)?;
+        self.layers[..=provider].iter().rev().find_map(|layer| {
+            layer
+                .index
+                .get_file_by_hash(hash)
+                .and_then(|file| file.name.as_deref())
+        })
+    }
+
+    /// Iterate over every file, sorted by hash, with its name (see [`MergedFarc::name_of`]) and the index of the layer
+    /// providing it
+    pub fn iter(&self) -> impl Iterator<Item = (u32, Option<&str>, usize)> {
+        self.providers
+            .iter()
+            .map(move |(hash, layer)| (*hash, self.name_of(*hash), *layer))
+    }
+
+    /// Return an handle to the file with this hash, from the layer providing it
+    pub fn get_hashed_file(&self, hash: u32) -> Result<PartitionMutex<F>, FarcError> {
+        match self.layer_of(hash) {
+            Some(layer) => self.layers[layer].get_hashed_file(hash),
+            None => Err(FarcError::HashedFileNotFound(hash)),
+        }
+    }
+
+    /// Return an handle to the file with this name, from the layer providing it
+    pub fn get_named_file(&self, name: &str) -> Result<PartitionMutex<F>, FarcError> {
+        let hash = hash_name(name);
+        match self.layer_of(hash) {
+            Some(_) => self.get_hashed_file(hash),
+            None => Err(FarcError::NamedFileNotFound(name.to_string())),
+        }
+    }
+
+    /// Create a [`FarcWriter`] containing every file of the merged view, with their known names
+    pub fn to_writer(&self) -> Result<FarcWriter, FarcWriterError> {
+        let mut writer = FarcWriter::default();
+        for (hash, name, _) in self.iter() {
+            let mut content = Vec::new();
+            self.get_hashed_file(hash)?.read_to_end(&mut content)?;
+            match name {
+                Some(name) if hash_name(name) == hash => {
+                    writer.add_named_file(name.to_string(), content)
+                }
+                _ => writer.add_hashed_file(hash, content),
+            };
+        }
+        Ok(writer)
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Default, Clone)]
+/// A cache of hash to name resolution, that can be shared between multiple [`Farc`](crate::Farc), like the
+/// different language variant of the same archive.
+///
+/// Cloning it return a handle to the same cache. See [`Farc::set_name_cache`](crate::Farc::set_name_cache).
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, Farc, FarcWriter, NameCache};
+/// use std::io::Cursor;
+/// let mut writer = FarcWriter::default();
+/// writer.add_hashed_file(hash_name("script_001.bin"), Vec::new());
+/// let mut archive = Cursor::new(Vec::new());
+/// writer.write_hashed(&mut archive).unwrap();
+/// let archive = archive.into_inner();
+///
+/// let cache = NameCache::default();
+/// let mut message_en = Farc::new(Cursor::new(archive.clone())).unwrap();
+/// let mut message_fr = Farc::new(Cursor::new(archive)).unwrap();
+/// message_en.set_name_cache(cache.clone());
+/// message_fr.set_name_cache(cache.clone());
+///
+/// assert!(message_en.check_file_name("script_001.bin"));
+/// message_fr.sync_name_cache();
+/// assert_eq!(message_fr.file_known_name(), 1);
+/// ```
+pub struct NameCache {
+    names: Arc<Mutex<HashMap<u32, String>>>,
+}
+
+impl NameCache {
+    /// Add a resolved name to the cache
+    pub fn insert(&self, hash: u32, name: String) {
+        self.lock().insert(hash, name);
+    }
+
+    /// Return the name corresponding to this hash, if it is in the cache
+    #[must_use]
+    pub fn get(&self, hash: u32) -> Option<String> {
+        self.lock().get(&hash).cloned()
+    }
+
+    /// Return the number of name in the cache
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Return true if the cache doesn't contain any name
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Return every name stored in this cache, with their hash
+    #[must_use]
+    pub fn to_vec(&self) -> Vec<(u32, String)> {
+        self.lock()
+            .iter()
+            .map(|(hash, name)| (*hash, name.clone()))
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<u32, String>> {
+        // the map is always left in a consistent state, so a poisoned lock can still be used
+        self.names
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,114 @@
+use crate::Farc;
+use std::io::{Read, Seek};
+
+/// Suffixes of the files commonly found in the graphic archives of Pokémon Super Mystery Dungeon
+pub const PSMD_GRAPHIC_SUFFIXES: [&str; 5] =
+    [".bgrs", ".bchmdl", ".bchmata", ".bchskla", ".bchtex"];
+
+#[derive(Debug, Default, Clone)]
+/// Generate plausible file names following the naming convention of Pokémon Super Mystery Dungeon, from known pieces
+/// of names.
+///
+/// The generated names have the form ``{pokemon}[_{form}][_{animation}]{suffix}``, where form is formatted on two
+/// digits. Every combination of the added pieces is generated, including the ones without form or animation.
+///
+/// # Example
+/// ```
+/// use pmd_farc::NameGenerator;
+/// let mut generator = NameGenerator::default();
+/// generator.add_pokemon_name("pikachu");
+/// generator.add_form_id(0);
+/// generator.add_animation_name("wait");
+/// generator.add_suffix(".bchskla");
+/// assert_eq!(
+///     generator.iter().collect::<Vec<_>>(),
+///     vec![
+///         "pikachu.bchskla",
+///         "pikachu_wait.bchskla",
+///         "pikachu_00.bchskla",
+///         "pikachu_00_wait.bchskla"
+///     ]
+/// );
+/// assert_eq!(generator.candidate_count(), 4);
+/// ```
+pub struct NameGenerator {
+    pokemon_names: Vec<String>,
+    form_ids: Vec<u8>,
+    animation_names: Vec<String>,
+    suffixes: Vec<String>,
+}
+
+impl NameGenerator {
+    /// Create a new [`NameGenerator`] that use the suffixes in [`PSMD_GRAPHIC_SUFFIXES`]
+    #[must_use]
+    pub fn new_psmd_graphic() -> Self {
+        let mut generator = Self::default();
+        for suffix in &PSMD_GRAPHIC_SUFFIXES {
+            generator.add_suffix(suffix);
+        }
+        generator
+    }
+
+    /// Add an internal Pokémon name (like ``pikachu``)
+    pub fn add_pokemon_name(&mut self, name: &str) {
+        self.pokemon_names.push(name.to_string());
+    }
+
+    /// Add a form id. Generated names will contain both the name without form, and with every added form.
+    pub fn add_form_id(&mut self, form_id: u8) {
+        self.form_ids.push(form_id);
+    }
+
+    /// Add an animation name (like ``wait``). Generated names will contain both the name without animation, and with every added animation.
+    pub fn add_animation_name(&mut self, name: &str) {
+        self.animation_names.push(name.to_string());
+    }
+
+    /// Add a suffix, usually an extension (like ``.bgrs``), added at the end of every generated name
+    pub fn add_suffix(&mut self, suffix: &str) {
+        self.suffixes.push(suffix.to_string());
+    }
+
+    /// Return the number of name that [`NameGenerator::iter`] will generate
+    #[must_use]
+    pub fn candidate_count(&self) -> usize {
+        self.pokemon_names.len()
+            * (self.form_ids.len() + 1)
+            * (self.animation_names.len() + 1)
+            * self.suffixes.len()
+    }
+
+    fn iter_pokemon_with_form(&self) -> impl Iterator<Item = String> + '_ {
+        self.pokemon_names.iter().flat_map(move |pokemon| {
+            std::iter::once(pokemon.clone()).chain(
+                self.form_ids
+                    .iter()
+                    .map(move |form| format!("{}_{:02}", pokemon, form)),
+            )
+        })
+    }
+
+    /// Iterate over all the generated names
+    pub fn iter(&self) -> impl Iterator<Item = String> + '_ {
+        self.iter_pokemon_with_form()
+            .flat_map(move |base| {
+                std::iter::once(base.clone()).chain(
+                    self.animation_names
+                        .iter()
+                        .map(move |animation| format!("{}_{}", base, animation)),
+                )
+            })
+            .flat_map(move |stem| {
+                self.suffixes
+                    .iter()
+                    .map(move |suffix| format!("{}{}", stem, suffix))
+            })
+    }
+
+    /// Check every generated name against the given [`Farc`]. Return the number of newly found file names.
+    pub fn find_names<F: Read + Seek>(&self, farc: &mut Farc<F>) -> usize {
+        self.iter()
+            .filter(|name| farc.check_file_name(name))
+            .count()
+    }
+}
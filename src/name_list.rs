@@ -0,0 +1,182 @@
+use crate::{hash_name, Farc};
+use std::io::{self, BufRead, Read, Seek};
+use thiserror::Error;
+
+/// An error that can happen while reading a [`NameList`]
+#[derive(Debug, Error)]
+pub enum NameListError {
+    /// An error happened while reading the file
+    #[error("an error happened while reading the name list")]
+    IOError(#[from] io::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// An entry of a [`NameList`]
+pub struct NameListEntry {
+    /// The hash given alongside the name, if any
+    pub hash: Option<u32>,
+    /// The name
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The result of applying a [`NameList`] to a [`Farc`]
+pub struct NameListReport {
+    /// The number of newly found names
+    pub applied: usize,
+    /// The number of names that doesn't match any file (or whose file already have a name)
+    pub not_found: usize,
+    /// The entries whose given hash doesn't match the hash of the name (usually a typo in the list)
+    pub mismatched: Vec<NameListEntry>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// A list of file names, used to share name research about PMD archives.
+///
+/// Each line is either:
+/// - empty, or a comment starting with ``#`` or ``//``
+/// - a name alone (like in the ``.lst`` files, the directory is ignored)
+/// - an hexadecimal hash starting with ``0x`` and a name, separated by a tab, as written by the ``dehash`` command
+/// - a name and an hexadecimal hash starting with ``0x``, separated by a tab
+///
+/// The tab is the only separator, so names can contain spaces and commas, and the ``0x`` prefix is required, so a
+/// name that only contain hexadecimal digits (like ``cafe``) isn't taken for a hash. Leading and trailing whitespace
+/// is ignored. Other formats (like CSV) should be converted first.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, Farc, FarcWriter, NameList};
+/// use std::io::Cursor;
+/// let mut writer = FarcWriter::default();
+/// writer.add_hashed_file(hash_name("script_001.bin"), Vec::new());
+/// writer.add_hashed_file(hash_name("script_002.bin"), Vec::new());
+/// let mut archive = Cursor::new(Vec::new());
+/// writer.write_hashed(&mut archive).unwrap();
+/// let mut farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+///
+/// let list = format!(
+///     "# names found by the community\n0x{:08X}\tscript_001.bin\nscript_002.bin\n0x12345678\ttypo.bin\n",
+///     hash_name("script_001.bin")
+/// );
+/// let name_list = NameList::parse(&mut list.as_bytes()).unwrap();
+/// let report = name_list.apply(&mut farc);
+/// assert_eq!(report.applied, 2);
+/// assert_eq!(report.mismatched.len(), 1);
+/// ```
+pub struct NameList {
+    /// The entries of this list, in order
+    pub entries: Vec<NameListEntry>,
+}
+
+/// Parse an hexadecimal hash, starting with ``0x``
+fn parse_hash(text: &str) -> Option<u32> {
+    let text = text.trim();
+    let hex = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))?;
+    if hex.is_empty() || hex.len() > 8 {
+        return None;
+    };
+    u32::from_str_radix(hex, 16).ok()
+}
+
+impl NameList {
+    /// Parse a name list
+    pub fn parse<R: BufRead>(file: &mut R) -> Result<Self, NameListError> {
+        let mut entries = Vec::new();
+        for line in file.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+                continue;
+            };
+            entries.push(Self::parse_line(line));
+        }
+        Ok(Self { entries })
+    }
+
+    fn parse_line(line: &str) -> NameListEntry {
+        let (hash, name) = match line.split_once('\t') {
+            Some((first, second)) => {
+                let (first, second) = (first.trim(), second.trim());
+                if let Some(hash) = parse_hash(first) {
+                    (Some(hash), second)
+                } else if let Some(hash) = parse_hash(second) {
+                    (Some(hash), first)
+                } else {
+                    (None, line)
+                }
+            }
+            None => (None, line),
+        };
+        let name = name.rsplit('/').next().unwrap_or(name);
+        NameListEntry {
+            hash,
+            name: name.to_string(),
+        }
+    }
+
+    /// Apply the names of this list to the [`Farc`]
+    pub fn apply<F: Read + Seek>(&self, farc: &mut Farc<F>) -> NameListReport {
+        let mut report = NameListReport::default();
+        for entry in &self.entries {
+            if let Some(hash) = entry.hash {
+                if hash != hash_name(&entry.name) {
+                    report.mismatched.push(entry.clone());
+                    continue;
+                };
+            };
+            if farc.check_file_name(&entry.name) {
+                report.applied += 1;
+            } else {
+                report.not_found += 1;
+            };
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(hash: Option<u32>, name: &str) -> NameListEntry {
+        NameListEntry {
+            hash,
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn parse_lines() {
+        let list = "# comment\n\
+            // comment\n\
+            \n\
+            a name, with spaces.bin\n\
+            cafe\n\
+            0x12AB\tbeef\n\
+            reversed.bin\t0x0000cafe\n\
+            cafe\tbeef\n\
+            0xcafe,beef.bin\n\
+            dir/sub/file.bin\n\
+            \t0xFFFFFFFF\ttrimmed.bin  \r\n";
+        let name_list = NameList::parse(&mut list.as_bytes()).unwrap();
+        assert_eq!(
+            name_list.entries,
+            vec![
+                entry(None, "a name, with spaces.bin"),
+                // hexadecimal digits without 0x are a name
+                entry(None, "cafe"),
+                entry(Some(0x12ab), "beef"),
+                entry(Some(0xcafe), "reversed.bin"),
+                entry(None, "cafe\tbeef"),
+                // only a tab separate the hash from the name
+                entry(None, "0xcafe,beef.bin"),
+                entry(None, "file.bin"),
+                entry(Some(0xffff_ffff), "trimmed.bin"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use crate::FarcFile;
+use std::fmt;
+use std::sync::Arc;
+
+/// Receive events while an archive is parsed, dehashed or written, for example to show live feedback in a GUI.
+/// Every method does nothing by default.
+///
+/// See [`Farc::new_observed`](crate::Farc::new_observed) and
+/// [`FarcWriter::set_observer`](crate::FarcWriter::set_observer).
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, Farc, FarcFile, FarcObserver, FarcWriter};
+/// use std::io::Cursor;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+///
+/// #[derive(Default)]
+/// struct Counter {
+///     parsed: AtomicUsize,
+///     resolved: AtomicUsize,
+///     written: AtomicUsize,
+/// }
+///
+/// impl FarcObserver for Counter {
+///     fn on_entry_parsed(&self, _file: &FarcFile) {
+///         self.parsed.fetch_add(1, Ordering::Relaxed);
+///     }
+///
+///     fn on_name_resolved(&self, _hash: u32, _name: &str, _strategy: Option<&str>) {
+///         self.resolved.fetch_add(1, Ordering::Relaxed);
+///     }
+///
+///     fn on_file_written(&self, _hash: u32, _name: Option<&str>, _length: u64) {
+///         self.written.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let counter = Arc::new(Counter::default());
+/// let mut writer = FarcWriter::default();
+/// writer.set_observer(counter.clone());
+/// writer.add_hashed_file(hash_name("a.bin"), vec![1]);
+/// writer.add_hashed_file(hash_name("b.bin"), vec![2]);
+/// let mut archive = Cursor::new(Vec::new());
+/// writer.write_hashed(&mut archive).unwrap();
+///
+/// let mut farc = Farc::new_observed(Cursor::new(archive.into_inner()), counter.clone()).unwrap();
+/// farc.check_file_name("a.bin");
+/// assert_eq!(counter.written.load(Ordering::Relaxed), 2);
+/// assert_eq!(counter.parsed.load(Ordering::Relaxed), 2);
+/// assert_eq!(counter.resolved.load(Ordering::Relaxed), 1);
+/// ```
+pub trait FarcObserver: Send + Sync {
+    /// Called when an entry of the file table has been parsed
+    fn on_entry_parsed(&self, _file: &FarcFile) {}
+
+    /// Called when the name of a file is found. ``strategy`` is the name of the [`Dehasher`](crate::Dehasher) that
+    /// found it, if any.
+    fn on_name_resolved(&self, _hash: u32, _name: &str, _strategy: Option<&str>) {}
+
+    /// Called when the content of a file has been written, with its length including the padding
+    fn on_file_written(&self, _hash: u32, _name: Option<&str>, _length: u64) {}
+
+    /// Called with the same message when a warning is logged
+    fn on_warning(&self, _message: &str) {}
+}
+
+/// The optional observer of a [`Farc`](crate::Farc) or [`FarcWriter`](crate::FarcWriter)
+#[derive(Clone, Default)]
+pub(crate) struct Observer(Option<Arc<dyn FarcObserver>>);
+
+impl Observer {
+    pub(crate) fn new(observer: Arc<dyn FarcObserver>) -> Self {
+        Self(Some(observer))
+    }
+
+    pub(crate) fn entry_parsed(&self, file: &FarcFile) {
+        if let Some(observer) = &self.0 {
+            observer.on_entry_parsed(file);
+        };
+    }
+
+    pub(crate) fn name_resolved(&self, hash: u32, name: &str, strategy: Option<&str>) {
+        if let Some(observer) = &self.0 {
+            observer.on_name_resolved(hash, name, strategy);
+        };
+    }
+
+    pub(crate) fn file_written(&self, hash: u32, name: Option<&str>, length: u64) {
+        if let Some(observer) = &self.0 {
+            observer.on_file_written(hash, name, length);
+        };
+    }
+
+    /// Log the warning, and give it to the observer
+    pub(crate) fn warning(&self, message: &str) {
+        warn!("{}", message);
+        if let Some(observer) = &self.0 {
+            observer.on_warning(message);
+        };
+    }
+}
+
+impl fmt::Debug for Observer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.0.is_some() {
+            "Observer(Some(..))"
+        } else {
+            "Observer(None)"
+        })
+    }
+}
@@ -0,0 +1,556 @@
+use crate::{hash_name, Farc, FarcError, FarcWriter};
+use byteorder::{ReadBytesExt, WriteBytesExt, LE};
+use crc::crc32::checksum_ieee;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryInto;
+use std::io::{self, Read, Seek, Write};
+use thiserror::Error;
+
+const PATCH_MAGIC: &[u8; 8] = b"FARCPTCH";
+const PATCH_VERSION: u32 = 1;
+/// The length of the blocks of the original file searched in the modified file
+const BLOCK_LENGTH: usize = 16;
+
+/// An error that can happen while creating, reading or applying a [`FarcPatch`]
+#[derive(Debug, Error)]
+pub enum PatchError {
+    /// An error happened while reading or writing the patch
+    #[error("input/output error")]
+    IOError(#[from] io::Error),
+    /// An error happened while reading an archive
+    #[error("an error happened while reading the archive")]
+    FarcError(#[from] FarcError),
+    /// The patch doesn't start with ``FARCPTCH``, or has an unsupported version
+    #[error("this isn't a FARC patch, or it was created by an incompatible version")]
+    BadHeader,
+    /// The patch contains invalid data
+    #[error("the patch is corrupted: {0}")]
+    Corrupted(&'static str),
+    /// A file removed or modified by the patch isn't in the archive
+    #[error("the file {0:08x} is not in the archive")]
+    MissingFile(u32),
+    /// A file added by the patch is already in the archive
+    #[error("the file {0:08x} is already in the archive")]
+    AlreadyPresent(u32),
+    /// A file modified by the patch doesn't have the content the patch was created from
+    #[error("the file {0:08x} differ from the one the patch was created from")]
+    OriginalMismatch(u32),
+    /// A file is too big to be stored in a patch
+    #[error("the file {0:08x} is too big")]
+    TooBig(u32),
+}
+
+/// An instruction to rebuild a modified file from the original one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeltaInstruction {
+    /// Copy bytes of the original file
+    Copy {
+        /// The position of the first copied byte in the original file
+        offset: u32,
+        /// The number of copied bytes
+        length: u32,
+    },
+    /// Insert new bytes
+    Insert(Vec<u8>),
+}
+
+/// What a [`FarcPatch`] does to a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchOperation {
+    /// Add a new file, with this content
+    Add(Vec<u8>),
+    /// Remove the file
+    Remove,
+    /// Modify the file
+    Modify {
+        /// The crc32 of the original content, checked before applying the patch
+        original_crc32: u32,
+        /// The instructions to build the new content from the original one
+        delta: Vec<DeltaInstruction>,
+    },
+}
+
+/// A change to a single file of an archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchEntry {
+    /// The hash of the file
+    pub hash: u32,
+    /// The name of the file in the modified archive, if known
+    pub name: Option<String>,
+    /// The change
+    pub operation: PatchOperation,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// The difference between two versions of an archive, that can be applied to the original version to get the
+/// modified one. Modified files are stored as binary differences, so a patch usually contains little of the original
+/// data, and can be distributed without the original archive.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, Farc, FarcPatch, FarcWriter};
+/// use std::io::{Cursor, Read};
+/// let build = |files: &[(&str, Vec<u8>)]| {
+///     let mut writer = FarcWriter::default();
+///     for (name, content) in files {
+///         writer.add_hashed_file(hash_name(name), content.clone());
+///     }
+///     let mut archive = Cursor::new(Vec::new());
+///     writer.write_hashed(&mut archive).unwrap();
+///     Farc::new(Cursor::new(archive.into_inner())).unwrap()
+/// };
+/// let text = b"a long enough text, that is only slightly modified".to_vec();
+/// let original = build(&[("a.bin", text.clone()), ("b.bin", vec![1; 10])]);
+/// let mut modified_text = text.clone();
+/// modified_text[2] = b'L';
+/// let modified = build(&[("a.bin", modified_text.clone()), ("c.bin", vec![2; 10])]);
+///
+/// let patch = FarcPatch::create(&original, &modified).unwrap();
+/// let mut serialized = Vec::new();
+/// patch.write(&mut serialized).unwrap();
+/// let patch = FarcPatch::read(&mut serialized.as_slice()).unwrap();
+///
+/// let mut result = Cursor::new(Vec::new());
+/// patch.apply(&original).unwrap().write_hashed(&mut result).unwrap();
+/// let result = Farc::new(Cursor::new(result.into_inner())).unwrap();
+/// assert!(result.get_named_file("b.bin").is_err());
+/// let mut content = Vec::new();
+/// result.get_named_file("a.bin").unwrap().read_to_end(&mut content).unwrap();
+/// assert!(content.starts_with(&modified_text));
+/// ```
+pub struct FarcPatch {
+    /// The changed files, sorted by hash
+    pub entries: Vec<PatchEntry>,
+}
+
+fn read_content<F: Read + Seek>(farc: &Farc<F>, hash: u32) -> Result<Vec<u8>, PatchError> {
+    let mut content = Vec::new();
+    farc.get_hashed_file(hash)?.read_to_end(&mut content)?;
+    Ok(content)
+}
+
+fn to_u32(length: usize, hash: u32) -> Result<u32, PatchError> {
+    length.try_into().map_err(|_| PatchError::TooBig(hash))
+}
+
+/// Compute the instructions to build ``modified`` from ``original``. Blocks of the original file are searched in the
+/// modified file, and extended as far as possible.
+fn compute_delta(
+    original: &[u8],
+    modified: &[u8],
+    hash: u32,
+) -> Result<Vec<DeltaInstruction>, PatchError> {
+    let mut blocks: HashMap<&[u8], usize> = HashMap::new();
+    for (index, block) in original.chunks_exact(BLOCK_LENGTH).enumerate() {
+        blocks.entry(block).or_insert(index * BLOCK_LENGTH);
+    }
+    let mut delta = Vec::new();
+    let mut inserted = Vec::new();
+    let mut position = 0;
+    while position < modified.len() {
+        let found = modified
+            .get(position..position + BLOCK_LENGTH)
+            .and_then(|block| blocks.get(block));
+        let mut offset = match found {
+            Some(offset) => *offset,
+            None => {
+                inserted.push(modified[position]);
+                position += 1;
+                continue;
+            }
+        };
+        // extend the match backward, into the bytes that would otherwise be inserted
+        while offset > 0 && inserted.last() == Some(&original[offset - 1]) {
+            inserted.pop();
+            offset -= 1;
+            position -= 1;
+        }
+        let length = original[offset..]
+            .iter()
+            .zip(&modified[position..])
+            .take_while(|(a, b)| a == b)
+            .count();
+        if !inserted.is_empty() {
+            delta.push(DeltaInstruction::Insert(std::mem::take(&mut inserted)));
+        };
+        delta.push(DeltaInstruction::Copy {
+            offset: to_u32(offset, hash)?,
+            length: to_u32(length, hash)?,
+        });
+        position += length;
+    }
+    if !inserted.is_empty() {
+        delta.push(DeltaInstruction::Insert(inserted));
+    };
+    Ok(delta)
+}
+
+fn apply_delta(original: &[u8], delta: &[DeltaInstruction]) -> Option<Vec<u8>> {
+    let mut result = Vec::new();
+    for instruction in delta {
+        match instruction {
+            DeltaInstruction::Copy { offset, length } => {
+                let start = *offset as usize;
+                result
+                    .extend_from_slice(original.get(start..start.checked_add(*length as usize)?)?);
+            }
+            DeltaInstruction::Insert(bytes) => result.extend_from_slice(bytes),
+        };
+    }
+    Some(result)
+}
+
+fn write_bytes<W: Write>(writer: &mut W, bytes: &[u8], hash: u32) -> Result<(), PatchError> {
+    writer.write_u32::<LE>(to_u32(bytes.len(), hash)?)?;
+    writer.write_all(bytes)?;
+    Ok(())
+}
+
+fn read_bytes<R: Read>(reader: &mut R) -> Result<Vec<u8>, PatchError> {
+    let length = reader.read_u32::<LE>()?;
+    let mut bytes = Vec::new();
+    reader.take(u64::from(length)).read_to_end(&mut bytes)?;
+    if bytes.len() != length as usize {
+        return Err(PatchError::Corrupted(
+            "the patch end in the middle of some data",
+        ));
+    };
+    Ok(bytes)
+}
+
+impl FarcPatch {
+    /// Create the patch that transform the ``original`` archive into the ``modified`` one
+    pub fn create<F1: Read + Seek, F2: Read + Seek>(
+        original: &Farc<F1>,
+        modified: &Farc<F2>,
+    ) -> Result<Self, PatchError> {
+        let original_files = original.iter().collect::<BTreeMap<_, _>>();
+        let modified_files = modified.iter().collect::<BTreeMap<_, _>>();
+        let mut entries = Vec::new();
+        for (hash, name) in &original_files {
+            if !modified_files.contains_key(hash) {
+                entries.push(PatchEntry {
+                    hash: *hash,
+                    name: name.map(str::to_string),
+                    operation: PatchOperation::Remove,
+                });
+            };
+        }
+        for (hash, name) in modified_files {
+            let modified_content = read_content(modified, hash)?;
+            let operation = if original_files.contains_key(&hash) {
+                let original_content = read_content(original, hash)?;
+                if original_content == modified_content {
+                    continue;
+                };
+                PatchOperation::Modify {
+                    original_crc32: checksum_ieee(&original_content),
+                    delta: compute_delta(&original_content, &modified_content, hash)?,
+                }
+            } else {
+                PatchOperation::Add(modified_content)
+            };
+            entries.push(PatchEntry {
+                hash,
+                name: name.map(str::to_string),
+                operation,
+            });
+        }
+        entries.sort_by_key(|entry| entry.hash);
+        Ok(Self { entries })
+    }
+
+    /// Apply this patch to an archive, and return the modified archive, ready to be written. The names known in
+    /// the archive or in the patch are kept.
+    ///
+    /// The archive is checked to be the one the patch was created from: a [`PatchError`] is returned if a file
+    /// to remove or modify is missing or has a different content, or if a file to add is already present.
+    pub fn apply<F: Read + Seek>(&self, farc: &Farc<F>) -> Result<FarcWriter, PatchError> {
+        let mut files = BTreeMap::new();
+        for (hash, name) in farc.iter() {
+            files.insert(hash, (name.map(str::to_string), read_content(farc, hash)?));
+        }
+        for entry in &self.entries {
+            let hash = entry.hash;
+            match &entry.operation {
+                PatchOperation::Add(content) => {
+                    if files.contains_key(&hash) {
+                        return Err(PatchError::AlreadyPresent(hash));
+                    };
+                    files.insert(hash, (entry.name.clone(), content.clone()));
+                }
+                PatchOperation::Remove => {
+                    files.remove(&hash).ok_or(PatchError::MissingFile(hash))?;
+                }
+                PatchOperation::Modify {
+                    original_crc32,
+                    delta,
+                } => {
+                    let (name, content) =
+                        files.get_mut(&hash).ok_or(PatchError::MissingFile(hash))?;
+                    if checksum_ieee(content) != *original_crc32 {
+                        return Err(PatchError::OriginalMismatch(hash));
+                    };
+                    *content =
+                        apply_delta(content, delta).ok_or(PatchError::OriginalMismatch(hash))?;
+                    if name.is_none() {
+                        *name = entry.name.clone();
+                    };
+                }
+            };
+        }
+        let mut writer = FarcWriter::default();
+        for (hash, (name, content)) in files {
+            match name {
+                Some(name) if hash_name(&name) == hash => writer.add_named_file(name, content),
+                _ => writer.add_hashed_file(hash, content),
+            };
+        }
+        Ok(writer)
+    }
+
+    /// Write this patch
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), PatchError> {
+        writer.write_all(PATCH_MAGIC)?;
+        writer.write_u32::<LE>(PATCH_VERSION)?;
+        writer.write_u32::<LE>(to_u32(self.entries.len(), 0)?)?;
+        for entry in &self.entries {
+            writer.write_u32::<LE>(entry.hash)?;
+            match &entry.name {
+                Some(name) => {
+                    writer.write_u8(1)?;
+                    write_bytes(writer, name.as_bytes(), entry.hash)?;
+                }
+                None => writer.write_u8(0)?,
+            };
+            match &entry.operation {
+                PatchOperation::Add(content) => {
+                    writer.write_u8(0)?;
+                    write_bytes(writer, content, entry.hash)?;
+                }
+                PatchOperation::Remove => writer.write_u8(1)?,
+                PatchOperation::Modify {
+                    original_crc32,
+                    delta,
+                } => {
+                    writer.write_u8(2)?;
+                    writer.write_u32::<LE>(*original_crc32)?;
+                    writer.write_u32::<LE>(to_u32(delta.len(), entry.hash)?)?;
+                    for instruction in delta {
+                        match instruction {
+                            DeltaInstruction::Copy { offset, length } => {
+                                writer.write_u8(0)?;
+                                writer.write_u32::<LE>(*offset)?;
+                                writer.write_u32::<LE>(*length)?;
+                            }
+                            DeltaInstruction::Insert(bytes) => {
+                                writer.write_u8(1)?;
+                                write_bytes(writer, bytes, entry.hash)?;
+                            }
+                        };
+                    }
+                }
+            };
+        }
+        Ok(())
+    }
+
+    /// Read a patch written by [`FarcPatch::write`]
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, PatchError> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != PATCH_MAGIC || reader.read_u32::<LE>()? != PATCH_VERSION {
+            return Err(PatchError::BadHeader);
+        };
+        let entry_count = reader.read_u32::<LE>()?;
+        let mut entries = Vec::new();
+        for _ in 0..entry_count {
+            let hash = reader.read_u32::<LE>()?;
+            let name = match reader.read_u8()? {
+                0 => None,
+                1 => Some(
+                    String::from_utf8(read_bytes(reader)?)
+                        .map_err(|_| PatchError::Corrupted("a file name isn't valid utf-8"))?,
+                ),
+                _ => return Err(PatchError::Corrupted("unknown name kind")),
+            };
+            let operation = match reader.read_u8()? {
+                0 => PatchOperation::Add(read_bytes(reader)?),
+                1 => PatchOperation::Remove,
+                2 => {
+                    let original_crc32 = reader.read_u32::<LE>()?;
+                    let instruction_count = reader.read_u32::<LE>()?;
+                    let mut delta = Vec::new();
+                    for _ in 0..instruction_count {
+                        delta.push(match reader.read_u8()? {
+                            0 => DeltaInstruction::Copy {
+                                offset: reader.read_u32::<LE>()?,
+                                length: reader.read_u32::<LE>()?,
+                            },
+                            1 => DeltaInstruction::Insert(read_bytes(reader)?),
+                            _ => return Err(PatchError::Corrupted("unknown delta instruction")),
+                        });
+                    }
+                    PatchOperation::Modify {
+                        original_crc32,
+                        delta,
+                    }
+                }
+                _ => return Err(PatchError::Corrupted("unknown operation")),
+            };
+            entries.push(PatchEntry {
+                hash,
+                name,
+                operation,
+            });
+        }
+        Ok(Self { entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::SyntheticFarc;
+    use std::io::Cursor;
+
+    fn serialize(entries: Vec<PatchEntry>) -> Vec<u8> {
+        let mut serialized = Vec::new();
+        FarcPatch { entries }.write(&mut serialized).unwrap();
+        serialized
+    }
+
+    fn entry(name: Option<&str>, operation: PatchOperation) -> PatchEntry {
+        PatchEntry {
+            hash: hash_name("a.bin"),
+            name: name.map(str::to_string),
+            operation,
+        }
+    }
+
+    fn modify(original_crc32: u32) -> PatchOperation {
+        PatchOperation::Modify {
+            original_crc32,
+            delta: vec![DeltaInstruction::Copy {
+                offset: 0,
+                length: 4,
+            }],
+        }
+    }
+
+    fn read(serialized: &[u8]) -> Result<FarcPatch, PatchError> {
+        FarcPatch::read(&mut Cursor::new(serialized))
+    }
+
+    #[test]
+    fn read_bad_header() {
+        let mut serialized = serialize(Vec::new());
+        assert!(read(&serialized).unwrap().entries.is_empty());
+        serialized[8] = 2;
+        assert!(matches!(read(&serialized), Err(PatchError::BadHeader)));
+        serialized[8] = 1;
+        serialized[0] = b'X';
+        assert!(matches!(read(&serialized), Err(PatchError::BadHeader)));
+        assert!(matches!(read(b"FARC"), Err(PatchError::IOError(_))));
+    }
+
+    #[test]
+    fn read_corrupted() {
+        // the 16 bytes header, then the hash of the entry, and the kind of name at 20
+        let mut serialized = serialize(vec![entry(None, PatchOperation::Remove)]);
+        serialized[20] = 2;
+        assert!(matches!(
+            read(&serialized),
+            Err(PatchError::Corrupted("unknown name kind"))
+        ));
+        serialized[20] = 0;
+        serialized[21] = 3;
+        assert!(matches!(
+            read(&serialized),
+            Err(PatchError::Corrupted("unknown operation"))
+        ));
+
+        // the name is at 25, after its length
+        let mut serialized = serialize(vec![entry(Some("a.bin"), PatchOperation::Remove)]);
+        serialized[25] = 0xFF;
+        assert!(matches!(
+            read(&serialized),
+            Err(PatchError::Corrupted("a file name isn't valid utf-8"))
+        ));
+
+        // the kind of the first instruction is at 30, after the crc32 and the number of instructions
+        let mut serialized = serialize(vec![entry(None, modify(0))]);
+        serialized[30] = 2;
+        assert!(matches!(
+            read(&serialized),
+            Err(PatchError::Corrupted("unknown delta instruction"))
+        ));
+    }
+
+    #[test]
+    fn read_truncated() {
+        let serialized = serialize(vec![entry(None, PatchOperation::Add(vec![1; 32]))]);
+        assert!(matches!(
+            read(&serialized[..serialized.len() - 1]),
+            Err(PatchError::Corrupted(
+                "the patch end in the middle of some data"
+            ))
+        ));
+        // the length of the added content
+        assert!(matches!(
+            read(&serialized[..24]),
+            Err(PatchError::IOError(_))
+        ));
+        // the file count is bigger than the number of entries
+        let mut serialized = serialize(Vec::new());
+        serialized[12] = 1;
+        assert!(matches!(read(&serialized), Err(PatchError::IOError(_))));
+    }
+
+    #[test]
+    fn apply_to_another_archive() {
+        let farc = SyntheticFarc::new()
+            .with_file("a.bin", b"abcdefgh".to_vec())
+            .build_farc();
+        let apply = |operation| {
+            FarcPatch {
+                entries: vec![entry(None, operation)],
+            }
+            .apply(&farc)
+        };
+
+        assert!(matches!(
+            apply(PatchOperation::Add(Vec::new())),
+            Err(PatchError::AlreadyPresent(hash)) if hash == hash_name("a.bin")
+        ));
+        // the file is read with the padding to 16 bytes
+        let content = farc.read_hashed_file(hash_name("a.bin")).unwrap();
+        assert!(apply(modify(checksum_ieee(&content))).is_ok());
+        assert!(matches!(
+            apply(modify(checksum_ieee(b"abcdefgh"))),
+            Err(PatchError::OriginalMismatch(_))
+        ));
+        // a delta copying bytes past the end of the original file
+        assert!(matches!(
+            apply(PatchOperation::Modify {
+                original_crc32: checksum_ieee(&content),
+                delta: vec![DeltaInstruction::Copy {
+                    offset: 4,
+                    length: u32::MAX,
+                }],
+            }),
+            Err(PatchError::OriginalMismatch(_))
+        ));
+
+        let empty = SyntheticFarc::new().build_farc();
+        for operation in [PatchOperation::Remove, modify(0)] {
+            let patch = FarcPatch {
+                entries: vec![entry(None, operation)],
+            };
+            assert!(matches!(
+                patch.apply(&empty),
+                Err(PatchError::MissingFile(hash)) if hash == hash_name("a.bin")
+            ));
+        }
+    }
+}
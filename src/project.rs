@@ -0,0 +1,408 @@
+use crate::{
+    hash_name, Farc, FarcError, FarcWriter, FarcWriterError, TransformError, TransformRegistry,
+};
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fmt::Write as _;
+use std::fs::{create_dir_all, read, read_to_string, write};
+use std::io::{self, Read, Seek, Write};
+use std::path::{Component, Path};
+use thiserror::Error;
+use toml_edit::{DocumentMut, Item};
+
+/// The name of the file describing a project, at the root of its directory
+pub const PROJECT_FILE_NAME: &str = "farc.toml";
+
+/// The directory of a project containing the files of the archive
+pub const PROJECT_FILES_DIRECTORY: &str = "files";
+
+/// An error that can happen while converting an archive to a [`Project`] or back
+#[derive(Debug, Error)]
+pub enum ProjectError {
+    /// An error happened while reading or writing a file of the project
+    #[error("input/output error")]
+    IOError(#[from] io::Error),
+    /// The archive can't be read
+    #[error("the archive can't be read")]
+    FarcError(#[from] FarcError),
+    /// The archive can't be written
+    #[error("the archive can't be written")]
+    FarcWriterError(#[from] FarcWriterError),
+    /// A file can't be converted with its [`Transform`](crate::Transform)
+    #[error("a file can't be transformed")]
+    TransformError(#[from] TransformError),
+    /// ``farc.toml`` isn't valid toml
+    #[error("farc.toml isn't valid toml: {0}")]
+    InvalidToml(String),
+    /// A field of ``farc.toml`` is missing or invalid
+    #[error("the field {field:?} of farc.toml is invalid: {reason}")]
+    InvalidField {
+        /// The field, like ``files[2].hash``
+        field: String,
+        /// Why it is invalid
+        reason: String,
+    },
+    /// Two files of the project have the same hash
+    #[error("the hash {0:08x} is used by several files")]
+    DuplicateHash(u32),
+}
+
+/// A file of a [`Project`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProjectFile {
+    /// The hash of the name of the file
+    pub hash: u32,
+    /// The name of the file in the archive, if known
+    pub name: Option<String>,
+    /// The path of the content of the file, relative to the ``files`` directory of the project
+    pub path: String,
+    /// The name of the [`Transform`](crate::Transform) the file was converted with when it was extracted, if any
+    pub transform: Option<String>,
+}
+
+/// An archive as an editable directory, to keep a mod in version control: a ``farc.toml`` file describing the archive,
+/// and its files in the ``files`` directory.
+///
+/// ```toml
+/// named = false
+/// alignment = 16
+///
+/// [[files]]
+/// hash = 0x22089cae
+/// path = "0x22089cae.bin"
+///
+/// [[files]]
+/// hash = 0xc93f27ad
+/// name = "a.bin"
+/// path = "a.bin"
+/// ```
+/// - ``named`` tell whether the archive stores the file names, or only their hashes
+/// - ``alignment`` is the alignment of the start of each file, in bytes (a multiple of 16)
+/// - every ``[[files]]`` is a file, in the order they were stored in the archive. The ``hash`` can be omitted if the
+///   ``name`` is given. An optional ``transform`` is the name of the [`Transform`](crate::Transform) the file was
+///   converted with (see [`Farc::to_project_with`]), reversed when the archive is built.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, Farc, FarcWriter, Project};
+/// use std::io::{Cursor, Read};
+/// let directory = std::env::temp_dir().join(format!("pmd_farc_project_doctest_{}", std::process::id()));
+/// let mut writer = FarcWriter::default();
+/// writer.add_hashed_file(hash_name("script_001.bin"), b"hello".to_vec());
+/// let mut archive = Cursor::new(Vec::new());
+/// writer.write_hashed(&mut archive).unwrap();
+/// let mut farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+/// farc.check_file_name("script_001.bin");
+/// farc.to_project(&directory).unwrap();
+///
+/// // a modder edit the file, and add a new one to farc.toml
+/// std::fs::write(directory.join("files/script_001.bin"), b"modified").unwrap();
+/// std::fs::write(directory.join("files/new.bin"), b"new").unwrap();
+/// let mut description = std::fs::read_to_string(directory.join("farc.toml")).unwrap();
+/// description.push_str("\n[[files]]\nname = \"new.bin\"\npath = \"new.bin\"\n");
+/// std::fs::write(directory.join("farc.toml"), description).unwrap();
+///
+/// let mut archive = Cursor::new(Vec::new());
+/// Project::read(&directory).unwrap().build(&directory, &mut archive).unwrap();
+/// let farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+/// assert_eq!(farc.file_count(), 2);
+/// let mut content = Vec::new();
+/// farc.get_named_file("script_001.bin").unwrap().read_to_end(&mut content).unwrap();
+/// assert!(content.starts_with(b"modified"));
+/// # std::fs::remove_dir_all(&directory).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Project {
+    /// Whether the archive stores the file names
+    pub named: bool,
+    /// The alignment of the start of each file, in bytes
+    pub alignment: u32,
+    /// The files, in the order they were stored in the archive
+    pub files: Vec<ProjectFile>,
+}
+
+impl Default for Project {
+    fn default() -> Self {
+        Self {
+            named: false,
+            alignment: 16,
+            files: Vec::new(),
+        }
+    }
+}
+
+/// Return the name of a file in the ``files`` directory: its name if it can be used as a file name, or
+/// ``0x{hash}.bin`` otherwise
+fn project_path(hash: u32, name: Option<&str>, used: &HashSet<String>) -> String {
+    match name {
+        Some(name)
+            if !name.is_empty()
+                && !name.contains(['/', '\\'])
+                && !name.chars().any(char::is_control)
+                && name != "."
+                && name != ".."
+                && !used.contains(name) =>
+        {
+            name.to_string()
+        }
+        _ => format!("0x{:08x}.bin", hash),
+    }
+}
+
+/// Write a toml basic string
+fn toml_string(text: &str) -> String {
+    let mut result = String::from("\"");
+    for chara in text.chars() {
+        match chara {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            chara if chara.is_control() => {
+                let _ = write!(result, "\\u{:04x}", chara as u32);
+            }
+            chara => result.push(chara),
+        };
+    }
+    result.push('"');
+    result
+}
+
+impl Project {
+    /// Create the description of a [`Farc`], without writing its files
+    pub fn from_farc<F: Read + Seek>(farc: &Farc<F>) -> Self {
+        let mut files = farc.iter_files().collect::<Vec<_>>();
+        files.sort_by_key(|file| (file.start, file.name_hash));
+        let mut used = HashSet::new();
+        let files = files
+            .into_iter()
+            .map(|file| {
+                let path = project_path(file.name_hash, file.name.as_deref(), &used);
+                used.insert(path.clone());
+                ProjectFile {
+                    hash: file.name_hash,
+                    name: file.name.clone(),
+                    path,
+                    transform: None,
+                }
+            })
+            .collect();
+        Self {
+            named: farc.is_named_in_archive(),
+            alignment: 16,
+            files,
+        }
+    }
+
+    /// Return the content of ``farc.toml`` for this project
+    #[must_use]
+    pub fn to_toml(&self) -> String {
+        let mut toml = String::new();
+        let _ = writeln!(toml, "named = {}", self.named);
+        let _ = writeln!(toml, "alignment = {}", self.alignment);
+        for file in &self.files {
+            let _ = writeln!(toml, "\n[[files]]\nhash = 0x{:08x}", file.hash);
+            if let Some(name) = &file.name {
+                let _ = writeln!(toml, "name = {}", toml_string(name));
+            };
+            let _ = writeln!(toml, "path = {}", toml_string(&file.path));
+            if let Some(transform) = &file.transform {
+                let _ = writeln!(toml, "transform = {}", toml_string(transform));
+            };
+        }
+        toml
+    }
+
+    /// Parse the content of ``farc.toml``
+    pub fn from_toml(toml: &str) -> Result<Self, ProjectError> {
+        let document = toml
+            .parse::<DocumentMut>()
+            .map_err(|err| ProjectError::InvalidToml(err.to_string()))?;
+        let invalid = |field: &str, reason: &str| ProjectError::InvalidField {
+            field: field.to_string(),
+            reason: reason.to_string(),
+        };
+        let mut project = Self::default();
+        if let Some(named) = document.get("named") {
+            project.named = named
+                .as_bool()
+                .ok_or_else(|| invalid("named", "should be a boolean"))?;
+        };
+        if let Some(alignment) = document.get("alignment") {
+            project.alignment = alignment
+                .as_integer()
+                .and_then(|alignment| u32::try_from(alignment).ok())
+                .filter(|alignment| *alignment != 0 && alignment.is_multiple_of(16))
+                .ok_or_else(|| invalid("alignment", "should be a non-zero multiple of 16"))?;
+        };
+        let files = match document.get("files") {
+            None => return Ok(project),
+            Some(Item::ArrayOfTables(files)) => files,
+            Some(_) => return Err(invalid("files", "should be an array of tables")),
+        };
+        let mut hashes = HashSet::new();
+        for (index, table) in files.iter().enumerate() {
+            let field = |name: &str| format!("files[{}].{}", index, name);
+            let name = match table.get("name") {
+                Some(name) => Some(
+                    name.as_str()
+                        .ok_or_else(|| invalid(&field("name"), "should be a string"))?
+                        .to_string(),
+                ),
+                None => None,
+            };
+            let hash = match (table.get("hash"), &name) {
+                (Some(hash), _) => hash
+                    .as_integer()
+                    .and_then(|hash| u32::try_from(hash).ok())
+                    .ok_or_else(|| invalid(&field("hash"), "should be a 32 bits integer"))?,
+                (None, Some(name)) => hash_name(name),
+                (None, None) => {
+                    return Err(invalid(&field("hash"), "is required if there is no name"))
+                }
+            };
+            if let Some(name) = &name {
+                if hash_name(name) != hash {
+                    return Err(invalid(&field("hash"), "doesn't match the name"));
+                };
+            };
+            let path = table
+                .get("path")
+                .and_then(Item::as_str)
+                .ok_or_else(|| invalid(&field("path"), "is required, and should be a string"))?;
+            let is_relative = Path::new(path)
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+            if path.is_empty() || !is_relative {
+                return Err(invalid(
+                    &field("path"),
+                    "should be a relative path inside the files directory",
+                ));
+            };
+            let transform = match table.get("transform") {
+                Some(transform) => Some(
+                    transform
+                        .as_str()
+                        .ok_or_else(|| invalid(&field("transform"), "should be a string"))?
+                        .to_string(),
+                ),
+                None => None,
+            };
+            if !hashes.insert(hash) {
+                return Err(ProjectError::DuplicateHash(hash));
+            };
+            project.files.push(ProjectFile {
+                hash,
+                name,
+                path: path.to_string(),
+                transform,
+            });
+        }
+        Ok(project)
+    }
+
+    /// Read the ``farc.toml`` of the project in ``directory``
+    pub fn read<P: AsRef<Path>>(directory: P) -> Result<Self, ProjectError> {
+        Self::from_toml(&read_to_string(directory.as_ref().join(PROJECT_FILE_NAME))?)
+    }
+
+    /// Write the ``farc.toml`` of the project in ``directory``
+    pub fn write<P: AsRef<Path>>(&self, directory: P) -> Result<(), ProjectError> {
+        create_dir_all(directory.as_ref())?;
+        write(directory.as_ref().join(PROJECT_FILE_NAME), self.to_toml())?;
+        Ok(())
+    }
+
+    /// Create a [`FarcWriter`] with the files of the project in ``directory``
+    pub fn to_writer<P: AsRef<Path>>(&self, directory: P) -> Result<FarcWriter, ProjectError> {
+        self.to_writer_with(directory, &TransformRegistry::default())
+    }
+
+    /// Create a [`FarcWriter`] with the files of the project in ``directory``, converting back the files extracted
+    /// with a [`Transform`](crate::Transform) of ``registry``
+    pub fn to_writer_with<P: AsRef<Path>>(
+        &self,
+        directory: P,
+        registry: &TransformRegistry,
+    ) -> Result<FarcWriter, ProjectError> {
+        let files_directory = directory.as_ref().join(PROJECT_FILES_DIRECTORY);
+        let mut writer = FarcWriter::default();
+        writer.set_alignment(self.alignment)?;
+        for file in &self.files {
+            let content = registry.pack(
+                file.transform.as_deref(),
+                &read(files_directory.join(&file.path))?,
+            )?;
+            match &file.name {
+                Some(name) => writer.add_named_file(name.clone(), content),
+                None => writer.add_hashed_file(file.hash, content),
+            };
+        }
+        Ok(writer)
+    }
+
+    /// Write the archive of the project in ``directory``, in the named or hashed form depending on
+    /// [`Project::named`]
+    pub fn build<P: AsRef<Path>, W: Write + Seek>(
+        &self,
+        directory: P,
+        output: &mut W,
+    ) -> Result<(), ProjectError> {
+        self.build_with(directory, output, &TransformRegistry::default())
+    }
+
+    /// Write the archive of the project in ``directory`` like [`Project::build`], converting back the files
+    /// extracted with a [`Transform`](crate::Transform) of ``registry``
+    pub fn build_with<P: AsRef<Path>, W: Write + Seek>(
+        &self,
+        directory: P,
+        output: &mut W,
+        registry: &TransformRegistry,
+    ) -> Result<(), ProjectError> {
+        let writer = self.to_writer_with(directory, registry)?;
+        if self.named {
+            writer.write_named(output)?;
+        } else {
+            writer.write_hashed(output)?;
+        };
+        Ok(())
+    }
+}
+
+impl<F: Read + Seek> Farc<F> {
+    /// Write the files of this archive to the ``files`` directory of ``directory``, with a ``farc.toml`` describing
+    /// it (see [`Project`])
+    pub fn to_project<P: AsRef<Path>>(&self, directory: P) -> Result<Project, ProjectError> {
+        self.to_project_with(directory, &TransformRegistry::default())
+    }
+
+    /// Write the files of this archive to a project like [`Farc::to_project`], converting them with the
+    /// [`Transform`](crate::Transform) of ``registry`` that apply to them. The extension of the transform is added to
+    /// their path.
+    pub fn to_project_with<P: AsRef<Path>>(
+        &self,
+        directory: P,
+        registry: &TransformRegistry,
+    ) -> Result<Project, ProjectError> {
+        let mut project = Project::from_farc(self);
+        let files_directory = directory.as_ref().join(PROJECT_FILES_DIRECTORY);
+        create_dir_all(&files_directory)?;
+        for file in &mut project.files {
+            let transformed = self.get_transformed_file(file.hash, registry)?;
+            if let Some(extension) = &transformed.extension {
+                file.path = format!("{}.{}", file.path, extension);
+            };
+            file.transform = transformed.transform;
+            write(files_directory.join(&file.path), transformed.content)?;
+        }
+        project.write(directory)?;
+        Ok(project)
+    }
+}
+
+impl FarcWriter {
+    /// Create a [`FarcWriter`] from the project in ``directory`` (see [`Project`])
+    pub fn from_project<P: AsRef<Path>>(directory: P) -> Result<Self, ProjectError> {
+        Project::read(directory.as_ref())?.to_writer(directory)
+    }
+}
@@ -0,0 +1,1046 @@
+//! A minimal parser of the FARC structure, that only need ``alloc``. It is the only part of the crate available
+//! without the ``std`` feature, for example to read archives directly on the console.
+//!
+//! The data are read through the [`ReadAt`] trait, implemented for byte slices and (with the ``std`` feature) files,
+//! and that [`Farc::from_storage`](crate::Farc::from_storage) can also use. Contrary to [`Farc`](crate::Farc),
+//! the sir0 pointer list isn't checked, and the file names aren't indexed.
+//!
+//! Both are built on [`FarcParser`], which doesn't perform any input/output itself, and can thus be used with any
+//! kind of source.
+//!
+//! # Example
+//! ```
+//! use pmd_farc::raw::{read_entries, read_header, RawName};
+//! use pmd_farc::{hash_name, FarcWriter};
+//! use std::io::Cursor;
+//! let mut writer = FarcWriter::default();
+//! writer.add_hashed_file(hash_name("a.bin"), b"hello".to_vec());
+//! let mut archive = Cursor::new(Vec::new());
+//! writer.write_hashed(&mut archive).unwrap();
+//! let archive = archive.into_inner();
+//!
+//! let header = read_header(archive.as_slice()).unwrap();
+//! let entries = read_entries(archive.as_slice(), &header).unwrap();
+//! assert_eq!(entries[0].name, RawName::Hash(hash_name("a.bin")));
+//! let start = entries[0].start as usize;
+//! assert_eq!(&archive[start..start + 5], b"hello");
+//! ```
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::convert::{TryFrom, TryInto};
+use core::fmt;
+use crc::crc32;
+
+#[cfg(feature = "std")]
+pub(crate) fn string_to_utf16(to_transform: &str) -> impl Iterator<Item = u8> + '_ {
+    to_transform
+        .encode_utf16()
+        .flat_map(|chara| chara.to_le_bytes())
+}
+
+/// Hash a name, first transforming it into utf16, then applying the ieee crc32 checksum
+#[must_use]
+pub fn hash_name(name: &str) -> u32 {
+    // hashed by chunks, to not allocate the utf-16 name
+    let mut chunk = [0; 64];
+    let mut chunk_length = 0;
+    let mut hash = 0;
+    for chara in name.encode_utf16() {
+        if chunk_length == chunk.len() {
+            hash = crc32::update(hash, &crc32::IEEE_TABLE, &chunk);
+            chunk_length = 0;
+        };
+        chunk[chunk_length..chunk_length + 2].copy_from_slice(&chara.to_le_bytes());
+        chunk_length += 2;
+    }
+    crc32::update(hash, &crc32::IEEE_TABLE, &chunk[..chunk_length])
+}
+
+/// A source of bytes that can be read at any position, without being modified
+pub trait ReadAt {
+    /// Return the number of readable bytes, or [`RawError::LengthFailed`] if the source can't tell it
+    fn len(&self) -> Result<u64, RawError>;
+
+    /// Return true if there is no readable bytes
+    fn is_empty(&self) -> Result<bool, RawError> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Fill ``buffer`` with the bytes starting at ``offset``. Return [`RawError::OutOfBounds`] if there isn't
+    /// enough bytes.
+    fn read_exact_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), RawError>;
+}
+
+impl ReadAt for [u8] {
+    fn len(&self) -> Result<u64, RawError> {
+        Ok(<[u8]>::len(self) as u64)
+    }
+
+    fn read_exact_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), RawError> {
+        let out_of_bounds = RawError::OutOfBounds {
+            offset,
+            length: buffer.len() as u64,
+        };
+        let start = usize::try_from(offset).map_err(|_| out_of_bounds.clone())?;
+        let source = start
+            .checked_add(buffer.len())
+            .and_then(|end| self.get(start..end))
+            .ok_or(out_of_bounds)?;
+        buffer.copy_from_slice(source);
+        Ok(())
+    }
+}
+
+impl ReadAt for Vec<u8> {
+    fn len(&self) -> Result<u64, RawError> {
+        Ok(self.as_slice().len() as u64)
+    }
+
+    fn read_exact_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), RawError> {
+        self.as_slice().read_exact_at(offset, buffer)
+    }
+}
+
+macro_rules! impl_read_at_for_pointer {
+    ($($pointer:ty),*) => {
+        $(
+            impl<T: ReadAt + ?Sized> ReadAt for $pointer {
+                fn len(&self) -> Result<u64, RawError> {
+                    (**self).len()
+                }
+
+                fn read_exact_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), RawError> {
+                    (**self).read_exact_at(offset, buffer)
+                }
+            }
+        )*
+    };
+}
+
+impl_read_at_for_pointer!(&T, Box<T>, Arc<T>);
+
+/// Decode the little-endian ``u32`` at ``position`` in ``bytes``
+fn u32_at(bytes: &[u8], position: usize) -> u32 {
+    u32::from_le_bytes([
+        bytes[position],
+        bytes[position + 1],
+        bytes[position + 2],
+        bytes[position + 3],
+    ])
+}
+
+#[cfg(any(all(test, feature = "std"), feature = "test-util"))]
+pub(crate) fn read_u32_at<S: ReadAt + ?Sized>(source: &S, offset: u64) -> Result<u32, RawError> {
+    let mut buffer = [0; 4];
+    source.read_exact_at(offset, &mut buffer)?;
+    Ok(u32::from_le_bytes(buffer))
+}
+
+/// An error that the functions of the [`raw`](self) module can return
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RawError {
+    /// Some bytes are read past the end of the source
+    OutOfBounds {
+        /// The position of the first read byte
+        offset: u64,
+        /// The number of read bytes
+        length: u64,
+    },
+    /// The file doesn't start with ``FARC``
+    BadMagic,
+    /// The sir0 type in the header is neither 4 nor 5
+    UnsupportedSir0Type(u32),
+    /// The sir0 section doesn't start with ``SIR0``
+    BadSir0Magic,
+    /// The sir0 content header is shorter than 12 bytes
+    Sir0HeaderTooShort(u32),
+    /// The fat5 type is not recognized
+    UnsupportedFat5Type(u32),
+    /// The start of a sub-file overflow a u32 integer (data offset, offset of the file)
+    DataStartOverflow(u32, u32),
+    /// A sub-file doesn't start at an offset that is a multiple of 16
+    BadAlignment(u32),
+    /// A file name isn't valid utf-16
+    InvalidName,
+    /// The source failed to read some bytes, for example because of an input/output error
+    ReadFailed {
+        /// The position of the first read byte
+        offset: u64,
+        /// The number of read bytes
+        length: u64,
+        /// The description of the error
+        reason: String,
+    },
+    /// The source failed to tell its length, for example because of an input/output error
+    LengthFailed {
+        /// The description of the error
+        reason: String,
+    },
+}
+
+impl fmt::Display for RawError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::OutOfBounds { offset, length } => write!(
+                f,
+                "can't read {} bytes at 0x{:x}, after the end of the file",
+                length, offset
+            ),
+            Self::BadMagic => write!(f, "the file doesn't start with the FARC magic"),
+            Self::UnsupportedSir0Type(t) => {
+                write!(f, "the sir0 type is not supported: found {}", t)
+            }
+            Self::BadSir0Magic => write!(f, "the sir0 section doesn't start with the SIR0 magic"),
+            Self::Sir0HeaderTooShort(l) => write!(
+                f,
+                "the sir0 header should be at least 12 bytes long, but it is only {} bytes long",
+                l
+            ),
+            Self::UnsupportedFat5Type(t) => {
+                write!(f, "the fat5 type is not supported: found {}", t)
+            }
+            Self::DataStartOverflow(a, b) => write!(
+                f,
+                "a contained file position overflow a u32 integer ({}+{})",
+                a, b
+            ),
+            Self::BadAlignment(start) => write!(
+                f,
+                "a sub-file start at 0x{:x}, which isn't a multiple of 16",
+                start
+            ),
+            Self::InvalidName => write!(f, "a file name isn't valid utf-16"),
+            Self::ReadFailed {
+                offset,
+                length,
+                reason,
+            } => write!(
+                f,
+                "can't read {} bytes at 0x{:x}: {}",
+                length, offset, reason
+            ),
+            Self::LengthFailed { reason } => {
+                write!(f, "can't get the length of the file: {}", reason)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RawError {}
+
+/// The header of a FARC file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RawHeader {
+    /// The sir0 type (4 or 5)
+    pub sir0_type: u32,
+    /// The offset of the sir0 section, containing the file table
+    pub sir0_offset: u32,
+    /// The length of the sir0 section
+    pub sir0_length: u32,
+    /// The offset of the data section. The position of the sub-files are relative to it.
+    pub data_offset: u32,
+    /// The length of the data section, as written in the header (it may go past the end of the file)
+    pub data_length: u32,
+}
+
+/// The length of the header of a FARC file
+pub(crate) const HEADER_LENGTH: usize = 0x34;
+
+impl RawHeader {
+    /// Decode the header at the start of ``bytes``, without checking the magic and the sir0 type
+    pub(crate) fn decode(bytes: &[u8; HEADER_LENGTH]) -> Self {
+        Self {
+            sir0_type: u32_at(bytes, 0x20),
+            sir0_offset: u32_at(bytes, 0x24),
+            sir0_length: u32_at(bytes, 0x28),
+            data_offset: u32_at(bytes, 0x2C),
+            data_length: u32_at(bytes, 0x30),
+        }
+    }
+}
+
+/// Read the 0x34 bytes long header of a FARC file
+pub fn read_header<S: ReadAt + ?Sized>(source: &S) -> Result<RawHeader, RawError> {
+    match FarcParser::new().next_from(source)? {
+        ParseStep::Header(header) => Ok(header),
+        _ => unreachable!("the header is the first thing found by the parser"),
+    }
+}
+
+/// How a sub-file is identified in the file table
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RawName {
+    /// The crc32 hash of the name (see [`hash_name`])
+    Hash(u32),
+    /// The name itself
+    Name(String),
+}
+
+impl RawName {
+    /// Return the hash of the name
+    #[must_use]
+    pub fn hash(&self) -> u32 {
+        match self {
+            Self::Hash(hash) => *hash,
+            Self::Name(name) => hash_name(name),
+        }
+    }
+}
+
+/// An entry of the file table of a FARC file
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RawEntry {
+    /// The name or the hash of the sub-file
+    pub name: RawName,
+    /// The offset of the sub-file, from the start of the FARC file
+    pub start: u32,
+    /// The length of the sub-file
+    pub length: u32,
+}
+
+/// Read the file table of a FARC file, in the order it is stored in the file
+pub fn read_entries<S: ReadAt + ?Sized>(
+    source: &S,
+    header: &RawHeader,
+) -> Result<Vec<RawEntry>, RawError> {
+    let mut parser = FarcParser::from_header(header);
+    let mut entries = Vec::new();
+    loop {
+        match parser.next_from(source)? {
+            // the file count isn't trusted, as each entry must fit in the sir0 section
+            ParseStep::Table { file_count, .. } => {
+                entries.reserve(file_count.min(header.sir0_length / 12) as usize)
+            }
+            ParseStep::Entry(entry) => entries.push(entry),
+            ParseStep::Done => return Ok(entries),
+            // the invalid entries and the truncated table are only reported in lenient mode
+            ParseStep::Header(_)
+            | ParseStep::Read { .. }
+            | ParseStep::InvalidEntry { .. }
+            | ParseStep::TableTruncated { .. } => (),
+        }
+    }
+}
+
+/// What a [`FarcParser`] need to continue, or what it found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseStep {
+    /// The parser need the bytes at this position, to be given to [`FarcParser::feed`]
+    Read {
+        /// The position of the first byte, from the start of the FARC file
+        offset: u64,
+        /// The number of bytes
+        length: usize,
+    },
+    /// The header has been parsed
+    Header(RawHeader),
+    /// The start of the file table has been parsed
+    Table {
+        /// The position of the first entry, from the start of the FARC file
+        offset: u64,
+        /// The number of entries in the table
+        file_count: u32,
+        /// True if the entries contain the names of the files, false if they only contain their hash
+        named: bool,
+    },
+    /// An entry of the file table has been parsed. They are found in the order they are stored.
+    Entry(RawEntry),
+    /// In lenient mode (see [`FarcParser::lenient`]), an entry of the file table is invalid, and was skipped
+    InvalidEntry {
+        /// The position of the entry in the file table
+        index: u32,
+        /// Why it is invalid
+        error: RawError,
+    },
+    /// In lenient mode, the file table is cut by the end of the source. The following entries are skipped.
+    TableTruncated {
+        /// The number of entries according to the file table
+        expected: u32,
+        /// The number of entries that could be read
+        read: u32,
+    },
+    /// The whole file table has been parsed
+    Done,
+}
+
+#[derive(Debug, Clone)]
+enum ParserState {
+    Header,
+    Sir0Header,
+    ContentHeader {
+        offset: u64,
+    },
+    Entries,
+    /// Reading the name of the first pending entry, continuing at ``offset``
+    Names {
+        offset: u64,
+        name: Vec<u16>,
+    },
+    Done,
+}
+
+/// A parser of the header and the file table of a FARC file, that doesn't perform any input/output itself.
+/// [`FarcParser::next_step`] tell which bytes it need, and they should be given back with [`FarcParser::feed`].
+/// This permit to read archives with any kind of source, for example an asynchronous one, or one only available
+/// without ``std``. [`FarcParser::next_from`] drive it with a [`ReadAt`] source, and
+/// [`Farc::new`](crate::Farc::new) with a ``Read + Seek`` one.
+///
+/// The file table is read in bulk (by chunks of 16384 entries, so a corrupted file count can't cause a huge
+/// allocation), and the names by chunks of up to 4KiB, which usually contain several of them. Like
+/// [`read_entries`], the sir0 pointer list isn't checked. The parser shouldn't be used after an error, except in
+/// lenient mode (see [`FarcParser::lenient`]).
+///
+/// # Example
+/// ```
+/// use pmd_farc::raw::{FarcParser, ParseStep, RawName};
+/// use pmd_farc::{hash_name, FarcWriter};
+/// use std::io::Cursor;
+/// let mut writer = FarcWriter::default();
+/// writer.add_hashed_file(hash_name("a.bin"), b"hello".to_vec());
+/// let mut archive = Cursor::new(Vec::new());
+/// writer.write_hashed(&mut archive).unwrap();
+/// let archive = archive.into_inner();
+///
+/// let mut parser = FarcParser::new();
+/// let mut hashes = Vec::new();
+/// loop {
+///     match parser.next_step() {
+///         // this could be an asynchronous read, or a request to a server
+///         ParseStep::Read { offset, length } => {
+///             let offset = offset as usize;
+///             parser.feed(&archive[offset..offset + length]).unwrap();
+///         }
+///         ParseStep::Entry(entry) => hashes.push(entry.name.hash()),
+///         ParseStep::Done => break,
+///         _ => (),
+///     }
+/// }
+/// assert_eq!(hashes, vec![hash_name("a.bin")]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FarcParser {
+    state: ParserState,
+    found: VecDeque<ParseStep>,
+    data_offset: u32,
+    sir0_offset: u64,
+    sir0_end: u64,
+    table_offset: u64,
+    file_count: u32,
+    named: bool,
+    /// The number of entries already read
+    read_entries: u32,
+    /// The entries read but not yet returned, as their index, the name offset or hash, the start and the length
+    pending: VecDeque<(u32, u32, u32, u32)>,
+    /// Whether the invalid entries and a truncated file table are reported instead of being an error
+    lenient: bool,
+    /// Whether the sub-files must start at a multiple of 16
+    check_alignment: bool,
+}
+
+/// The maximum number of entries of the file table read at once
+const TABLE_CHUNK_ENTRIES: u32 = 0x4000;
+
+/// The maximum number of bytes read at once for the names
+const NAME_CHUNK_LENGTH: u64 = 0x1000;
+
+impl Default for FarcParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FarcParser {
+    /// Create a parser for a FARC file, starting with its header
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            state: ParserState::Header,
+            found: VecDeque::new(),
+            data_offset: 0,
+            sir0_offset: 0,
+            sir0_end: 0,
+            table_offset: 0,
+            file_count: 0,
+            named: false,
+            read_entries: 0,
+            pending: VecDeque::new(),
+            lenient: false,
+            check_alignment: true,
+        }
+    }
+
+    /// Create a parser for a FARC file whose header has already been read. It start with the file table.
+    #[must_use]
+    pub const fn from_header(header: &RawHeader) -> Self {
+        Self {
+            state: ParserState::Sir0Header,
+            found: VecDeque::new(),
+            data_offset: header.data_offset,
+            sir0_offset: header.sir0_offset as u64,
+            sir0_end: header.sir0_offset as u64 + header.sir0_length as u64,
+            table_offset: 0,
+            file_count: 0,
+            named: false,
+            read_entries: 0,
+            pending: VecDeque::new(),
+            lenient: false,
+            check_alignment: true,
+        }
+    }
+
+    /// Create a parser for a fat5 table on its own, with ``file_count`` entries starting at ``table_offset`` in a
+    /// sir0 section of ``sir0_length`` bytes. The offsets are relative to the start of the section, and the start of
+    /// the entries is their offset in the data section, which doesn't need to be aligned.
+    #[cfg(feature = "std")]
+    pub(crate) const fn from_table(
+        table_offset: u64,
+        file_count: u32,
+        named: bool,
+        sir0_length: u64,
+    ) -> Self {
+        Self {
+            state: ParserState::Entries,
+            found: VecDeque::new(),
+            data_offset: 0,
+            sir0_offset: 0,
+            sir0_end: sir0_length,
+            table_offset,
+            file_count,
+            named,
+            read_entries: 0,
+            pending: VecDeque::new(),
+            lenient: false,
+            check_alignment: false,
+        }
+    }
+
+    /// Make the parser lenient, to recover what can be from a damaged archive. The header and the start of the file
+    /// table must still be valid, but:
+    /// - an entry whose start overflow, or whose name is invalid or cut by the end of the source, is reported with
+    ///   [`ParseStep::InvalidEntry`] and skipped
+    /// - a file table cut by the end of the source is reported with [`ParseStep::TableTruncated`], and the readable
+    ///   entries are still returned
+    /// - the sub-files don't need to start at a multiple of 16
+    ///
+    /// [`FarcParser::feed`] then accept less bytes than asked for the file table and the names, and
+    /// [`FarcParser::next_from`] only read the bytes before the end of the source.
+    #[must_use]
+    pub const fn lenient(mut self) -> Self {
+        self.lenient = true;
+        self.check_alignment = false;
+        self
+    }
+
+    /// Return the bytes needed to continue, as an offset and a length
+    fn needed(&self) -> Option<(u64, usize)> {
+        match &self.state {
+            ParserState::Header => Some((0, 0x34)),
+            ParserState::Sir0Header => Some((self.sir0_offset, 12)),
+            ParserState::ContentHeader { offset } => Some((*offset, 12)),
+            ParserState::Entries if self.read_entries < self.file_count => {
+                let count = (self.file_count - self.read_entries).min(TABLE_CHUNK_ENTRIES);
+                Some((
+                    self.table_offset + u64::from(self.read_entries) * 12,
+                    count as usize * 12,
+                ))
+            }
+            ParserState::Entries => None,
+            // read up to the end of the sir0 section, where the names are
+            ParserState::Names { offset, .. } => Some((
+                *offset,
+                self.sir0_end
+                    .saturating_sub(*offset)
+                    .clamp(2, NAME_CHUNK_LENGTH) as usize,
+            )),
+            ParserState::Done => None,
+        }
+    }
+
+    /// Return what was found since the last call, or else what the parser need to continue
+    pub fn next_step(&mut self) -> ParseStep {
+        if let Some(found) = self.found.pop_front() {
+            return found;
+        };
+        match self.needed() {
+            Some((offset, length)) => ParseStep::Read { offset, length },
+            None => ParseStep::Done,
+        }
+    }
+
+    /// Give the bytes asked by the last [`ParseStep::Read`] returned by [`FarcParser::next_step`], which should be
+    /// called again afterward. Return [`RawError::OutOfBounds`] if ``bytes`` is shorter than what was asked, for
+    /// example because the source ended.
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<(), RawError> {
+        let (offset, length) = match self.needed() {
+            Some(needed) => needed,
+            None => return Ok(()),
+        };
+        // in lenient mode, the file table and the names may be cut by the end of the source
+        let bytes = match self.state {
+            ParserState::Entries | ParserState::Names { .. } if self.lenient => {
+                &bytes[..bytes.len().min(length)]
+            }
+            _ => bytes.get(..length).ok_or(RawError::OutOfBounds {
+                offset,
+                length: length as u64,
+            })?,
+        };
+        let truncated = bytes.len() < length;
+        let u32_at = |position: usize| u32_at(bytes, position);
+        self.state = match core::mem::replace(&mut self.state, ParserState::Done) {
+            ParserState::Header => {
+                if &bytes[..4] != b"FARC" {
+                    return Err(RawError::BadMagic);
+                };
+                let header = RawHeader::decode(bytes.try_into().expect("the header is 0x34 bytes"));
+                if header.sir0_type != 4 && header.sir0_type != 5 {
+                    return Err(RawError::UnsupportedSir0Type(header.sir0_type));
+                };
+                *self = if self.lenient {
+                    Self::from_header(&header).lenient()
+                } else {
+                    Self::from_header(&header)
+                };
+                self.found.push_back(ParseStep::Header(header));
+                ParserState::Sir0Header
+            }
+            ParserState::Sir0Header => {
+                if &bytes[..4] != b"SIR0" {
+                    return Err(RawError::BadSir0Magic);
+                };
+                let content_header = u32_at(4);
+                let content_header_length = u32_at(8).saturating_sub(content_header);
+                if content_header_length < 12 {
+                    return Err(RawError::Sir0HeaderTooShort(content_header_length));
+                };
+                ParserState::ContentHeader {
+                    offset: self.sir0_offset + u64::from(content_header),
+                }
+            }
+            ParserState::ContentHeader { .. } => {
+                let fat5_type = u32_at(8);
+                if fat5_type > 1 {
+                    return Err(RawError::UnsupportedFat5Type(fat5_type));
+                };
+                self.table_offset = self.sir0_offset + u64::from(u32_at(0));
+                self.file_count = u32_at(4);
+                self.named = fat5_type == 0;
+                self.found.push_back(ParseStep::Table {
+                    offset: self.table_offset,
+                    file_count: self.file_count,
+                    named: self.named,
+                });
+                ParserState::Entries
+            }
+            ParserState::Entries => {
+                let count = bytes.len() / 12;
+                for entry in 0..count {
+                    let index = self.read_entries + entry as u32;
+                    let name_offset_or_hash = u32_at(entry * 12);
+                    let data_offset = u32_at(entry * 12 + 4);
+                    let start = match self.data_offset.checked_add(data_offset) {
+                        Some(start) => start,
+                        None if self.lenient => {
+                            self.found.push_back(ParseStep::InvalidEntry {
+                                index,
+                                error: RawError::DataStartOverflow(self.data_offset, data_offset),
+                            });
+                            continue;
+                        }
+                        None => {
+                            return Err(RawError::DataStartOverflow(self.data_offset, data_offset))
+                        }
+                    };
+                    if self.check_alignment && start % 16 != 0 {
+                        return Err(RawError::BadAlignment(start));
+                    };
+                    let length = u32_at(entry * 12 + 8);
+                    if self.named {
+                        self.pending
+                            .push_back((index, name_offset_or_hash, start, length));
+                    } else {
+                        self.found.push_back(ParseStep::Entry(RawEntry {
+                            name: RawName::Hash(name_offset_or_hash),
+                            start,
+                            length,
+                        }));
+                    };
+                }
+                self.read_entries += count as u32;
+                if truncated {
+                    self.found.push_back(ParseStep::TableTruncated {
+                        expected: self.file_count,
+                        read: self.read_entries,
+                    });
+                    self.file_count = self.read_entries;
+                };
+                self.names_state(Vec::new())
+            }
+            ParserState::Names { mut name, .. } => {
+                let mut chunk = bytes;
+                loop {
+                    if chunk.len() < 2 {
+                        if truncated {
+                            // the source ended before the end of the name
+                            if let Some((index, ..)) = self.pending.pop_front() {
+                                self.found.push_back(ParseStep::InvalidEntry {
+                                    index,
+                                    error: RawError::OutOfBounds {
+                                        offset,
+                                        length: length as u64,
+                                    },
+                                });
+                            };
+                            break self.names_state(Vec::new());
+                        };
+                        // the name continue after this chunk
+                        break ParserState::Names {
+                            offset: offset + (bytes.len() - chunk.len()) as u64,
+                            name,
+                        };
+                    };
+                    let chara = u16::from_le_bytes([chunk[0], chunk[1]]);
+                    chunk = &chunk[2..];
+                    if chara != 0 {
+                        name.push(chara);
+                        continue;
+                    };
+                    if let Some((index, _, start, length)) = self.pending.pop_front() {
+                        match String::from_utf16(&name) {
+                            Ok(entry_name) => self.found.push_back(ParseStep::Entry(RawEntry {
+                                name: RawName::Name(entry_name),
+                                start,
+                                length,
+                            })),
+                            Err(_) if self.lenient => {
+                                self.found.push_back(ParseStep::InvalidEntry {
+                                    index,
+                                    error: RawError::InvalidName,
+                                })
+                            }
+                            Err(_) => return Err(RawError::InvalidName),
+                        };
+                    };
+                    name.clear();
+                    // continue with the next name if it is in this chunk, which is usually the case
+                    let next = match self.names_state(name) {
+                        ParserState::Names { offset: next, name } => (next, name),
+                        state => break state,
+                    };
+                    match next
+                        .0
+                        .checked_sub(offset)
+                        .and_then(|next| bytes.get(next as usize..))
+                    {
+                        Some(next_chunk) if !next_chunk.is_empty() => {
+                            chunk = next_chunk;
+                            name = next.1;
+                        }
+                        _ => {
+                            break ParserState::Names {
+                                offset: next.0,
+                                name: next.1,
+                            }
+                        }
+                    }
+                }
+            }
+            ParserState::Done => ParserState::Done,
+        };
+        Ok(())
+    }
+
+    /// Return the state reading the name of the next pending entry, if any
+    fn names_state(&self, name: Vec<u16>) -> ParserState {
+        match self.pending.front() {
+            Some((_, name_offset, _, _)) => ParserState::Names {
+                offset: self.sir0_offset + u64::from(*name_offset),
+                name,
+            },
+            None => ParserState::Entries,
+        }
+    }
+
+    /// Read the bytes needed from ``source`` until something is found, and return it (never a
+    /// [`ParseStep::Read`])
+    pub fn next_from<S: ReadAt + ?Sized>(&mut self, source: &S) -> Result<ParseStep, RawError> {
+        let mut buffer = Vec::new();
+        loop {
+            match self.next_step() {
+                ParseStep::Read { offset, length } => {
+                    // in lenient mode, only the bytes before the end of the source are read
+                    let length = if self.lenient {
+                        let available = source.len()?.saturating_sub(offset);
+                        usize::try_from(available).map_or(length, |available| available.min(length))
+                    } else {
+                        length
+                    };
+                    buffer.resize(length, 0);
+                    // nothing is read after the end of the source in lenient mode
+                    if !buffer.is_empty() {
+                        source.read_exact_at(offset, &mut buffer)?;
+                    };
+                    self.feed(&buffer)?;
+                }
+                step => return Ok(step),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::test_util::{Pathology, SyntheticFarc};
+    use crate::Farc;
+    use std::collections::BTreeSet;
+    use std::io::Cursor;
+
+    fn parse_entries(archive: &[u8]) -> Result<Vec<RawEntry>, RawError> {
+        read_entries(archive, &read_header(archive)?)
+    }
+
+    /// Return the names of the entries, ignoring the hashed ones
+    fn entry_names(entries: &[RawEntry]) -> BTreeSet<String> {
+        entries
+            .iter()
+            .filter_map(|entry| match &entry.name {
+                RawName::Name(name) => Some(name.clone()),
+                RawName::Hash(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn truncated_table() {
+        for named in [false, true] {
+            let archive = SyntheticFarc::new()
+                .with_files(10)
+                .named(named)
+                .pathology(Pathology::TruncatedTable)
+                .build();
+            assert!(matches!(
+                parse_entries(&archive),
+                Err(RawError::OutOfBounds { .. })
+            ));
+            assert!(Farc::new(Cursor::new(archive.clone())).is_err());
+            assert!(Farc::from_storage(archive).is_err());
+        }
+    }
+
+    #[test]
+    fn oversized_file_count() {
+        for named in [false, true] {
+            let archive = SyntheticFarc::new()
+                .with_files(10)
+                .named(named)
+                .pathology(Pathology::OversizedFileCount)
+                .build();
+            let mut parser = FarcParser::new();
+            let file_count = loop {
+                if let ParseStep::Table { file_count, .. } = parser.next_from(&archive).unwrap() {
+                    break file_count;
+                };
+            };
+            assert_eq!(file_count, u32::MAX);
+            // the table is read by chunks, so the first one already goes past the end of the archive
+            assert!(matches!(
+                parser.next_from(&archive),
+                Err(RawError::OutOfBounds { length, .. }) if length == u64::from(TABLE_CHUNK_ENTRIES) * 12
+            ));
+            assert!(matches!(
+                parse_entries(&archive),
+                Err(RawError::OutOfBounds { .. })
+            ));
+            assert!(Farc::new(Cursor::new(archive.clone())).is_err());
+            assert!(Farc::from_storage(archive).is_err());
+        }
+    }
+
+    #[test]
+    fn names_crossing_name_chunks() {
+        // names of many lengths, with characters of 1, 2 and 4 bytes in utf-8 (and 2 or 4 in utf-16), so the chunks
+        // end at every kind of position in a name
+        let mut builder = SyntheticFarc::new().named(true);
+        for index in 0..600 {
+            let filler = if index % 2 == 0 { "n" } else { "é🎵" };
+            let name = format!("{:03}_{}", index, filler.repeat(index % 41));
+            builder = builder.with_file(&name, vec![index as u8; index % 33]);
+        }
+        let archive = builder.build();
+        let expected = builder
+            .names()
+            .flatten()
+            .map(str::to_string)
+            .collect::<BTreeSet<_>>();
+
+        assert_eq!(entry_names(&parse_entries(&archive).unwrap()), expected);
+
+        // only feed the bytes asked for, never more
+        let mut parser = FarcParser::new();
+        let mut names = BTreeSet::new();
+        let mut name_chunks = 0;
+        loop {
+            match parser.next_step() {
+                ParseStep::Read { offset, length } => {
+                    if length as u64 == NAME_CHUNK_LENGTH {
+                        name_chunks += 1;
+                    };
+                    let offset = offset as usize;
+                    parser.feed(&archive[offset..offset + length]).unwrap();
+                }
+                ParseStep::Entry(RawEntry {
+                    name: RawName::Name(name),
+                    ..
+                }) => {
+                    names.insert(name);
+                }
+                ParseStep::Done => break,
+                _ => (),
+            };
+        }
+        assert!(name_chunks > 1);
+        assert_eq!(names, expected);
+
+        let farc = Farc::new(Cursor::new(archive.clone())).unwrap();
+        let storage_farc = Farc::from_storage(archive).unwrap();
+        for (hash, content) in builder.contents() {
+            assert!(farc.read_hashed_file(hash).unwrap().starts_with(content));
+            assert!(storage_farc
+                .read_hashed_file(hash)
+                .unwrap()
+                .starts_with(content));
+        }
+        for name in &expected {
+            assert!(farc.get_named_file(name).is_ok());
+        }
+    }
+
+    #[test]
+    fn more_entries_than_a_table_chunk() {
+        let file_count = TABLE_CHUNK_ENTRIES as usize + 3;
+        for named in [false, true] {
+            let builder = SyntheticFarc::new().with_files(file_count).named(named);
+            let archive = builder.build();
+
+            let entries = parse_entries(&archive).unwrap();
+            assert_eq!(entries.len(), file_count);
+            assert_eq!(
+                entries
+                    .iter()
+                    .map(|entry| entry.name.hash())
+                    .collect::<BTreeSet<_>>(),
+                builder.contents().map(|(hash, _)| hash).collect()
+            );
+            if named {
+                assert_eq!(entry_names(&entries).len(), file_count);
+            };
+
+            let farc = Farc::new(Cursor::new(archive.clone())).unwrap();
+            let storage_farc = Farc::from_storage(archive).unwrap();
+            assert_eq!(farc.file_count(), file_count);
+            assert_eq!(storage_farc.file_count(), file_count);
+            // the last files are only in the second chunk of the table
+            for (hash, content) in builder.contents().skip(file_count - 5) {
+                assert!(farc.read_hashed_file(hash).unwrap().starts_with(content));
+                assert!(storage_farc
+                    .read_hashed_file(hash)
+                    .unwrap()
+                    .starts_with(content));
+            }
+        }
+    }
+
+    /// Parse the archive with a lenient parser, and return every step
+    fn lenient_steps(archive: &[u8]) -> Vec<ParseStep> {
+        let mut parser = FarcParser::new().lenient();
+        let mut steps = Vec::new();
+        loop {
+            match parser.next_from(archive).unwrap() {
+                ParseStep::Done => return steps,
+                step => steps.push(step),
+            };
+        }
+    }
+
+    #[test]
+    fn lenient() {
+        let files = SyntheticFarc::new().with_files(3);
+        let expected = parse_entries(&files.build()).unwrap();
+
+        // a misaligned file is only an error for the strict parser
+        let archive = files.clone().pathology(Pathology::MisalignedFile).build();
+        assert!(matches!(
+            parse_entries(&archive),
+            Err(RawError::BadAlignment(_))
+        ));
+        let entries = lenient_steps(&archive)
+            .into_iter()
+            .filter_map(|step| match step {
+                ParseStep::Entry(entry) => Some(entry),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].start, expected[0].start + 4);
+        assert_eq!(entries[1..], expected[1..]);
+
+        // an overflowing entry is skipped
+        let archive = files.pathology(Pathology::DataStartOverflow).build();
+        let steps = lenient_steps(&archive);
+        assert!(matches!(
+            steps.as_slice(),
+            [
+                ParseStep::Header(_),
+                ParseStep::Table { .. },
+                ParseStep::InvalidEntry {
+                    index: 0,
+                    error: RawError::DataStartOverflow(..)
+                },
+                ParseStep::Entry(_),
+                ParseStep::Entry(_)
+            ]
+        ));
+    }
+
+    #[test]
+    fn lenient_truncated_table() {
+        for named in [false, true] {
+            let files = SyntheticFarc::new().with_files(10).named(named);
+            let expected = parse_entries(&files.build()).unwrap();
+            let archive = files.pathology(Pathology::OversizedFileCount).build();
+            let steps = lenient_steps(&archive);
+            // the real entries are found first, followed by the bytes after the table read as entries
+            let entries = steps
+                .iter()
+                .filter_map(|step| match step {
+                    ParseStep::Entry(entry) => Some(entry.clone()),
+                    _ => None,
+                })
+                .take(10)
+                .collect::<Vec<_>>();
+            assert_eq!(entries, expected);
+            let table = steps
+                .iter()
+                .find_map(|step| match step {
+                    ParseStep::Table { offset, .. } => Some(*offset),
+                    _ => None,
+                })
+                .unwrap();
+            let read = ((archive.len() as u64 - table) / 12) as u32;
+            assert!(steps.contains(&ParseStep::TableTruncated {
+                expected: u32::MAX,
+                read
+            }));
+        }
+    }
+}
@@ -0,0 +1,358 @@
+use crate::raw::{FarcParser, ParseStep, RawError, RawName, ReadAt};
+use crate::{FarcWriter, FarcWriterError};
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::io::{Seek, Write};
+use thiserror::Error;
+
+/// An error that prevent an archive from being repaired
+#[derive(Debug, Error)]
+pub enum RepairError {
+    /// The header or the file table is too damaged to find any file
+    #[error("the archive is too damaged to be repaired")]
+    Unrecoverable(#[from] RawError),
+}
+
+/// Something [`repair_farc`] fixed, or couldn't fix
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RepairAction {
+    /// The sir0 section ends after the end of the file. Only its available part was read.
+    #[error("the file table ends at 0x{end:x}, after the end of the file at 0x{file_length:x}")]
+    Sir0Truncated {
+        /// The offset of the end of the sir0 section, according to the header
+        end: u64,
+        /// The length of the file
+        file_length: u64,
+    },
+    /// The file table is truncated. The files after the last readable entry are lost.
+    #[error("only {read} of the {expected} entries of the file table can be read")]
+    TableTruncated {
+        /// The number of files according to the file table
+        expected: u32,
+        /// The number of entries that could be read
+        read: u32,
+    },
+    /// An entry of the file table is invalid, and its file is lost
+    #[error("the entry {index} of the file table is invalid: {reason}")]
+    EntryUnreadable {
+        /// The position of the entry in the file table
+        index: u32,
+        /// Why it is invalid
+        reason: RawError,
+    },
+    /// The file starts after the end of the archive, and is lost
+    #[error("the file {hash:08x} starts at 0x{start:x}, after the end of the archive")]
+    FileMissing {
+        /// The hash of the name of the file
+        hash: u32,
+        /// The offset of the start of the file
+        start: u32,
+    },
+    /// The file ends after the end of the archive. Only its available part was kept.
+    #[error(
+        "the file {hash:08x} is truncated: only {recovered} of its {length} bytes were recovered"
+    )]
+    FileTruncated {
+        /// The hash of the name of the file
+        hash: u32,
+        /// The length of the file, according to the file table
+        length: u32,
+        /// The number of bytes recovered
+        recovered: u32,
+    },
+    /// The file doesn't start at a multiple of 16. It is aligned in the repaired archive.
+    #[error("the file {hash:08x} starts at 0x{start:x}, which isn't aligned")]
+    Realigned {
+        /// The hash of the name of the file
+        hash: u32,
+        /// The offset of the start of the file
+        start: u32,
+    },
+    /// Another entry of the file table has the same hash. Only the first one is kept.
+    #[error("the file {hash:08x} is listed several times, only the first one is kept")]
+    DuplicateEntry {
+        /// The hash of the name of the file
+        hash: u32,
+    },
+}
+
+/// What [`repair_farc`] did
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RepairReport {
+    /// Every problem found, in the order they were found
+    pub actions: Vec<RepairAction>,
+    /// The number of files in the repaired archive
+    pub recovered: usize,
+}
+
+impl RepairReport {
+    /// Return true if no content was lost (files can still have been realigned, or duplicated entries removed)
+    #[must_use]
+    pub fn is_lossless(&self) -> bool {
+        self.actions.iter().all(|action| {
+            matches!(
+                action,
+                RepairAction::Sir0Truncated { .. }
+                    | RepairAction::Realigned { .. }
+                    | RepairAction::DuplicateEntry { .. }
+            )
+        })
+    }
+}
+
+/// An archive reconstructed by [`repair_farc`]
+#[derive(Debug)]
+pub struct RepairedFarc {
+    /// The files that could be recovered
+    pub writer: FarcWriter,
+    /// Whether the damaged archive stored the file names
+    pub named: bool,
+    /// What was fixed or lost
+    pub report: RepairReport,
+}
+
+impl RepairedFarc {
+    /// Write the repaired archive, in the same form (named or hashed) as the damaged one
+    pub fn write<T: Write + Seek>(&self, file: &mut T) -> Result<(), FarcWriterError> {
+        if self.named {
+            self.writer.write_named(file)
+        } else {
+            self.writer.write_hashed(file)
+        }
+    }
+}
+
+/// Recover as much as possible of a damaged archive, like one truncated by an interrupted write, or with files
+/// whose length go past the end of the archive. The files are then written again, with valid padding and lengths.
+///
+/// Only an archive whose header or file table header can't be read at all is unrecoverable.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, repair_farc, Farc, FarcWriter, RepairAction};
+/// use std::io::Cursor;
+/// let mut writer = FarcWriter::default();
+/// writer.add_hashed_file(hash_name("a.bin"), vec![1; 32]);
+/// writer.add_hashed_file(hash_name("b.bin"), vec![2; 32]);
+/// let mut archive = Cursor::new(Vec::new());
+/// writer.write_hashed(&mut archive).unwrap();
+/// let mut archive = archive.into_inner();
+/// // an interrupted write lost the end of the archive
+/// archive.truncate(archive.len() - 40);
+///
+/// let repaired = repair_farc(&archive).unwrap();
+/// assert_eq!(repaired.report.recovered, 2);
+/// assert!(!repaired.report.is_lossless());
+/// assert!(matches!(repaired.report.actions[0], RepairAction::FileTruncated { recovered: 8, .. }));
+///
+/// let mut fixed = Cursor::new(Vec::new());
+/// repaired.write(&mut fixed).unwrap();
+/// assert_eq!(Farc::new(Cursor::new(fixed.into_inner())).unwrap().file_count(), 2);
+/// ```
+pub fn repair_farc<S: ReadAt + ?Sized>(source: &S) -> Result<RepairedFarc, RepairError> {
+    let file_length = source.len()?;
+    let mut report = RepairReport::default();
+    let mut writer = FarcWriter::default();
+    let mut named = false;
+    let mut hashes = HashSet::new();
+    let mut parser = FarcParser::new().lenient();
+    loop {
+        match parser.next_from(source)? {
+            ParseStep::Header(header) => {
+                let sir0_end = u64::from(header.sir0_offset) + u64::from(header.sir0_length);
+                if sir0_end > file_length {
+                    report.actions.push(RepairAction::Sir0Truncated {
+                        end: sir0_end,
+                        file_length,
+                    });
+                };
+            }
+            ParseStep::Table {
+                named: table_named, ..
+            } => named = table_named,
+            ParseStep::InvalidEntry { index, error } => {
+                report.actions.push(RepairAction::EntryUnreadable {
+                    index,
+                    reason: error,
+                })
+            }
+            ParseStep::TableTruncated { expected, read } => report
+                .actions
+                .push(RepairAction::TableTruncated { expected, read }),
+            ParseStep::Entry(entry) => {
+                let hash = entry.name.hash();
+                let (start, length) = (entry.start, entry.length);
+                if !hashes.insert(hash) {
+                    report.actions.push(RepairAction::DuplicateEntry { hash });
+                    continue;
+                };
+                if u64::from(start) >= file_length && length > 0 {
+                    report
+                        .actions
+                        .push(RepairAction::FileMissing { hash, start });
+                    continue;
+                };
+                if !start.is_multiple_of(16) {
+                    report.actions.push(RepairAction::Realigned { hash, start });
+                };
+                let available =
+                    (file_length - u64::from(start).min(file_length)).min(u64::from(length));
+                // available is at most length, a u32
+                let recovered = u32::try_from(available).unwrap_or(length);
+                if recovered < length {
+                    report.actions.push(RepairAction::FileTruncated {
+                        hash,
+                        length,
+                        recovered,
+                    });
+                };
+                let mut content = vec![0; recovered as usize];
+                source.read_exact_at(u64::from(start), &mut content)?;
+                match entry.name {
+                    RawName::Name(name) => writer.add_named_file(name, content),
+                    RawName::Hash(hash) => writer.add_hashed_file(hash, content),
+                };
+            }
+            ParseStep::Read { .. } => (),
+            ParseStep::Done => break,
+        };
+    }
+
+    report.recovered = writer.file_count();
+    Ok(RepairedFarc {
+        writer,
+        named,
+        report,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raw::read_header;
+    use crate::test_util::{Pathology, SyntheticFarc};
+    use crate::Farc;
+    use std::io::Cursor;
+
+    fn repair(builder: &SyntheticFarc) -> Result<RepairedFarc, RepairError> {
+        repair_farc(builder.build().as_slice())
+    }
+
+    /// Write the repaired archive, and check it can be parsed
+    fn parse_repaired(repaired: &RepairedFarc) -> Farc<Cursor<Vec<u8>>> {
+        let mut fixed = Cursor::new(Vec::new());
+        repaired.write(&mut fixed).unwrap();
+        Farc::new(Cursor::new(fixed.into_inner())).unwrap()
+    }
+
+    #[test]
+    fn unrecoverable_archives() {
+        let files = SyntheticFarc::new().with_files(3);
+        assert!(matches!(
+            repair(&files.clone().pathology(Pathology::BadMagic)),
+            Err(RepairError::Unrecoverable(RawError::BadMagic))
+        ));
+        assert!(matches!(
+            repair(&files.pathology(Pathology::UnsupportedFat5Type)),
+            Err(RepairError::Unrecoverable(RawError::UnsupportedFat5Type(2)))
+        ));
+        assert!(matches!(
+            repair_farc(&b"FARC"[..]),
+            Err(RepairError::Unrecoverable(RawError::OutOfBounds { .. }))
+        ));
+    }
+
+    #[test]
+    fn truncated_table() {
+        // the content header of the sir0 section is after the file table, so nothing can be found
+        assert!(matches!(
+            repair(
+                &SyntheticFarc::new()
+                    .with_files(10)
+                    .pathology(Pathology::TruncatedTable)
+            ),
+            Err(RepairError::Unrecoverable(RawError::OutOfBounds { .. }))
+        ));
+    }
+
+    #[test]
+    fn oversized_file_count() {
+        let repaired = repair(
+            &SyntheticFarc::new()
+                .with_files(10)
+                .pathology(Pathology::OversizedFileCount),
+        )
+        .unwrap();
+        // the entries after the real ones are read until the end of the archive
+        assert!(repaired.report.actions.iter().any(|action| matches!(
+            action,
+            RepairAction::TableTruncated {
+                expected: u32::MAX,
+                ..
+            }
+        )));
+        parse_repaired(&repaired);
+    }
+
+    #[test]
+    fn lossless_repairs() {
+        let files = SyntheticFarc::new().with_files(4).named(true);
+
+        let repaired = repair(&files.clone().pathology(Pathology::DuplicateEntry)).unwrap();
+        assert!(repaired.report.is_lossless());
+        assert!(matches!(
+            repaired.report.actions.as_slice(),
+            [RepairAction::DuplicateEntry { .. }]
+        ));
+        assert_eq!(repaired.report.recovered, 3);
+
+        let repaired = repair(&files.pathology(Pathology::MisalignedFile)).unwrap();
+        assert!(repaired.report.is_lossless());
+        assert!(matches!(
+            repaired.report.actions.as_slice(),
+            [RepairAction::Realigned { start, .. }] if start % 16 == 4
+        ));
+        assert_eq!(parse_repaired(&repaired).file_count(), 4);
+    }
+
+    #[test]
+    fn lost_files() {
+        let repaired = repair(
+            &SyntheticFarc::new()
+                .with_files(3)
+                .pathology(Pathology::DataStartOverflow),
+        )
+        .unwrap();
+        assert!(!repaired.report.is_lossless());
+        assert!(matches!(
+            repaired.report.actions.as_slice(),
+            [RepairAction::EntryUnreadable {
+                index: 0,
+                reason: RawError::DataStartOverflow(..)
+            }]
+        ));
+        assert_eq!(repaired.report.recovered, 2);
+
+        // the first file is cut, and the second one is lost
+        let archive = SyntheticFarc::new()
+            .with_file("a.bin", vec![1; 64])
+            .with_file("b.bin", vec![2; 64])
+            .build();
+        let header = read_header(archive.as_slice()).unwrap();
+        let repaired = repair_farc(&archive[..header.data_offset as usize + 72]).unwrap();
+        let report = &repaired.report;
+        assert!(!report.is_lossless());
+        assert!(report
+            .actions
+            .iter()
+            .any(|action| matches!(action, RepairAction::FileTruncated { recovered: 72, .. })));
+        assert!(report
+            .actions
+            .iter()
+            .any(|action| matches!(action, RepairAction::FileMissing { .. })));
+        assert_eq!(report.recovered, 1);
+        assert_eq!(parse_repaired(&repaired).file_count(), 1);
+    }
+}
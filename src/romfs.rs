@@ -0,0 +1,223 @@
+use crate::{
+    message_dehash, script_dehash, DehashError, DehashPipeline, Farc, FarcError, FileHashType,
+};
+use io_partition::PartitionMutex;
+use std::collections::BTreeMap;
+use std::fs::{read_dir, File};
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// An error that can happen while opening a [`RomFs`] or a file inside it
+#[derive(Debug, Error)]
+pub enum RomFsError {
+    /// An error happened while reading a directory or a file of the romfs
+    #[error("can't read {0:?}")]
+    IOError(PathBuf, #[source] io::Error),
+    /// An archive can't be parsed
+    #[error("can't parse the archive {0:?}")]
+    FarcError(PathBuf, #[source] FarcError),
+    /// An error happened while finding the names of the files of an archive
+    #[error("can't find the file names of the archive {0:?}")]
+    DehashError(PathBuf, #[source] DehashError),
+    /// No archive contain this path
+    #[error("no archive contain the path {0:?}")]
+    ArchiveNotFound(String),
+    /// The archive doesn't contain this file
+    #[error("the archive {archive:?} doesn't contain the file {file:?}")]
+    FileNotFound {
+        /// The path of the archive, relative to the root of the romfs
+        archive: String,
+        /// The name of the file inside the archive
+        file: String,
+    },
+}
+
+/// All the [`Farc`] archives of a dumped romfs (of Pokémon Super Mystery Dungeon, or another game using the same
+/// format), with the names of their files found like the ``pmd_farc`` command line tool does:
+/// - for message archives, with the adjacent ``.lst`` file (see [`message_dehash`])
+/// - for script archives, with the default [`script_dehash::ScriptNameRule`]
+/// - with the ``bgrs`` or ``bch`` feature, from the content of the other files of the archive
+///
+/// Archives are found by their magic in every sub-directory, and are identified by their path relative to the root,
+/// with ``/`` as separator. Files inside them are opened with a path like ``"message_us.bin/script_001.bin"``, where
+/// the name may be replaced by ``0x{hash}.bin`` (with the hash written as 8 lowercase hexadecimal digits).
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, FarcWriter, RomFs};
+/// use std::io::Read;
+/// let root = std::env::temp_dir().join(format!("pmd_farc_romfs_doctest_{}", std::process::id()));
+/// std::fs::create_dir_all(root.join("message")).unwrap();
+/// let mut writer = FarcWriter::default();
+/// writer.add_hashed_file(hash_name("script_001.bin"), b"hello".to_vec());
+/// writer.write_hashed(&mut std::fs::File::create(root.join("message/message_us.bin")).unwrap()).unwrap();
+/// std::fs::write(root.join("message/message_us.lst"), "message/script_001.bin\n").unwrap();
+///
+/// let romfs = RomFs::new(&root).unwrap();
+/// assert_eq!(romfs.archive_paths().collect::<Vec<_>>(), vec!["message/message_us.bin"]);
+/// let mut content = Vec::new();
+/// romfs.open("message/message_us.bin/script_001.bin").unwrap().read_to_end(&mut content).unwrap();
+/// assert!(content.starts_with(b"hello"));
+/// let placeholder = format!("message/message_us.bin/0x{:08x}.bin", hash_name("script_001.bin"));
+/// assert!(romfs.open(&placeholder).is_ok());
+/// # std::fs::remove_dir_all(&root).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct RomFs {
+    root: PathBuf,
+    archives: BTreeMap<String, Farc<BufReader<File>>>,
+}
+
+impl RomFs {
+    /// Find and open every archive under ``root``, and find the names of their files
+    pub fn new<P: AsRef<Path>>(root: P) -> Result<Self, RomFsError> {
+        let mut romfs = Self {
+            root: root.as_ref().to_path_buf(),
+            archives: BTreeMap::new(),
+        };
+        let root = romfs.root.clone();
+        romfs.add_directory(&root, "")?;
+        Ok(romfs)
+    }
+
+    fn add_directory(&mut self, directory: &Path, prefix: &str) -> Result<(), RomFsError> {
+        let io_error = |error| RomFsError::IOError(directory.to_path_buf(), error);
+        for entry in read_dir(directory).map_err(io_error)? {
+            let entry = entry.map_err(io_error)?;
+            let path = entry.path();
+            let file_name = match entry.file_name().into_string() {
+                Ok(file_name) => file_name,
+                Err(_) => {
+                    warn!("ignoring {:?}, as its name isn't valid unicode", path);
+                    continue;
+                }
+            };
+            let relative_path = format!("{}{}", prefix, file_name);
+            if entry.file_type().map_err(io_error)?.is_dir() {
+                self.add_directory(&path, &format!("{}/", relative_path))?;
+            } else if is_farc(&path).map_err(|err| RomFsError::IOError(path.clone(), err))? {
+                let farc = open_archive(&path, &file_name)?;
+                self.archives.insert(relative_path, farc);
+            };
+        }
+        Ok(())
+    }
+
+    /// Return the root directory of the romfs
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Iterate over the path of every archive, relative to the root, in alphabetical order
+    pub fn archive_paths(&self) -> impl Iterator<Item = &str> {
+        self.archives.keys().map(String::as_str)
+    }
+
+    /// Iterate over every archive, with its path relative to the root, in alphabetical order
+    pub fn archives(&self) -> impl Iterator<Item = (&str, &Farc<BufReader<File>>)> {
+        self.archives
+            .iter()
+            .map(|(path, farc)| (path.as_str(), farc))
+    }
+
+    /// Return the archive with this path, relative to the root
+    #[must_use]
+    pub fn archive(&self, path: &str) -> Option<&Farc<BufReader<File>>> {
+        self.archives.get(path)
+    }
+
+    /// Return the archive with this path, relative to the root, for example to check more file names
+    pub fn archive_mut(&mut self, path: &str) -> Option<&mut Farc<BufReader<File>>> {
+        self.archives.get_mut(path)
+    }
+
+    /// Split a path like ``"message_us.bin/script_001.bin"`` in the path of the archive and the name of the file
+    fn split_path<'a>(&self, path: &'a str) -> Option<(&'a str, &'a str)> {
+        let mut separators = path.match_indices('/').map(|(index, _)| index);
+        separators.find_map(|index| {
+            let (archive, file) = (&path[..index], &path[index + 1..]);
+            if self.archives.contains_key(archive) {
+                Some((archive, file))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Open a file inside an archive, with a path like ``"message_us.bin/script_001.bin"``
+    pub fn open(&self, path: &str) -> Result<PartitionMutex<BufReader<File>>, RomFsError> {
+        let (archive_path, file_name) = self
+            .split_path(path)
+            .ok_or_else(|| RomFsError::ArchiveNotFound(path.to_string()))?;
+        let farc = &self.archives[archive_path];
+        let not_found = || RomFsError::FileNotFound {
+            archive: archive_path.to_string(),
+            file: file_name.to_string(),
+        };
+        let hash = match farc.index.get_file_by_name(file_name) {
+            Some(file) => file.name_hash,
+            None => parse_placeholder_name(file_name).ok_or_else(not_found)?,
+        };
+        farc.get_hashed_file(hash).map_err(|_| not_found())
+    }
+}
+
+/// Return true if the file start with the magic of a [`Farc`]
+pub(crate) fn is_farc(path: &Path) -> Result<bool, io::Error> {
+    let mut magic = [0; 4];
+    let mut file = File::open(path)?;
+    match file.read_exact(&mut magic) {
+        Ok(()) => Ok(&magic == b"FARC"),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// Open an archive, and find the name of its files
+pub(crate) fn open_archive(
+    path: &Path,
+    file_name: &str,
+) -> Result<Farc<BufReader<File>>, RomFsError> {
+    let io_error = |path: &Path, error| RomFsError::IOError(path.to_path_buf(), error);
+    let file = File::open(path).map_err(|err| io_error(path, err))?;
+    let mut farc = Farc::new(BufReader::new(file))
+        .map_err(|err| RomFsError::FarcError(path.to_path_buf(), err))?;
+
+    let mut pipeline = DehashPipeline::default();
+    match FileHashType::predict_from_file_name(file_name) {
+        Some(FileHashType::Message) => {
+            if let Some(list_name) = message_dehash::get_file_name(file_name) {
+                let list_path = path.with_file_name(list_name);
+                if list_path.is_file() {
+                    let mut list_file =
+                        File::open(&list_path).map_err(|err| io_error(&list_path, err))?;
+                    message_dehash::try_possible_name(&mut farc, &mut list_file)
+                        .map_err(|err| io_error(&list_path, err))?;
+                };
+            };
+        }
+        Some(FileHashType::Script) => {
+            pipeline.add_dehasher(Box::new(script_dehash::ScriptNameRule::default()));
+        }
+        _ => (),
+    };
+    #[cfg(any(feature = "bgrs", feature = "bch"))]
+    pipeline.add_dehasher(Box::new(crate::find_name::ContentDehasher));
+    if !pipeline.is_empty() {
+        pipeline
+            .run(&mut farc)
+            .map_err(|err| RomFsError::DehashError(path.to_path_buf(), err))?;
+    };
+    Ok(farc)
+}
+
+/// Return the hash written in a name like ``0x1234abcd.bin``
+fn parse_placeholder_name(file_name: &str) -> Option<u32> {
+    let hex = file_name.strip_prefix("0x")?.strip_suffix(".bin")?;
+    if hex.len() != 8 {
+        return None;
+    };
+    u32::from_str_radix(hex, 16).ok()
+}
@@ -0,0 +1,103 @@
+use crate::{hash_name, ArchiveRead, RomFs};
+use std::collections::BTreeSet;
+
+/// A file found by [`search_hash`] in one of the archives
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashLocation<'a> {
+    /// The label of the archive, as given to [`search_hash`]
+    pub archive: &'a str,
+    /// The name of the file in this archive, if known
+    pub name: Option<&'a str>,
+    /// The length of the file
+    pub length: u64,
+}
+
+/// The archives containing a file, returned by [`search_hash`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashSearch<'a> {
+    /// The searched hash
+    pub hash: u32,
+    /// The archives containing a file with this hash, in the order they were given
+    pub locations: Vec<HashLocation<'a>>,
+}
+
+impl<'a> HashSearch<'a> {
+    /// Return the different names the file is known as
+    #[must_use]
+    pub fn names(&self) -> BTreeSet<&'a str> {
+        self.locations
+            .iter()
+            .filter_map(|location| location.name)
+            .collect()
+    }
+
+    /// Return true if the file has different names in different archives (which mean at least one is wrong, as
+    /// they have the same hash)
+    #[must_use]
+    pub fn names_differ(&self) -> bool {
+        self.names().len() > 1
+    }
+}
+
+/// Find which archives contain a file with this hash, with the name it has in each of them. ``archives`` are the
+/// archives with a label, like their path.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, search_hash, Farc, FarcWriter};
+/// use std::io::Cursor;
+/// let mut archives = Vec::new();
+/// for files in &[&["a.bin", "b.bin"][..], &["b.bin"], &["c.bin"]] {
+///     let mut writer = FarcWriter::default();
+///     for file in files.iter() {
+///         writer.add_hashed_file(hash_name(file), Vec::new());
+///     }
+///     let mut archive = Cursor::new(Vec::new());
+///     writer.write_hashed(&mut archive).unwrap();
+///     archives.push(Farc::new(Cursor::new(archive.into_inner())).unwrap());
+/// }
+/// archives[0].check_file_name("b.bin");
+///
+/// let labeled = vec![("message_us.bin", &archives[0]), ("message_fr.bin", &archives[1]), ("other.bin", &archives[2])];
+/// let search = search_hash(labeled, hash_name("b.bin"));
+/// assert_eq!(search.locations.len(), 2);
+/// assert_eq!(search.locations[0].name, Some("b.bin"));
+/// assert_eq!(search.locations[1].archive, "message_fr.bin");
+/// assert!(!search.names_differ());
+/// ```
+pub fn search_hash<'a, A, I>(archives: I, hash: u32) -> HashSearch<'a>
+where
+    A: ArchiveRead + 'a,
+    I: IntoIterator<Item = (&'a str, &'a A)>,
+{
+    let locations = archives
+        .into_iter()
+        .filter_map(|(archive, farc)| {
+            farc.entries()
+                .find(|entry| entry.hash == hash)
+                .map(|entry| HashLocation {
+                    archive,
+                    name: entry.name,
+                    length: entry.length,
+                })
+        })
+        .collect();
+    HashSearch { hash, locations }
+}
+
+/// Find which archives contain a file with this name, like [`search_hash`] with the hash of the name
+pub fn search_name<'a, A, I>(archives: I, name: &str) -> HashSearch<'a>
+where
+    A: ArchiveRead + 'a,
+    I: IntoIterator<Item = (&'a str, &'a A)>,
+{
+    search_hash(archives, hash_name(name))
+}
+
+impl RomFs {
+    /// Find which archives of the romfs contain a file with this hash (see [`search_hash`])
+    #[must_use]
+    pub fn search_hash(&self, hash: u32) -> HashSearch<'_> {
+        search_hash(self.archives(), hash)
+    }
+}
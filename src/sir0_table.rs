@@ -0,0 +1,225 @@
+use byteorder::{ReadBytesExt, WriteBytesExt, LE};
+use pmd_sir0::{write_sir0_footer, write_sir0_header, Sir0, Sir0Error, Sir0WriteFooterError};
+use std::convert::TryInto;
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+use std::num::TryFromIntError;
+use std::string::FromUtf16Error;
+use thiserror::Error;
+
+/// An error that can happen while reading or writing a sir0 section with [`Sir0TableReader`] or [`Sir0TableWriter`]
+#[derive(Debug, Error)]
+pub enum Sir0TableError {
+    /// An [`io::Error`] occured
+    #[error("input/output error")]
+    IOError(#[from] io::Error),
+    /// The sir0 header or footer can't be read
+    #[error("the sir0 section can't be read")]
+    Sir0Error(#[from] Sir0Error),
+    /// The sir0 footer can't be written
+    #[error("the sir0 footer can't be written")]
+    Sir0WriteFooterError(#[from] Sir0WriteFooterError),
+    /// A string isn't valid utf-16
+    #[error("a string isn't valid utf-16")]
+    FromUtf16Error(#[from] FromUtf16Error),
+    /// The section would be bigger than 4GiB
+    #[error("the sir0 section would be bigger than 4GiB")]
+    TooBig(#[from] TryFromIntError),
+}
+
+/// A reader for a sir0 section, the container used by PMD files (like the file table of a [`Farc`](crate::Farc))
+/// to store tables with pointers. The offsets are relative to the start of the section.
+#[derive(Debug)]
+pub struct Sir0TableReader<F: Read + Seek> {
+    sir0: Sir0<F>,
+}
+
+impl<F: Read + Seek> Sir0TableReader<F> {
+    /// Parse the header and the pointer list of the sir0 section contained in ``file``
+    pub fn new(file: F) -> Result<Self, Sir0TableError> {
+        Ok(Self {
+            sir0: Sir0::new(file)?,
+        })
+    }
+
+    /// Return the content header, pointed by the sir0 header. Its layout depends on the format.
+    #[must_use]
+    pub fn content_header(&self) -> &[u8] {
+        self.sir0.get_header()
+    }
+
+    /// Return the ``index``-th little-endian ``u32`` of the content header
+    #[must_use]
+    pub fn content_header_u32(&self, index: usize) -> Option<u32> {
+        let bytes = self.content_header().get(index * 4..index * 4 + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().ok()?))
+    }
+
+    /// Return the length of the section
+    pub(crate) fn len(&mut self) -> Result<u64, Sir0TableError> {
+        Ok(self.sir0.get_file().seek(SeekFrom::End(0))?)
+    }
+
+    /// Fill ``buffer`` with the bytes starting at ``offset``
+    pub(crate) fn read_exact_at(
+        &mut self,
+        offset: u64,
+        buffer: &mut [u8],
+    ) -> Result<(), Sir0TableError> {
+        let file = self.sir0.get_file();
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(buffer)?;
+        Ok(())
+    }
+
+    /// Read a little-endian ``u32`` at ``offset``
+    pub fn read_u32_at(&mut self, offset: u64) -> Result<u32, Sir0TableError> {
+        let file = self.sir0.get_file();
+        file.seek(SeekFrom::Start(offset))?;
+        Ok(file.read_u32::<LE>()?)
+    }
+
+    /// Read a null-terminated utf-16 string at ``offset``. It is read by chunks, and not character by character.
+    pub fn read_utf16_at(&mut self, offset: u64) -> Result<String, Sir0TableError> {
+        let file = self.sir0.get_file();
+        file.seek(SeekFrom::Start(offset))?;
+        let mut bytes = Vec::new();
+        let mut chunk = [0; 64];
+        loop {
+            // the first character not yet checked
+            let checked = bytes.len() - bytes.len() % 2;
+            let read = file.read(&mut chunk)?;
+            if read == 0 {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            };
+            bytes.extend_from_slice(&chunk[..read]);
+            if let Some(end) = bytes[checked..]
+                .chunks_exact(2)
+                .position(|chara| chara == [0, 0])
+            {
+                let buffer = bytes[..checked + end * 2]
+                    .chunks_exact(2)
+                    .map(|chara| u16::from_le_bytes([chara[0], chara[1]]))
+                    .collect::<Vec<u16>>();
+                return Ok(String::from_utf16(&buffer)?);
+            };
+        }
+    }
+}
+
+/// A writer for a sir0 section. It keeps track of the pointers written, to create the pointer list of the footer.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{Sir0TableReader, Sir0TableWriter};
+/// use std::io::Cursor;
+/// let mut writer = Sir0TableWriter::default();
+/// let table = writer.position().unwrap();
+/// writer.write_u32(42).unwrap();
+/// let name_pointer = writer.write_pointer(0).unwrap();
+/// let name = writer.position().unwrap();
+/// writer.write_utf16("hello").unwrap();
+/// writer.set_pointer(name_pointer, name).unwrap();
+/// let section = writer.finish(table, &[1]).unwrap();
+///
+/// let mut reader = Sir0TableReader::new(Cursor::new(section)).unwrap();
+/// assert_eq!(reader.content_header_u32(0), Some(table));
+/// assert_eq!(reader.content_header_u32(1), Some(1));
+/// let table = u64::from(table);
+/// assert_eq!(reader.read_u32_at(table).unwrap(), 42);
+/// let name = reader.read_u32_at(table + 4).unwrap();
+/// assert_eq!(reader.read_utf16_at(name.into()).unwrap(), "hello");
+/// ```
+#[derive(Debug)]
+pub struct Sir0TableWriter {
+    content: Cursor<Vec<u8>>,
+    pointers: Vec<u32>,
+}
+
+impl Default for Sir0TableWriter {
+    fn default() -> Self {
+        let mut content = Vec::new();
+        content.extend_from_slice(&[0; 12]); // reserve sir0 header space
+        content.extend_from_slice(&[0; 4]); // 0x10 padding
+        let mut content = Cursor::new(content);
+        content.set_position(0x10);
+        Self {
+            content,
+            // the pointers of the sir0 header
+            pointers: vec![4, 8],
+        }
+    }
+}
+
+impl Sir0TableWriter {
+    /// Return the current position, where the next value will be written
+    pub fn position(&self) -> Result<u32, Sir0TableError> {
+        Ok(self.content.position().try_into()?)
+    }
+
+    /// Write a little-endian ``u32``
+    pub fn write_u32(&mut self, value: u32) -> Result<(), Sir0TableError> {
+        self.content.write_u32::<LE>(value)?;
+        Ok(())
+    }
+
+    /// Write a pointer to ``target``, a position in this section, and return the position of the pointer (for
+    /// [`Sir0TableWriter::set_pointer`])
+    pub fn write_pointer(&mut self, target: u32) -> Result<u32, Sir0TableError> {
+        let position = self.position()?;
+        self.pointers.push(position);
+        self.write_u32(target)?;
+        Ok(position)
+    }
+
+    /// Change the target of the pointer written at ``position``, for example once the pointed value is written
+    pub fn set_pointer(&mut self, position: u32, target: u32) -> Result<(), Sir0TableError> {
+        let end = self.content.position();
+        self.content.set_position(u64::from(position));
+        self.write_u32(target)?;
+        self.content.set_position(end);
+        Ok(())
+    }
+
+    /// Write bytes
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Sir0TableError> {
+        self.content.write_all(bytes)?;
+        Ok(())
+    }
+
+    /// Write a null-terminated utf-16 string
+    pub fn write_utf16(&mut self, string: &str) -> Result<(), Sir0TableError> {
+        for chara in string.encode_utf16() {
+            self.write_bytes(&chara.to_le_bytes())?;
+        }
+        self.write_bytes(&[0, 0])
+    }
+
+    /// Write zeros until the position is a multiple of 16
+    pub fn pad(&mut self) -> Result<(), Sir0TableError> {
+        let position = self.content.position();
+        if !position.is_multiple_of(16) {
+            self.write_bytes(&vec![0; 16 - position as usize % 16])?;
+        };
+        Ok(())
+    }
+
+    /// Write the content header, made of a pointer to ``data`` followed by ``fields``, then the footer, and return
+    /// the whole section
+    pub fn finish(mut self, data: u32, fields: &[u32]) -> Result<Vec<u8>, Sir0TableError> {
+        self.pad()?;
+        let content_header = self.position()?;
+        self.write_pointer(data)?;
+        for field in fields {
+            self.write_u32(*field)?;
+        }
+        self.pad()?;
+
+        let footer = self.position()?;
+        write_sir0_footer(&mut self.content, &self.pointers)?;
+        self.pad()?;
+
+        self.content.set_position(0);
+        write_sir0_header(&mut self.content, content_header, footer)?;
+        Ok(self.content.into_inner())
+    }
+}
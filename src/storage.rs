@@ -0,0 +1,323 @@
+use crate::farc::{parse_index, SharedStorage};
+use crate::observer::Observer;
+use crate::raw::{RawError, ReadAt};
+use crate::{Farc, FarcError};
+use io_partition::Partition;
+use std::cmp::min;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::{Arc, Mutex};
+
+fn read_failed(offset: u64, buffer: &[u8], error: &io::Error) -> RawError {
+    RawError::ReadFailed {
+        offset,
+        length: buffer.len() as u64,
+        reason: error.to_string(),
+    }
+}
+
+/// Files are read at a position without moving their cursor, so they can be shared (for example in an ``Arc``)
+#[cfg(unix)]
+impl ReadAt for File {
+    fn len(&self) -> Result<u64, RawError> {
+        self.metadata()
+            .map(|metadata| metadata.len())
+            .map_err(|err| RawError::LengthFailed {
+                reason: err.to_string(),
+            })
+    }
+
+    fn read_exact_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), RawError> {
+        use std::os::unix::fs::FileExt;
+        FileExt::read_exact_at(self, buffer, offset).map_err(|err| {
+            if err.kind() == io::ErrorKind::UnexpectedEof {
+                RawError::OutOfBounds {
+                    offset,
+                    length: buffer.len() as u64,
+                }
+            } else {
+                read_failed(offset, buffer, &err)
+            }
+        })
+    }
+}
+
+/// Files are read at a position without moving their cursor, so they can be shared (for example in an ``Arc``)
+#[cfg(windows)]
+impl ReadAt for File {
+    fn len(&self) -> Result<u64, RawError> {
+        self.metadata()
+            .map(|metadata| metadata.len())
+            .map_err(|err| RawError::LengthFailed {
+                reason: err.to_string(),
+            })
+    }
+
+    fn read_exact_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), RawError> {
+        use std::os::windows::fs::FileExt;
+        let mut read = 0;
+        while read < buffer.len() {
+            match self.seek_read(&mut buffer[read..], offset + read as u64) {
+                Ok(0) => {
+                    return Err(RawError::OutOfBounds {
+                        offset,
+                        length: buffer.len() as u64,
+                    })
+                }
+                Ok(count) => read += count,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                Err(err) => return Err(read_failed(offset, buffer, &err)),
+            };
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl ReadAt for memmap2::Mmap {
+    fn len(&self) -> Result<u64, RawError> {
+        Ok((**self).len() as u64)
+    }
+
+    fn read_exact_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), RawError> {
+        (**self).read_exact_at(offset, buffer)
+    }
+}
+
+/// A part of a [`ReadAt`] source, starting at an offset. It can be used to read an archive embedded in another
+/// container, like a full romfs image.
+///
+/// # Example
+/// ```
+/// use pmd_farc::raw::ReadAt;
+/// use pmd_farc::StorageRange;
+/// let container = b"headerFARC...".to_vec();
+/// let archive = StorageRange::new(container, 6, 7).unwrap();
+/// let mut magic = [0; 4];
+/// archive.read_exact_at(0, &mut magic).unwrap();
+/// assert_eq!(&magic, b"FARC");
+/// assert!(archive.read_exact_at(4, &mut magic).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct StorageRange<S: ReadAt> {
+    inner: S,
+    offset: u64,
+    length: u64,
+}
+
+impl<S: ReadAt> StorageRange<S> {
+    /// Create a view of the ``length`` bytes of ``inner`` starting at ``offset``. Return
+    /// [`RawError::OutOfBounds`] if they aren't all inside ``inner``.
+    pub fn new(inner: S, offset: u64, length: u64) -> Result<Self, RawError> {
+        match offset.checked_add(length) {
+            Some(end) if end <= inner.len()? => Ok(Self {
+                inner,
+                offset,
+                length,
+            }),
+            _ => Err(RawError::OutOfBounds { offset, length }),
+        }
+    }
+
+    /// Return the position of the start of this range in the inner source
+    #[must_use]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Return the inner source
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: ReadAt> ReadAt for StorageRange<S> {
+    fn len(&self) -> Result<u64, RawError> {
+        Ok(self.length)
+    }
+
+    fn read_exact_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), RawError> {
+        let length = buffer.len() as u64;
+        match offset.checked_add(length) {
+            Some(end) if end <= self.length => {
+                self.inner.read_exact_at(self.offset + offset, buffer)
+            }
+            _ => Err(RawError::OutOfBounds { offset, length }),
+        }
+    }
+}
+
+/// A [`Read`] + [`Seek`] cursor over a [`ReadAt`] source, so it can be used with [`Farc`]. Cloning it (if the
+/// source can be cloned, like an ``Arc``) create an independent cursor over the same source.
+#[derive(Debug, Clone)]
+pub struct StorageCursor<S: ReadAt> {
+    storage: S,
+    position: u64,
+}
+
+impl<S: ReadAt> StorageCursor<S> {
+    /// Create a cursor at the start of ``storage``
+    pub fn new(storage: S) -> Self {
+        Self {
+            storage,
+            position: 0,
+        }
+    }
+
+    /// Return the source
+    #[must_use]
+    pub fn get_ref(&self) -> &S {
+        &self.storage
+    }
+
+    /// Return the source
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+}
+
+impl<S: ReadAt> Read for StorageCursor<S> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let length = self.storage.len().map_err(io::Error::other)?;
+        let remaining = length.saturating_sub(self.position);
+        let count = min(
+            usize::try_from(remaining).unwrap_or(usize::MAX),
+            buffer.len(),
+        );
+        self.storage
+            .read_exact_at(self.position, &mut buffer[..count])
+            .map_err(io::Error::other)?;
+        self.position += count as u64;
+        Ok(count)
+    }
+}
+
+impl<S: ReadAt> Seek for StorageCursor<S> {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let (base, delta) = match position {
+            SeekFrom::Start(position) => {
+                self.position = position;
+                return Ok(position);
+            }
+            SeekFrom::End(delta) => (self.storage.len().map_err(io::Error::other)?, delta),
+            SeekFrom::Current(delta) => (self.position, delta),
+        };
+        match base.checked_add_signed(delta) {
+            Some(position) => {
+                self.position = position;
+                Ok(position)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
+
+impl<S: ReadAt + Send + Sync + 'static> Farc<StorageCursor<Arc<S>>> {
+    /// Parse an archive from a [`ReadAt`] source, like a [`File`], a ``Vec<u8>``, or a [`StorageRange`] of another
+    /// container. The archive is parsed, and [`Farc::read_hashed_file`] read the files, with positional reads that
+    /// don't lock the source. The handles of [`Farc::get_hashed_file`] use a shared [`StorageCursor`].
+    ///
+    /// # Example
+    /// ```
+    /// use pmd_farc::{hash_name, Farc, FarcWriter, StorageRange};
+    /// use std::io::{Cursor, Read};
+    /// let mut writer = FarcWriter::default();
+    /// writer.add_hashed_file(hash_name("a.bin"), b"hello".to_vec());
+    /// let mut archive = Cursor::new(Vec::new());
+    /// writer.write_hashed(&mut archive).unwrap();
+    /// let archive = archive.into_inner();
+    ///
+    /// // the archive is stored after 32 bytes of another container
+    /// let mut container = vec![0; 32];
+    /// container.extend_from_slice(&archive);
+    /// let range = StorageRange::new(container, 32, archive.len() as u64).unwrap();
+    /// let farc = Farc::from_storage(range).unwrap();
+    /// assert!(farc.read_named_file("a.bin").unwrap().starts_with(b"hello"));
+    /// let mut content = Vec::new();
+    /// farc.get_named_file("a.bin").unwrap().read_to_end(&mut content).unwrap();
+    /// assert!(content.starts_with(b"hello"));
+    /// ```
+    pub fn from_storage(storage: S) -> Result<Self, FarcError> {
+        let storage = Arc::new(storage);
+        let observer = Observer::default();
+        let (index, named_in_archive) = parse_index(
+            storage.len()?,
+            &mut |offset, buffer| Ok(storage.read_exact_at(offset, buffer)?),
+            &observer,
+        )?;
+        let cursor = StorageCursor::new(storage.clone());
+        let mut farc = Self::from_index(Arc::new(Mutex::new(cursor)), index, named_in_archive);
+        farc.storage = Some(SharedStorage(storage));
+        Ok(farc)
+    }
+}
+
+/// A [`Read`] + [`Seek`] source that can be sent to another thread, implemented for every such type. It is used as
+/// a trait object by [`Farc::new_boxed`].
+pub trait ReadSeekSend: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ReadSeekSend for T {}
+
+/// A [`Farc`] reading from a source chosen at runtime (see [`Farc::new_boxed`])
+pub type BoxedFarc = Farc<Box<dyn ReadSeekSend>>;
+
+impl Farc<Box<dyn ReadSeekSend>> {
+    /// Parse an archive from any [`ReadSeekSend`] source, so a file, a buffer in memory or a network stream can be
+    /// used without making the code using the archive generic
+    ///
+    /// # Example
+    /// ```
+    /// use pmd_farc::{hash_name, BoxedFarc, Farc, FarcWriter, ReadSeekSend};
+    /// use std::io::Cursor;
+    /// # let mut writer = FarcWriter::default();
+    /// # writer.add_hashed_file(hash_name("a.bin"), b"hello".to_vec());
+    /// # let mut archive = Cursor::new(Vec::new());
+    /// # writer.write_hashed(&mut archive).unwrap();
+    /// # let bytes = archive.into_inner();
+    /// # let in_memory = true;
+    /// let source: Box<dyn ReadSeekSend> = if in_memory {
+    ///     Box::new(Cursor::new(bytes))
+    /// } else {
+    ///     Box::new(std::fs::File::open("message_us.bin").unwrap())
+    /// };
+    /// let farc: BoxedFarc = Farc::new_boxed(source).unwrap();
+    /// assert_eq!(farc.file_count(), 1);
+    /// ```
+    pub fn new_boxed(file: Box<dyn ReadSeekSend>) -> Result<Self, FarcError> {
+        Self::new(file)
+    }
+}
+
+impl<F: Read + Seek> Farc<Partition<F>> {
+    /// Parse an archive stored in the ``length`` bytes of ``reader`` starting at ``offset``, like an archive inside a
+    /// romfs image or a memory dump, without copying it out first. Use [`Farc::from_storage`] with a
+    /// [`StorageRange`] if the source implement [`ReadAt`].
+    ///
+    /// # Example
+    /// ```
+    /// use pmd_farc::{hash_name, Farc, FarcWriter};
+    /// use std::io::{Cursor, Read};
+    /// let mut writer = FarcWriter::default();
+    /// writer.add_hashed_file(hash_name("a.bin"), b"hello".to_vec());
+    /// let mut archive = Cursor::new(Vec::new());
+    /// writer.write_hashed(&mut archive).unwrap();
+    /// let archive = archive.into_inner();
+    ///
+    /// let mut image = vec![0xFF; 0x200];
+    /// image.extend_from_slice(&archive);
+    /// image.extend_from_slice(&[0xFF; 0x100]);
+    /// let farc = Farc::new_at(Cursor::new(image), 0x200, archive.len() as u64).unwrap();
+    /// let mut content = Vec::new();
+    /// farc.get_named_file("a.bin").unwrap().read_to_end(&mut content).unwrap();
+    /// assert!(content.starts_with(b"hello"));
+    /// ```
+    pub fn new_at(reader: F, offset: u64, length: u64) -> Result<Self, FarcError> {
+        Self::new(
+            Partition::new(reader, offset, length).map_err(FarcError::PartitionCreationError)?,
+        )
+    }
+}
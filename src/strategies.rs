@@ -0,0 +1,122 @@
+//! [proptest](https://docs.rs/proptest) strategies generating the content of archives, and [`check_round_trip`], a
+//! property checking that an archive written by [`FarcWriter`] is parsed back by [`Farc`] with the same files. Only
+//! available with the ``proptest`` feature.
+//!
+//! # Example
+//! ```
+//! use pmd_farc::strategies::{check_round_trip, farc_writer};
+//! use proptest::test_runner::TestRunner;
+//!
+//! let mut runner = TestRunner::default();
+//! runner
+//!     .run(&farc_writer(16, 64), |writer| check_round_trip(&writer))
+//!     .unwrap();
+//! ```
+//!
+//! In a test, they are usually used with the ``proptest!`` macro, like ``fn round_trip(writer in
+//! farc_writer(16, 64))``, with ``check_round_trip(&writer)?`` as the body.
+
+use crate::{hash_name, Farc, FarcWriter};
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseError;
+use std::io::{Cursor, Read};
+
+/// A file name, like the ones used in the game (lowercase letters, digits and underscores, with an extension)
+pub fn file_name() -> impl Strategy<Value = String> {
+    "[a-z0-9_]{1,16}\\.(bin|bgrs|bchenv|lst|dat)"
+}
+
+/// Any name that can be written in an archive: a non-empty string without null character
+pub fn any_name() -> impl Strategy<Value = String> {
+    "[^\\x00]{1,16}"
+}
+
+/// The content of a file, with at most ``max_length`` bytes
+pub fn file_content(max_length: usize) -> impl Strategy<Value = Vec<u8>> {
+    vec(any::<u8>(), 0..=max_length)
+}
+
+/// An alignment accepted by [`FarcWriter::set_alignment`], up to 256 bytes
+pub fn alignment() -> impl Strategy<Value = u32> {
+    (1..=16_u32).prop_map(|factor| factor * 16)
+}
+
+/// A file, as its hash, its name (``None`` about half of the time) and its content
+pub fn entry(max_length: usize) -> impl Strategy<Value = (u32, Option<String>, Vec<u8>)> {
+    let name = prop_oneof![
+        prop_oneof![file_name(), any_name()].prop_map(|name| (hash_name(&name), Some(name))),
+        any::<u32>().prop_map(|hash| (hash, None)),
+    ];
+    (name, file_content(max_length)).prop_map(|((hash, name), content)| (hash, name, content))
+}
+
+fn writer_from_entries(alignment: u32, entries: Vec<(u32, Option<String>, Vec<u8>)>) -> FarcWriter {
+    let mut writer = FarcWriter::default();
+    writer
+        .set_alignment(alignment)
+        .expect("the alignment strategy only generate multiples of 16");
+    for (hash, name, content) in entries {
+        match name {
+            Some(name) => writer.add_named_file(name, content),
+            None => writer.add_hashed_file(hash, content),
+        };
+    }
+    writer
+}
+
+/// A [`FarcWriter`] with up to ``max_files`` files of up to ``max_length`` bytes, mixing files with a name and files
+/// with only a hash, and with an arbitrary [`alignment`]
+pub fn farc_writer(max_files: usize, max_length: usize) -> impl Strategy<Value = FarcWriter> {
+    (alignment(), vec(entry(max_length), 0..=max_files))
+        .prop_map(|(alignment, entries)| writer_from_entries(alignment, entries))
+}
+
+/// Like [`farc_writer`], but every file has a name, so the archive can be written with [`FarcWriter::write_named`]
+pub fn named_farc_writer(max_files: usize, max_length: usize) -> impl Strategy<Value = FarcWriter> {
+    let entry = (file_name(), file_content(max_length))
+        .prop_map(|(name, content)| (hash_name(&name), Some(name), content));
+    (alignment(), vec(entry, 0..=max_files))
+        .prop_map(|(alignment, entries)| writer_from_entries(alignment, entries))
+}
+
+/// Check that the archive written from ``writer`` is parsed back with the same files. The archive is written with
+/// [`FarcWriter::write_hashed`], and also with [`FarcWriter::write_named`] if the name of every file is known.
+///
+/// The content read back may be longer than the written one, as the length of a file include its padding, but the
+/// additional bytes should all be 0.
+pub fn check_round_trip(writer: &FarcWriter) -> Result<(), TestCaseError> {
+    let all_named = writer.iter().all(|(_, name, _)| name.is_some());
+    let mut write_modes = vec![false];
+    if all_named {
+        write_modes.push(true);
+    };
+    for named in write_modes {
+        let mut archive = Cursor::new(Vec::new());
+        let written = if named {
+            writer.write_named(&mut archive)
+        } else {
+            writer.write_hashed(&mut archive)
+        };
+        prop_assert!(written.is_ok(), "can't write the archive: {:?}", written);
+        let farc = Farc::new(Cursor::new(archive.into_inner()));
+        prop_assert!(farc.is_ok(), "can't parse the archive: {:?}", farc.err());
+        let farc = farc.unwrap();
+        prop_assert_eq!(farc.is_named_in_archive(), named);
+        prop_assert_eq!(farc.file_count(), writer.file_count());
+
+        for (hash, name, content) in writer.iter() {
+            let mut read = Vec::new();
+            let file = match (named, name) {
+                (true, Some(name)) => farc.get_named_file(name),
+                _ => farc.get_hashed_file(hash),
+            };
+            prop_assert!(file.is_ok(), "can't find the file {:08x}", hash);
+            prop_assert!(file.unwrap().read_to_end(&mut read).is_ok());
+            prop_assert!(read.len() >= content.len());
+            prop_assert_eq!(&read[..content.len()], content);
+            prop_assert!(read[content.len()..].iter().all(|byte| *byte == 0));
+        }
+    }
+    Ok(())
+}
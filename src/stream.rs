@@ -0,0 +1,165 @@
+use crate::{Farc, FarcWriter, NameConfidence};
+use byteorder::{ReadBytesExt, LE};
+use io_partition::PartitionMutex;
+use std::convert::TryFrom;
+use std::io::{self, Read, Seek};
+use std::string::FromUtf8Error;
+use thiserror::Error;
+
+/// The magic at the start of a stream created by [`FarcStream`]
+pub const STREAM_MAGIC: &[u8; 8] = b"FARCSTR1";
+
+/// An error that can happen while reading a stream created by [`FarcStream`]
+#[derive(Debug, Error)]
+pub enum StreamError {
+    /// An error happened while reading the stream
+    #[error("input/output error")]
+    IOError(#[from] io::Error),
+    /// The stream doesn't start with [`STREAM_MAGIC`]
+    #[error("the stream doesn't start with the FARCSTR1 magic")]
+    BadMagic,
+    /// The name of a file isn't valid utf-8
+    #[error("the name of a file isn't valid utf-8")]
+    InvalidName(#[from] FromUtf8Error),
+}
+
+/// A [`Read`] adapter streaming every file of a [`Farc`] in the order they are stored, each preceded by a small header,
+/// so an archive can be sent through a pipe or a checksummer without random access. The stream is read back with
+/// [`FarcWriter::from_stream`].
+///
+/// The stream start with [`STREAM_MAGIC`] and the number of files (``u32``), then contain for each file its hash
+/// (``u32``), the length of its name in bytes (``u16``, 0 if unknown or not verified), its name in utf-8, the length of its content
+/// (``u32``) and its content. Every number is little-endian.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, Farc, FarcStream, FarcWriter};
+/// use std::io::{Cursor, Read};
+/// let mut writer = FarcWriter::default();
+/// writer.add_named_file("a.bin".to_string(), b"hello".to_vec());
+/// writer.add_hashed_file(hash_name("b.bin"), b"world".to_vec());
+/// let mut archive = Cursor::new(Vec::new());
+/// writer.write_hashed(&mut archive).unwrap();
+/// let mut farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+/// farc.check_file_name("a.bin");
+///
+/// let mut stream = Vec::new();
+/// FarcStream::new(&farc).read_to_end(&mut stream).unwrap();
+/// let received = FarcWriter::from_stream(stream.as_slice()).unwrap();
+/// assert_eq!(received.file_count(), 2);
+/// assert_eq!(received.get_name(hash_name("a.bin")), Some("a.bin"));
+/// ```
+pub struct FarcStream<'a, F: Read + Seek> {
+    farc: &'a Farc<F>,
+    /// The hash of the files, in the order they are stored
+    hashes: Vec<u32>,
+    next_file: usize,
+    /// The header being sent, and the number of bytes of it already sent
+    header: Vec<u8>,
+    header_position: usize,
+    current: Option<PartitionMutex<F>>,
+}
+
+impl<'a, F: Read + Seek> FarcStream<'a, F> {
+    /// Create a stream of the files of ``farc``
+    pub fn new(farc: &'a Farc<F>) -> Self {
+        let mut files = farc.iter_files().collect::<Vec<_>>();
+        files.sort_by_key(|file| (file.start, file.name_hash));
+        let mut header = STREAM_MAGIC.to_vec();
+        header.extend_from_slice(&(files.len() as u32).to_le_bytes());
+        Self {
+            farc,
+            hashes: files.iter().map(|file| file.name_hash).collect(),
+            next_file: 0,
+            header,
+            header_position: 0,
+            current: None,
+        }
+    }
+
+    /// Prepare the header of the next file, and open it. Return false if every file was sent.
+    fn start_next_file(&mut self) -> io::Result<bool> {
+        let hash = match self.hashes.get(self.next_file) {
+            Some(hash) => *hash,
+            None => return Ok(false),
+        };
+        self.next_file += 1;
+        let file = self
+            .farc
+            .index
+            .get_file_by_hash(hash)
+            .ok_or_else(|| io::Error::other(format!("the file {:08x} disappeared", hash)))?;
+        // forced or guessed names don't match the hash, and would change it when read back
+        let name = match file.name_confidence {
+            Some(NameConfidence::Verified) => file.name.as_deref().unwrap_or(""),
+            _ => "",
+        };
+        let name_length = u16::try_from(name.len()).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("the name {:?} is too long", name),
+            )
+        })?;
+        self.header.clear();
+        self.header.extend_from_slice(&hash.to_le_bytes());
+        self.header.extend_from_slice(&name_length.to_le_bytes());
+        self.header.extend_from_slice(name.as_bytes());
+        self.header.extend_from_slice(&file.length.to_le_bytes());
+        self.header_position = 0;
+        self.current = Some(self.farc.get_hashed_file(hash).map_err(io::Error::other)?);
+        Ok(true)
+    }
+}
+
+impl<F: Read + Seek> Read for FarcStream<'_, F> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.header_position < self.header.len() {
+                let remaining = &self.header[self.header_position..];
+                let count = remaining.len().min(buffer.len());
+                buffer[..count].copy_from_slice(&remaining[..count]);
+                self.header_position += count;
+                return Ok(count);
+            };
+            if let Some(current) = &mut self.current {
+                let count = current.read(buffer)?;
+                if count > 0 || buffer.is_empty() {
+                    return Ok(count);
+                };
+                self.current = None;
+            };
+            if !self.start_next_file()? {
+                return Ok(0);
+            };
+        }
+    }
+}
+
+impl FarcWriter {
+    /// Read a stream created by [`FarcStream`]. The file names it contains are kept.
+    pub fn from_stream<R: Read>(mut reader: R) -> Result<Self, StreamError> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != STREAM_MAGIC {
+            return Err(StreamError::BadMagic);
+        };
+        let mut writer = Self::default();
+        for _ in 0..reader.read_u32::<LE>()? {
+            let hash = reader.read_u32::<LE>()?;
+            let mut name = vec![0; usize::from(reader.read_u16::<LE>()?)];
+            reader.read_exact(&mut name)?;
+            let mut content = Vec::new();
+            let length = u64::from(reader.read_u32::<LE>()?);
+            (&mut reader).take(length).read_to_end(&mut content)?;
+            if content.len() as u64 != length {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            };
+            if name.is_empty() {
+                writer.add_hashed_file(hash, content);
+            } else {
+                writer.add_named_file(String::from_utf8(name)?, content);
+            };
+        }
+        Ok(writer)
+    }
+}
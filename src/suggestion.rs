@@ -0,0 +1,130 @@
+use crate::hash_name;
+use std::collections::HashSet;
+use std::fmt;
+
+/// The characters tried by default when looking for names near a candidate
+pub const DEFAULT_SUGGESTION_CHARSET: &str = "abcdefghijklmnopqrstuvwxyz0123456789_";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A name that match an unknown hash, and that is near (one character or the extension off) a candidate name that
+/// doesn't match anything. This usually indicate a typo in a name list.
+///
+/// It is displayed with the difference highlighted, like ``script_0[1->2]1.bin``.
+pub struct NameSuggestion {
+    /// The candidate name, that doesn't match any hash
+    pub candidate: String,
+    /// The name near the candidate that match an hash
+    pub suggestion: String,
+    /// The hash of the suggested name
+    pub hash: u32,
+}
+
+impl NameSuggestion {
+    /// Return the part before the difference, the differing part of the candidate, the differing part of the
+    /// suggestion, and the part after the difference
+    #[must_use]
+    pub fn diff(&self) -> (String, String, String, String) {
+        let candidate = self.candidate.chars().collect::<Vec<_>>();
+        let suggestion = self.suggestion.chars().collect::<Vec<_>>();
+        let prefix_len = candidate
+            .iter()
+            .zip(&suggestion)
+            .take_while(|(c1, c2)| c1 == c2)
+            .count();
+        let suffix_len = candidate
+            .iter()
+            .rev()
+            .zip(suggestion.iter().rev())
+            .take(candidate.len().min(suggestion.len()) - prefix_len)
+            .take_while(|(c1, c2)| c1 == c2)
+            .count();
+        (
+            candidate[..prefix_len].iter().collect(),
+            candidate[prefix_len..candidate.len() - suffix_len]
+                .iter()
+                .collect(),
+            suggestion[prefix_len..suggestion.len() - suffix_len]
+                .iter()
+                .collect(),
+            candidate[candidate.len() - suffix_len..].iter().collect(),
+        )
+    }
+}
+
+impl fmt::Display for NameSuggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (prefix, old, new, suffix) = self.diff();
+        write!(f, "{}[{}->{}]{}", prefix, old, new, suffix)
+    }
+}
+
+/// Return the names one edit away from ``candidate`` (one character substituted, inserted or removed among
+/// ``charset``, or the extension replaced by one of ``extensions``) whose hash is in ``unknown_hashes``.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, suggest_near_names, DEFAULT_SUGGESTION_CHARSET};
+/// use std::collections::HashSet;
+/// let unknown_hashes = [hash_name("script_021.bin")].iter().copied().collect::<HashSet<_>>();
+/// let suggestions = suggest_near_names("script_011.bin", &unknown_hashes, DEFAULT_SUGGESTION_CHARSET, &[]);
+/// assert_eq!(suggestions[0].suggestion, "script_021.bin");
+/// assert_eq!(suggestions[0].to_string(), "script_0[1->2]1.bin");
+/// ```
+#[must_use]
+pub fn suggest_near_names(
+    candidate: &str,
+    unknown_hashes: &HashSet<u32>,
+    charset: &str,
+    extensions: &[&str],
+) -> Vec<NameSuggestion> {
+    let chars = candidate.chars().collect::<Vec<_>>();
+    let mut variants = Vec::new();
+    for position in 0..=chars.len() {
+        let (before, after) = chars.split_at(position);
+        for chara in charset.chars() {
+            variants.push(
+                before
+                    .iter()
+                    .chain(&[chara])
+                    .chain(after)
+                    .collect::<String>(),
+            );
+            if let Some((_, after_substituted)) = after.split_first() {
+                variants.push(
+                    before
+                        .iter()
+                        .chain(&[chara])
+                        .chain(after_substituted)
+                        .collect::<String>(),
+                );
+            };
+        }
+        if let Some((_, after_removed)) = after.split_first() {
+            variants.push(before.iter().chain(after_removed).collect::<String>());
+        };
+    }
+    if let Some(dot) = candidate.rfind('.') {
+        for extension in extensions {
+            variants.push(format!("{}.{}", &candidate[..dot], extension));
+        }
+    };
+
+    let mut seen = HashSet::new();
+    variants
+        .into_iter()
+        .filter(|variant| variant != candidate)
+        .filter_map(|variant| {
+            let hash = hash_name(&variant);
+            if unknown_hashes.contains(&hash) && seen.insert(variant.clone()) {
+                Some(NameSuggestion {
+                    candidate: candidate.to_string(),
+                    suggestion: variant,
+                    hash,
+                })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,230 @@
+//! Builders generating valid (or deliberately broken) FARC archives in memory, so crates using this one can write
+//! tests without shipping files of the game. Only available with the ``test-util`` feature.
+//!
+//! # Example
+//! ```
+//! use pmd_farc::test_util::{Pathology, SyntheticFarc};
+//! use pmd_farc::{Farc, FarcError};
+//! use std::io::Cursor;
+//!
+//! let farc = SyntheticFarc::new().with_files(10).alignment(64).build_farc();
+//! assert_eq!(farc.file_count(), 10);
+//! assert!(farc.get_named_file("file_0003.bin").is_ok());
+//!
+//! let broken = SyntheticFarc::new().with_files(2).pathology(Pathology::UnsupportedFat5Type).build();
+//! assert!(matches!(Farc::new(Cursor::new(broken)), Err(FarcError::UnsuportedFat5Type(2))));
+//! for pathology in [Pathology::MisalignedFile, Pathology::DataStartOverflow, Pathology::DuplicateEntry] {
+//!     let broken = SyntheticFarc::new().with_files(2).named(true).pathology(pathology).build();
+//!     assert!(Farc::new(Cursor::new(broken)).is_err());
+//! }
+//! ```
+
+use crate::raw::{read_header, read_u32_at};
+use crate::{hash_name, Farc, FarcWriter};
+use std::io::Cursor;
+
+/// A way to break a generated archive, to test how errors are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pathology {
+    /// Only keep the first half of the archive
+    Truncated,
+    /// Replace the ``FARC`` magic at the start of the archive
+    BadMagic,
+    /// Set the fat5 type (telling whether names or hashes are stored) to the unsupported value 2
+    UnsupportedFat5Type,
+    /// Move the start of the first file by 4 bytes, so it isn't a multiple of 16. Need at least one file.
+    MisalignedFile,
+    /// Set the offset of the first file so its start overflow an ``u32``. Need at least one file.
+    DataStartOverflow,
+    /// Give the second file the same hash (or name) as the first one. Need at least two files.
+    DuplicateEntry,
+    /// Cut the archive in the middle of the file table, after half of its entries. Need at least one file.
+    TruncatedTable,
+    /// Set the number of files to ``u32::MAX``, far more than the file table can contain
+    OversizedFileCount,
+}
+
+/// A builder for an in-memory archive
+#[derive(Debug, Clone)]
+pub struct SyntheticFarc {
+    files: Vec<(u32, Option<String>, Vec<u8>)>,
+    named: bool,
+    alignment: u32,
+    pathology: Option<Pathology>,
+}
+
+impl Default for SyntheticFarc {
+    fn default() -> Self {
+        Self {
+            files: Vec::new(),
+            named: false,
+            alignment: 16,
+            pathology: None,
+        }
+    }
+}
+
+impl SyntheticFarc {
+    /// Create a builder for an empty, hash-indexed archive
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add ``count`` files, named ``file_0000.bin``, ``file_0001.bin``..., whose content is their name repeated a
+    /// number of times depending on their index (so the files have different lengths)
+    #[must_use]
+    pub fn with_files(mut self, count: usize) -> Self {
+        let first = self.files.len();
+        for index in first..first + count {
+            let name = format!("file_{:04}.bin", index);
+            let content = name.repeat(index % 7 + 1).into_bytes();
+            self = self.with_file(&name, content);
+        }
+        self
+    }
+
+    /// Add a file with this name
+    #[must_use]
+    pub fn with_file(mut self, name: &str, content: Vec<u8>) -> Self {
+        self.files
+            .push((hash_name(name), Some(name.to_string()), content));
+        self
+    }
+
+    /// Add a file with this hash, whose name is unknown. The archive can then only be hash-indexed.
+    #[must_use]
+    pub fn with_hashed_file(mut self, hash: u32, content: Vec<u8>) -> Self {
+        self.files.push((hash, None, content));
+        self
+    }
+
+    /// Store the names of the files in the archive (``true``) or only their hashes (``false``, the default)
+    #[must_use]
+    pub fn named(mut self, named: bool) -> Self {
+        self.named = named;
+        self
+    }
+
+    /// Set the alignment of the start of each file (see [`FarcWriter::set_alignment`])
+    #[must_use]
+    pub fn alignment(mut self, alignment: u32) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    /// Break the generated archive in this way
+    #[must_use]
+    pub fn pathology(mut self, pathology: Pathology) -> Self {
+        self.pathology = Some(pathology);
+        self
+    }
+
+    /// Return the names of the added files, in the order they were added (``None`` for the ones added by hash)
+    pub fn names(&self) -> impl Iterator<Item = Option<&str>> {
+        self.files.iter().map(|(_, name, _)| name.as_deref())
+    }
+
+    /// Return the content of the added files, with their hash
+    pub fn contents(&self) -> impl Iterator<Item = (u32, &[u8])> {
+        self.files
+            .iter()
+            .map(|(hash, _, content)| (*hash, content.as_slice()))
+    }
+
+    /// Create the archive
+    ///
+    /// # Panics
+    ///
+    /// Panics if the archive is named but a file was added by hash, if the alignment isn't a multiple of 16, or if the
+    /// pathology need more files than were added
+    #[must_use]
+    pub fn build(&self) -> Vec<u8> {
+        let mut writer = FarcWriter::default();
+        writer
+            .set_alignment(self.alignment)
+            .expect("the alignment isn't a non-zero multiple of 16");
+        for (hash, name, content) in &self.files {
+            match name {
+                Some(name) => writer.add_named_file(name.clone(), content.clone()),
+                None => writer.add_hashed_file(*hash, content.clone()),
+            };
+        }
+        let mut archive = Cursor::new(Vec::new());
+        if self.named {
+            writer.write_named(&mut archive)
+        } else {
+            writer.write_hashed(&mut archive)
+        }
+        .expect("can't write the synthetic archive");
+        let mut archive = archive.into_inner();
+        if let Some(pathology) = self.pathology {
+            apply_pathology(&mut archive, pathology);
+        };
+        archive
+    }
+
+    /// Create the archive, and parse it with [`Farc`]. For a hash-indexed archive, the name of the files added with
+    /// a name are checked, so they are all known.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`SyntheticFarc::build`], or if the archive can't be parsed (as is the case for most
+    /// pathologies)
+    #[must_use]
+    pub fn build_farc(&self) -> Farc<Cursor<Vec<u8>>> {
+        let mut farc =
+            Farc::new(Cursor::new(self.build())).expect("can't parse the synthetic archive");
+        for name in self.names().flatten() {
+            farc.check_file_name(name);
+        }
+        farc
+    }
+}
+
+fn write_u32_at(archive: &mut [u8], offset: u64, value: u32) {
+    let offset = offset as usize;
+    archive[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn apply_pathology(archive: &mut Vec<u8>, pathology: Pathology) {
+    // locate the fat5 table, the same way as raw::read_entries does
+    let header = read_header(archive.as_slice()).expect("invalid synthetic archive");
+    let sir0 = u64::from(header.sir0_offset);
+    let content_header =
+        sir0 + u64::from(read_u32_at(archive.as_slice(), sir0 + 4).expect("invalid sir0"));
+    let table = sir0 + u64::from(read_u32_at(archive.as_slice(), content_header).unwrap());
+    let file_count = read_u32_at(archive.as_slice(), content_header + 4).unwrap();
+    let need_files = |count: u32| {
+        assert!(
+            file_count >= count,
+            "the pathology {:?} need at least {} files",
+            pathology,
+            count
+        )
+    };
+    match pathology {
+        Pathology::Truncated => archive.truncate(archive.len() / 2),
+        Pathology::BadMagic => archive[..4].copy_from_slice(b"FRAC"),
+        Pathology::UnsupportedFat5Type => write_u32_at(archive, content_header + 8, 2),
+        Pathology::MisalignedFile => {
+            need_files(1);
+            let offset = read_u32_at(archive.as_slice(), table + 4).unwrap();
+            write_u32_at(archive, table + 4, offset + 4);
+        }
+        Pathology::DataStartOverflow => {
+            need_files(1);
+            write_u32_at(archive, table + 4, u32::MAX - 15);
+        }
+        Pathology::DuplicateEntry => {
+            need_files(2);
+            let first = read_u32_at(archive.as_slice(), table).unwrap();
+            write_u32_at(archive, table + 12, first);
+        }
+        Pathology::TruncatedTable => {
+            need_files(1);
+            archive.truncate((table + u64::from(file_count / 2) * 12 + 6) as usize);
+        }
+        Pathology::OversizedFileCount => write_u32_at(archive, content_header + 4, u32::MAX),
+    };
+}
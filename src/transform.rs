@@ -0,0 +1,222 @@
+use crate::{Farc, FarcError, FarcWriter};
+use std::io::{self, Read, Seek};
+use thiserror::Error;
+
+/// An error that can happen while transforming a file
+#[derive(Debug, Error)]
+pub enum TransformError {
+    /// An error happened while reading the archive
+    #[error("an error happened while reading the archive")]
+    FarcError(#[from] FarcError),
+    /// An error happened while reading a file
+    #[error("input/output error")]
+    IOError(#[from] io::Error),
+    /// No [`Transform`] with this name is registered
+    #[error("there is no transform named {0:?}")]
+    UnknownTransform(String),
+    /// The [`Transform`] failed
+    #[error("the {transform} transform failed: {message}")]
+    Failed {
+        /// The name of the [`Transform`]
+        transform: String,
+        /// The description of the error
+        message: String,
+    },
+}
+
+/// A conversion of the files of an archive to an editable format when they are extracted (like an image to PNG, or
+/// a binary table to JSON), reversed when they are packed again. Implementations are added to a
+/// [`TransformRegistry`].
+pub trait Transform: Send + Sync {
+    /// The name of the transform, like ``"bchunk-png"``
+    fn name(&self) -> &str;
+
+    /// The extension added to the name of the extracted files, like ``"png"``
+    fn extension(&self) -> &str;
+
+    /// The extensions of the file names (in the archive) this transform apply to, like ``["bch"]``
+    fn source_extensions(&self) -> &[&str] {
+        &[]
+    }
+
+    /// Return true if this transform apply to this content, usually by checking its magic. This is used for files
+    /// whose extension isn't in [`Transform::source_extensions`] (or whose name is unknown).
+    fn matches_content(&self, _content: &[u8]) -> bool {
+        false
+    }
+
+    /// Convert the content of a file of the archive. It may end with padding, that should be ignored.
+    fn extract(&self, content: &[u8]) -> Result<Vec<u8>, TransformError>;
+
+    /// Convert an extracted file back to the content stored in the archive
+    fn pack(&self, content: &[u8]) -> Result<Vec<u8>, TransformError>;
+}
+
+/// The content of a file, converted by a [`TransformRegistry`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransformedFile {
+    /// The converted content (or the raw content, if no transform apply)
+    pub content: Vec<u8>,
+    /// The name of the [`Transform`] used, if any
+    pub transform: Option<String>,
+    /// The extension to add to the name of the extracted file, if any
+    pub extension: Option<String>,
+}
+
+#[derive(Default)]
+/// An ordered list of [`Transform`], used to convert the files of a [`Farc`] on extraction, and to convert them back
+/// when writing them with a [`FarcWriter`]. The first transform whose [`Transform::source_extensions`] contain the
+/// extension of the file name is used, or else the first whose [`Transform::matches_content`] return true.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, Farc, FarcWriter, Transform, TransformError, TransformRegistry};
+/// use std::io::Cursor;
+///
+/// /// A toy transform, showing the bytes of .dat files as text
+/// struct Decimal;
+///
+/// impl Transform for Decimal {
+///     fn name(&self) -> &str {
+///         "decimal"
+///     }
+///     fn extension(&self) -> &str {
+///         "txt"
+///     }
+///     fn source_extensions(&self) -> &[&str] {
+///         &["dat"]
+///     }
+///     fn extract(&self, content: &[u8]) -> Result<Vec<u8>, TransformError> {
+///         let numbers = content.iter().map(|byte| byte.to_string()).collect::<Vec<_>>();
+///         Ok(numbers.join(" ").into_bytes())
+///     }
+///     fn pack(&self, content: &[u8]) -> Result<Vec<u8>, TransformError> {
+///         String::from_utf8_lossy(content)
+///             .split_whitespace()
+///             .map(|number| number.parse())
+///             .collect::<Result<_, _>>()
+///             .map_err(|err| TransformError::Failed {
+///                 transform: self.name().to_string(),
+///                 message: format!("{}", err),
+///             })
+///     }
+/// }
+///
+/// let mut registry = TransformRegistry::default();
+/// registry.add_transform(Box::new(Decimal));
+///
+/// let mut writer = FarcWriter::default();
+/// writer.add_transformed_file(hash_name("a.dat"), b"1 2 3", Some("decimal"), &registry).unwrap();
+/// let mut archive = Cursor::new(Vec::new());
+/// writer.write_hashed(&mut archive).unwrap();
+/// let mut farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+/// farc.check_file_name("a.dat");
+///
+/// let file = farc.get_transformed_file(hash_name("a.dat"), &registry).unwrap();
+/// assert!(file.content.starts_with(b"1 2 3"));
+/// assert_eq!(file.extension.as_deref(), Some("txt"));
+/// ```
+pub struct TransformRegistry {
+    transforms: Vec<Box<dyn Transform>>,
+}
+
+impl TransformRegistry {
+    /// Add a [`Transform`] at the end of this registry
+    pub fn add_transform(&mut self, transform: Box<dyn Transform>) {
+        self.transforms.push(transform);
+    }
+
+    /// Return the [`Transform`] with this name
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&dyn Transform> {
+        self.transforms
+            .iter()
+            .find(|transform| transform.name() == name)
+            .map(|transform| transform.as_ref())
+    }
+
+    /// Return the [`Transform`] that apply to the file with this name (if known) and content
+    #[must_use]
+    pub fn detect(&self, name: Option<&str>, content: &[u8]) -> Option<&dyn Transform> {
+        let extension = name.and_then(|name| name.rfind('.').map(|dot| &name[dot + 1..]));
+        let by_extension = extension.and_then(|extension| {
+            self.transforms.iter().find(|transform| {
+                transform
+                    .source_extensions()
+                    .iter()
+                    .any(|source| source.eq_ignore_ascii_case(extension))
+            })
+        });
+        by_extension
+            .or_else(|| {
+                self.transforms
+                    .iter()
+                    .find(|transform| transform.matches_content(content))
+            })
+            .map(|transform| transform.as_ref())
+    }
+
+    /// Convert the content of the file with this name (if known), if a [`Transform`] apply to it
+    pub fn extract(
+        &self,
+        name: Option<&str>,
+        content: Vec<u8>,
+    ) -> Result<TransformedFile, TransformError> {
+        Ok(match self.detect(name, &content) {
+            Some(transform) => TransformedFile {
+                content: transform.extract(&content)?,
+                transform: Some(transform.name().to_string()),
+                extension: Some(transform.extension().to_string()),
+            },
+            None => TransformedFile {
+                content,
+                transform: None,
+                extension: None,
+            },
+        })
+    }
+
+    /// Convert an extracted file back with the [`Transform`] with this name, or return it unchanged if ``name`` is
+    /// ``None``
+    pub fn pack(&self, name: Option<&str>, content: &[u8]) -> Result<Vec<u8>, TransformError> {
+        match name {
+            Some(name) => self
+                .get(name)
+                .ok_or_else(|| TransformError::UnknownTransform(name.to_string()))?
+                .pack(content),
+            None => Ok(content.to_vec()),
+        }
+    }
+}
+
+impl<F: Read + Seek> Farc<F> {
+    /// Read the file with this hash, and convert it with the [`Transform`] of the registry that apply to it, if any
+    pub fn get_transformed_file(
+        &self,
+        hash: u32,
+        registry: &TransformRegistry,
+    ) -> Result<TransformedFile, TransformError> {
+        let mut content = Vec::new();
+        self.get_hashed_file(hash)?.read_to_end(&mut content)?;
+        let name = self
+            .index
+            .get_file_by_hash(hash)
+            .and_then(|file| file.name.as_deref());
+        registry.extract(name, content)
+    }
+}
+
+impl FarcWriter {
+    /// Add a file with the given hash, converted back with the [`Transform`] of the registry with this name (usually
+    /// the one given by [`Farc::get_transformed_file`]), or unchanged if ``transform`` is ``None``
+    pub fn add_transformed_file(
+        &mut self,
+        hash: u32,
+        content: &[u8],
+        transform: Option<&str>,
+        registry: &TransformRegistry,
+    ) -> Result<(), TransformError> {
+        self.add_hashed_file(hash, registry.pack(transform, content)?);
+        Ok(())
+    }
+}
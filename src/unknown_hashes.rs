@@ -0,0 +1,117 @@
+use crate::Farc;
+use std::collections::HashSet;
+use std::io::{self, BufRead, Read, Seek, Write};
+use thiserror::Error;
+
+/// An error that can happen while reading an [`UnknownHashes`]
+#[derive(Debug, Error)]
+pub enum UnknownHashesError {
+    /// An error happened while reading the file
+    #[error("an error happened while reading the unknown hashes file")]
+    IOError(#[from] io::Error),
+    /// A line doesn't contain an hexadecimal hash
+    #[error("the line {0} of the unknown hashes file isn't an hexadecimal hash: {1:?}")]
+    InvalidLine(usize, String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// The set of hashes whose name is still unknown. It can be saved between runs of a tool with
+/// [`UnknownHashes::write`], so the next run only need to look for these hashes (for example with a
+/// [`BruteForce`](crate::BruteForce)), without opening the archive.
+///
+/// The file format contain one hash per line, as 8 hexadecimal digits.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, Farc, FarcWriter, UnknownHashes};
+/// use std::io::Cursor;
+/// let mut writer = FarcWriter::default();
+/// writer.add_hashed_file(hash_name("script_001.bin"), Vec::new());
+/// writer.add_hashed_file(hash_name("script_002.bin"), Vec::new());
+/// let mut archive = Cursor::new(Vec::new());
+/// writer.write_hashed(&mut archive).unwrap();
+/// let mut farc = Farc::new(Cursor::new(archive.into_inner())).unwrap();
+/// farc.check_file_name("script_001.bin");
+///
+/// let mut saved = Vec::new();
+/// UnknownHashes::new_from_farc(&farc).write(&mut saved).unwrap();
+/// let mut unknown = UnknownHashes::read(&mut saved.as_slice()).unwrap();
+/// assert_eq!(unknown.len(), 1);
+/// assert!(unknown.remove_name("script_002.bin"));
+/// assert!(unknown.is_empty());
+/// ```
+pub struct UnknownHashes {
+    hashes: HashSet<u32>,
+}
+
+impl UnknownHashes {
+    /// Create a new [`UnknownHashes`] with the hash of the files of the [`Farc`] whose name is unknown
+    #[must_use]
+    pub fn new_from_farc<F: Read + Seek>(farc: &Farc<F>) -> Self {
+        Self {
+            hashes: farc.iter_hash_unknown_name().collect(),
+        }
+    }
+
+    /// Return the hashes as a set
+    #[must_use]
+    pub const fn as_set(&self) -> &HashSet<u32> {
+        &self.hashes
+    }
+
+    /// Return true if this hash is unknown
+    #[must_use]
+    pub fn contains(&self, hash: u32) -> bool {
+        self.hashes.contains(&hash)
+    }
+
+    /// Return the number of unknown hashes
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// Return true if there isn't any unknown hash left
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    /// Remove an hash, because its name was found. Return true if it was present.
+    pub fn remove(&mut self, hash: u32) -> bool {
+        self.hashes.remove(&hash)
+    }
+
+    /// Remove the hash of this name. Return true if it was present (meaning the name was a new match).
+    pub fn remove_name(&mut self, name: &str) -> bool {
+        self.remove(crate::hash_name(name))
+    }
+
+    /// Write this set, sorted, with one hash per line
+    pub fn write<W: Write>(&self, file: &mut W) -> Result<(), io::Error> {
+        let mut hashes = self.hashes.iter().collect::<Vec<_>>();
+        hashes.sort_unstable();
+        for hash in hashes {
+            writeln!(file, "{:08x}", hash)?;
+        }
+        Ok(())
+    }
+
+    /// Read a set previously written with [`UnknownHashes::write`]
+    pub fn read<R: BufRead>(file: &mut R) -> Result<Self, UnknownHashesError> {
+        let mut hashes = HashSet::new();
+        for (line_number, line) in file.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            };
+            hashes.insert(
+                u32::from_str_radix(line, 16).map_err(|_| {
+                    UnknownHashesError::InvalidLine(line_number + 1, line.to_string())
+                })?,
+            );
+        }
+        Ok(Self { hashes })
+    }
+}
@@ -0,0 +1,291 @@
+use crate::raw::{RawHeader, HEADER_LENGTH};
+use crate::{Farc, FarcError};
+use std::io::{self, Read, Seek, SeekFrom};
+use thiserror::Error;
+
+/// A structural problem found by [`validate_farc`]
+#[derive(Debug, Error)]
+pub enum FarcProblem {
+    /// The file is shorter than the FARC header
+    #[error("the file is only {0} bytes long, shorter than the 0x34 bytes FARC header")]
+    HeaderTooShort(u64),
+    /// The file doesn't start with ``FARC``
+    #[error("the file doesn't start with the FARC magic (found {0:?})")]
+    BadMagic([u8; 4]),
+    /// The sir0 type in the header is neither 4 nor 5
+    #[error("the sir0 type in the header is {0}, expected 4 or 5")]
+    UnknownSir0Type(u32),
+    /// The sir0 section described by the header end after the end of the file
+    #[error("the sir0 section end at 0x{end:x}, after the end of the file at 0x{file_length:x}")]
+    Sir0OutOfBounds {
+        /// The offset of the end of the sir0 section
+        end: u64,
+        /// The length of the file
+        file_length: u64,
+    },
+    /// The data section described by the header start after the end of the file
+    #[error(
+        "the data section start at 0x{start:x}, after the end of the file at 0x{file_length:x}"
+    )]
+    DataOutOfBounds {
+        /// The offset of the start of the data section
+        start: u64,
+        /// The length of the file
+        file_length: u64,
+    },
+    /// The sir0 section and the data section overlap
+    #[error("the sir0 section (0x{sir0_start:x}..0x{sir0_end:x}) overlap with the data section starting at 0x{data_start:x}")]
+    SectionsOverlap {
+        /// The offset of the start of the sir0 section
+        sir0_start: u64,
+        /// The offset of the end of the sir0 section
+        sir0_end: u64,
+        /// The offset of the start of the data section
+        data_start: u64,
+    },
+    /// The file table can't be read (for example because of a bad sir0 pointer)
+    #[error("the file table can't be read")]
+    Unparsable(#[source] FarcError),
+    /// A sub-file end after the end of the file
+    #[error(
+        "the file {hash:08x} end at 0x{end:x}, after the end of the file at 0x{file_length:x}"
+    )]
+    EntryOutOfBounds {
+        /// The hash of the sub-file
+        hash: u32,
+        /// The offset of the end of the sub-file
+        end: u64,
+        /// The length of the file
+        file_length: u64,
+    },
+    /// Two sub-files share some bytes, without covering exactly the same bytes
+    #[error("the files {0:08x} and {1:08x} overlap")]
+    EntriesOverlap(u32, u32),
+}
+
+/// Check the structure of a FARC file, and return every problem found. An empty list mean the file looks valid.
+///
+/// Contrary to [`Farc::new`], this doesn't stop at the first problem when it can continue, so it is suitable to
+/// diagnose broken files. An [`io::Error`] is only returned if the file can't be read.
+///
+/// Note that the data length stored in the header isn't checked, as the game files (and [`FarcWriter`](crate::FarcWriter))
+/// store a length that goes past the end of the file.
+///
+/// # Example
+/// ```
+/// use pmd_farc::{hash_name, validate_farc, FarcProblem, FarcWriter};
+/// use std::io::Cursor;
+/// let mut writer = FarcWriter::default();
+/// writer.add_hashed_file(hash_name("script_001.bin"), vec![1, 2, 3]);
+/// let mut archive = Cursor::new(Vec::new());
+/// writer.write_hashed(&mut archive).unwrap();
+/// let mut archive = archive.into_inner();
+/// assert!(validate_farc(Cursor::new(&archive)).unwrap().is_empty());
+///
+/// archive.truncate(archive.len() - 16);
+/// let problems = validate_farc(Cursor::new(&archive)).unwrap();
+/// assert!(matches!(problems[0], FarcProblem::EntryOutOfBounds { .. }));
+/// ```
+pub fn validate_farc<F: Read + Seek>(mut file: F) -> Result<Vec<FarcProblem>, io::Error> {
+    let mut problems = Vec::new();
+    let file_length = file.seek(SeekFrom::End(0))?;
+    if file_length < HEADER_LENGTH as u64 {
+        problems.push(FarcProblem::HeaderTooShort(file_length));
+        return Ok(problems);
+    };
+    file.seek(SeekFrom::Start(0))?;
+    let mut header_bytes = [0; HEADER_LENGTH];
+    file.read_exact(&mut header_bytes)?;
+
+    if &header_bytes[0..4] != b"FARC" {
+        problems.push(FarcProblem::BadMagic([
+            header_bytes[0],
+            header_bytes[1],
+            header_bytes[2],
+            header_bytes[3],
+        ]));
+        return Ok(problems);
+    };
+    let header = RawHeader::decode(&header_bytes);
+    if header.sir0_type != 4 && header.sir0_type != 5 {
+        problems.push(FarcProblem::UnknownSir0Type(header.sir0_type));
+    };
+    let sir0_start = u64::from(header.sir0_offset);
+    let sir0_end = sir0_start + u64::from(header.sir0_length);
+    let data_start = u64::from(header.data_offset);
+    if sir0_end > file_length {
+        problems.push(FarcProblem::Sir0OutOfBounds {
+            end: sir0_end,
+            file_length,
+        });
+    };
+    if data_start > file_length {
+        problems.push(FarcProblem::DataOutOfBounds {
+            start: data_start,
+            file_length,
+        });
+    };
+    if sir0_start < data_start && sir0_end > data_start {
+        problems.push(FarcProblem::SectionsOverlap {
+            sir0_start,
+            sir0_end,
+            data_start,
+        });
+    };
+    if !problems.is_empty() {
+        return Ok(problems);
+    };
+
+    file.seek(SeekFrom::Start(0))?;
+    let farc = match Farc::new(file) {
+        Ok(farc) => farc,
+        Err(err) => {
+            problems.push(FarcProblem::Unparsable(err));
+            return Ok(problems);
+        }
+    };
+
+    let mut entries = farc
+        .index
+        .iter()
+        .map(|entry| {
+            let start = u64::from(entry.start);
+            (start, start + u64::from(entry.length), entry.name_hash)
+        })
+        .collect::<Vec<_>>();
+    entries.sort_unstable();
+    for (_, end, hash) in &entries {
+        if *end > file_length {
+            problems.push(FarcProblem::EntryOutOfBounds {
+                hash: *hash,
+                end: *end,
+                file_length,
+            });
+        };
+    }
+    // entries are sorted by start, so comparing with the entry that end the last is enough. Entries covering
+    // exactly the same bytes share their data, and aren't considered overlapping.
+    let mut furthest: Option<(u64, u64, u32)> = None;
+    for (start, end, hash) in &entries {
+        if start == end {
+            continue;
+        };
+        if let Some((furthest_start, furthest_end, furthest_hash)) = furthest {
+            if *start < furthest_end && (*start, *end) != (furthest_start, furthest_end) {
+                problems.push(FarcProblem::EntriesOverlap(furthest_hash, *hash));
+            };
+            if *end > furthest_end {
+                furthest = Some((*start, *end, *hash));
+            };
+        } else {
+            furthest = Some((*start, *end, *hash));
+        };
+    }
+    Ok(problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raw::{read_header, read_u32_at, FarcParser, ParseStep};
+    use crate::test_util::{Pathology, SyntheticFarc};
+    use std::io::Cursor;
+
+    fn validate(archive: &[u8]) -> Vec<FarcProblem> {
+        validate_farc(Cursor::new(archive)).unwrap()
+    }
+
+    fn set_u32(archive: &mut [u8], offset: usize, value: u32) {
+        archive[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[test]
+    fn valid_archives() {
+        for named in [false, true] {
+            let archive = SyntheticFarc::new().with_files(20).named(named).build();
+            assert!(validate(&archive).is_empty());
+        }
+    }
+
+    #[test]
+    fn broken_header() {
+        assert!(matches!(
+            validate(b"FARC").as_slice(),
+            [FarcProblem::HeaderTooShort(4)]
+        ));
+        let archive = SyntheticFarc::new()
+            .with_files(2)
+            .pathology(Pathology::BadMagic)
+            .build();
+        assert!(matches!(
+            validate(&archive).as_slice(),
+            [FarcProblem::BadMagic(magic)] if magic == b"FRAC"
+        ));
+
+        // every problem of the header is reported
+        let mut archive = SyntheticFarc::new().with_files(2).build();
+        let length = archive.len() as u32;
+        set_u32(&mut archive, 0x20, 3);
+        set_u32(&mut archive, 0x28, length);
+        set_u32(&mut archive, 0x2C, length + 1);
+        assert!(matches!(
+            validate(&archive).as_slice(),
+            [
+                FarcProblem::UnknownSir0Type(3),
+                FarcProblem::Sir0OutOfBounds { .. },
+                FarcProblem::DataOutOfBounds { .. },
+                FarcProblem::SectionsOverlap { .. },
+            ]
+        ));
+    }
+
+    #[test]
+    fn unparsable_table() {
+        for pathology in [
+            Pathology::UnsupportedFat5Type,
+            Pathology::MisalignedFile,
+            Pathology::DuplicateEntry,
+            Pathology::OversizedFileCount,
+        ] {
+            let archive = SyntheticFarc::new()
+                .with_files(4)
+                .named(true)
+                .pathology(pathology)
+                .build();
+            assert!(matches!(
+                validate(&archive).as_slice(),
+                [FarcProblem::Unparsable(_)]
+            ));
+        }
+        let archive = SyntheticFarc::new()
+            .with_files(4)
+            .pathology(Pathology::TruncatedTable)
+            .build();
+        assert!(matches!(
+            validate(&archive).as_slice(),
+            [FarcProblem::Sir0OutOfBounds { .. }, ..]
+        ));
+    }
+
+    #[test]
+    fn overlapping_entries() {
+        let mut archive = SyntheticFarc::new()
+            .with_hashed_file(1, vec![1; 32])
+            .with_hashed_file(2, vec![2; 32])
+            .build();
+        // the entries are sorted by hash. Move the start of the second file into the first one.
+        let header = read_header(archive.as_slice()).unwrap();
+        let mut parser = FarcParser::from_header(&header);
+        let table = loop {
+            if let ParseStep::Table { offset, .. } = parser.next_from(&archive).unwrap() {
+                break offset as usize;
+            };
+        };
+        let first_offset = read_u32_at(archive.as_slice(), table as u64 + 4).unwrap();
+        set_u32(&mut archive, table + 12 + 4, first_offset + 16);
+        assert!(matches!(
+            validate(&archive).as_slice(),
+            [FarcProblem::EntriesOverlap(1, 2)]
+        ));
+    }
+}